@@ -0,0 +1,104 @@
+use crate::*;
+use near_sdk::borsh::BorshSerialize;
+use near_sdk::json_types::{Base58CryptoHash, U128};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, CryptoHash};
+
+/// Proof that the buyer is in the sale allowlist. The Merkle tree leaves are
+/// `keccak256(borsh(account_id, max_allocation))` and every pair of nodes is
+/// hashed sorted: `keccak256(min(a, b) ++ max(a, b))`.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AllowlistProof {
+    /// Max deposit of the buyer, in the sale payment token.
+    pub max_allocation: U128,
+    pub proof: Vec<Base58CryptoHash>,
+}
+
+/// `ft_on_transfer` msg for allowlisted sales. Otherwise, the msg is the sale id.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DepositMsg {
+    pub sale_id: u32,
+    pub allowlist: Option<AllowlistProof>,
+}
+
+pub(crate) fn allowlist_leaf(account_id: &AccountId, max_allocation: Balance) -> CryptoHash {
+    env::keccak256_array(&(account_id, max_allocation).try_to_vec().unwrap())
+}
+
+pub(crate) fn verify_merkle_proof(
+    root: &CryptoHash,
+    leaf: CryptoHash,
+    proof: &[Base58CryptoHash],
+) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        let sibling: CryptoHash = (*sibling).into();
+        let (left, right) = if node <= sibling { (node, sibling) } else { (sibling, node) };
+        env::keccak256_array(&[left, right].concat())
+    });
+    computed == *root
+}
+
+#[near_bindgen]
+impl KatherineSaleContract {
+    /// Max deposit of an allowlisted buyer, once the proof was verified.
+    pub fn get_buyer_max_allocation(&self, buyer_id: AccountId, sale_id: u32) -> Option<U128> {
+        let sale = self.internal_get_sale(sale_id);
+        sale.allowlist_allocations.get(&buyer_id).map(U128::from)
+    }
+
+    pub fn verify_allowlist_proof(
+        &self,
+        sale_id: u32,
+        buyer_id: AccountId,
+        allowlist: AllowlistProof,
+    ) -> bool {
+        let sale = self.internal_get_sale(sale_id);
+        match &sale.allowlist_root {
+            Some(root) => verify_merkle_proof(
+                root,
+                allowlist_leaf(&buyer_id, allowlist.max_allocation.0),
+                &allowlist.proof,
+            ),
+            None => true,
+        }
+    }
+}
+
+impl Sale {
+    /// For private rounds, the cumulative deposit of the buyer is capped by the
+    /// allocation in the allowlist. The proof is only needed on the first deposit,
+    /// the verified allocation is returned to be stored with the deposit.
+    pub(crate) fn check_allowlisted(
+        &self,
+        buyer_id: &AccountId,
+        allowlist: Option<AllowlistProof>,
+        new_deposit_amount: Balance,
+    ) -> Result<Option<Balance>, String> {
+        let root = match &self.allowlist_root {
+            Some(root) => *root,
+            None => return Ok(None),
+        };
+        let (max_allocation, verified_allocation) = match self.allowlist_allocations.get(buyer_id) {
+            Some(max_allocation) => (max_allocation, None),
+            None => {
+                let allowlist = allowlist
+                    .ok_or_else(|| String::from("Private sale, allowlist proof required."))?;
+                let max_allocation = allowlist.max_allocation.0;
+                if !verify_merkle_proof(
+                    &root,
+                    allowlist_leaf(buyer_id, max_allocation),
+                    &allowlist.proof
+                ) {
+                    return Err(String::from("Invalid allowlist proof."));
+                }
+                (max_allocation, Some(max_allocation))
+            }
+        };
+        if new_deposit_amount > max_allocation {
+            return Err(format!("Max allocation for buyer is {}.", max_allocation));
+        }
+        Ok(verified_allocation)
+    }
+}
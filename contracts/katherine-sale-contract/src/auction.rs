@@ -0,0 +1,106 @@
+use crate::*;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, require};
+
+/// Price discovery of the sale. Prices are expressed as purchase rates, sold
+/// tokens for one payment token, so a falling price is a growing rate. The
+/// `one_payment_token_purchase_rate` of the sale is the floor price.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub enum SaleMode {
+    #[default]
+    FixedPrice,
+    /// The price falls linearly from `start_purchase_rate` at the open date to
+    /// the floor at the close date. Buyers pay the price at the deposit time.
+    DutchAuction { start_purchase_rate: U128 },
+    /// Uniform price. Deposits are accepted at the `max_price_purchase_rate`
+    /// ceiling, and after close everyone pays the clearing price, never under
+    /// the floor. If the demand at the ceiling is over the available tokens,
+    /// the allocation is pro-rata and the overpayments are refunded at withdraw.
+    BatchAuction { max_price_purchase_rate: U128 },
+}
+
+#[near_bindgen]
+impl KatherineSaleContract {
+    /// Sold tokens for one payment token. For batch auctions, this is the
+    /// clearing rate if the sale closed now.
+    pub fn get_current_purchase_rate(&self, sale_id: u32) -> U128 {
+        let sale = self.internal_get_sale(sale_id);
+        U128::from(sale.get_current_purchase_rate())
+    }
+}
+
+impl Sale {
+    pub(crate) fn assert_valid_mode(&self) {
+        let floor_purchase_rate = self.sale_payment_config().one_payment_token_purchase_rate;
+        match &self.mode {
+            SaleMode::FixedPrice => (),
+            SaleMode::DutchAuction { start_purchase_rate: rate }
+            | SaleMode::BatchAuction { max_price_purchase_rate: rate } => {
+                require!(
+                    rate.0 > 0 && rate.0 <= floor_purchase_rate,
+                    "Auction price must be over the floor price."
+                );
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn is_batch_auction(&self) -> bool {
+        matches!(self.mode, SaleMode::BatchAuction { .. })
+    }
+
+    /// Purchase rate to convert the deposits into requested sold tokens.
+    pub(crate) fn get_deposit_purchase_rate(&self) -> u128 {
+        let floor_purchase_rate = self.sale_payment_config().one_payment_token_purchase_rate;
+        match &self.mode {
+            SaleMode::FixedPrice => floor_purchase_rate,
+            SaleMode::DutchAuction { start_purchase_rate } => {
+                let now = get_current_epoch_millis();
+                let duration = self.close_date_timestamp - self.open_date_timestamp;
+                let elapsed = std::cmp::min(
+                    now.saturating_sub(self.open_date_timestamp),
+                    duration
+                );
+                start_purchase_rate.0 + proportional(
+                    floor_purchase_rate - start_purchase_rate.0,
+                    elapsed as u128,
+                    duration as u128
+                )
+            },
+            SaleMode::BatchAuction { max_price_purchase_rate } => max_price_purchase_rate.0,
+        }
+    }
+
+    pub(crate) fn get_current_purchase_rate(&self) -> u128 {
+        match &self.mode {
+            SaleMode::BatchAuction { max_price_purchase_rate } => {
+                let (requested, allocated) = self.get_allocation_ratio();
+                if requested == 0 {
+                    self.sale_payment_config().one_payment_token_purchase_rate
+                } else if requested > self.max_available_sold_token {
+                    max_price_purchase_rate.0
+                } else {
+                    proportional(max_price_purchase_rate.0, allocated, requested)
+                }
+            },
+            _ => self.get_deposit_purchase_rate(),
+        }
+    }
+
+    /// Sold tokens allocated for the `requested` tokens. Batch auctions allocate
+    /// up to the floor price, and oversubscribed sales are capped by the max.
+    pub(crate) fn get_projected_allocation(&self, requested: Balance) -> Balance {
+        let requested = match &self.mode {
+            SaleMode::BatchAuction { max_price_purchase_rate } => proportional(
+                requested,
+                self.sale_payment_config().one_payment_token_purchase_rate,
+                max_price_purchase_rate.0
+            ),
+            _ => requested,
+        };
+        std::cmp::min(requested, self.max_available_sold_token)
+    }
+}
@@ -1,6 +1,6 @@
 use crate::*;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::AccountId;
+use near_sdk::{log, AccountId};
 
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct Buyer {
@@ -32,4 +32,12 @@ impl KatherineSaleContract {
     ) -> Buyer {
         self.buyers.get(buyer_id).unwrap_or(Buyer::new(buyer_id))
     }
+
+    /// Remove the buyer once it is not supporting any sale.
+    pub(crate) fn internal_remove_empty_buyer(&mut self, buyer_id: &AccountId) {
+        if self.internal_get_buyer(buyer_id).is_empty() {
+            self.buyers.remove(buyer_id);
+            log!("GODSPEED: {} is no longer part of Katherine!", buyer_id);
+        }
+    }
 }
@@ -8,4 +8,21 @@ pub const STORAGE_PER_SALE: u128 = NEAR / 100;
 
 pub const TGAS: u64 = 1_000_000_000_000;
 pub const GAS_FOR_FT_TRANSFER: Gas = Gas(47 * TGAS);
-pub const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(11 * TGAS);
\ No newline at end of file
+pub const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(11 * TGAS);
+/// Resolve the sold tokens transfer, and then refund the excess payment of
+/// each payment token with `GAS_FOR_REFUND_EXCESS`.
+pub const GAS_FOR_RESOLVE_OVERSUBSCRIBED: Gas = Gas(17 * TGAS);
+pub const GAS_FOR_REFUND_EXCESS: Gas = Gas(58 * TGAS);
+/// Read the buyer locking positions, and then resolve the tiered deposit.
+pub const GAS_FOR_GET_VOTING_POWER: Gas = Gas(10 * TGAS);
+pub const GAS_FOR_RESOLVE_TIER_DEPOSIT: Gas = Gas(20 * TGAS);
+
+pub const MAX_SALE_TIERS: usize = 10;
+/// Buyers always get at least half of the deposit back on an early exit.
+pub const MAX_EARLY_EXIT_FEE: BasisPoints = 5_000;
+/// Buyers can deposit every payment token, and the refunds send one transfer
+/// for each token in a single call. With 3 tokens, the oversubscribed withdraw
+/// needs up to 250 TGas.
+pub const MAX_PAYMENT_TOKENS: usize = 3;
+/// Key of NEAR in the `payment_tokens` of the sale options.
+pub const NEAR_PAYMENT_TOKEN: &str = "NEAR";
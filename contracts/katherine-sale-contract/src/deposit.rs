@@ -16,12 +16,16 @@ impl FungibleTokenReceiver for KatherineSaleContract {
         amount: U128,
         msg: String,
     ) -> PromiseOrValue<U128> {
-        let sale_id = match msg.parse::<u32>() {
-            Ok(_id) => _id,
-            Err(_) => panic!("Invalid sale id."),
+        let (sale_id, allowlist) = match msg.parse::<u32>() {
+            Ok(_id) => (_id, None),
+            Err(_) => match near_sdk::serde_json::from_str::<DepositMsg>(&msg) {
+                Ok(deposit_msg) => (deposit_msg.sale_id, deposit_msg.allowlist),
+                Err(_) => panic!("Invalid sale id."),
+            },
         };
         let mut sale = self.internal_get_sale(sale_id);
         let amount = amount.0;
+        let token_id = Some(env::predecessor_account_id());
 
         // Deposit of the sold tokens.
         if env::predecessor_account_id() == sale.sold_token_contract_address {
@@ -30,22 +34,28 @@ impl FungibleTokenReceiver for KatherineSaleContract {
                 "DEPOSIT: {} sold tokens deposited from {} to sale {}",
                 amount,
                 &sender_id,
-                &msg
+                sale_id
+            );
+
+        // Deposit of a payment token for a tiered sale. The unused amount is
+        // returned by the callback.
+        } else if sale.has_tiers() && sale.accepts_payment_token(&token_id) {
+            return PromiseOrValue::Promise(
+                self.internal_verify_tier_deposit(sender_id, token_id, amount, &sale)
             );
 
         // Deposit of a payment token.
-        } else if sale.payment_config.payment_token_contract_address.is_some()
-                && sale.payment_config.payment_token_contract_address.as_ref().unwrap() == &env::predecessor_account_id() {
-            self.process_payment_tokens_deposit(&sender_id, amount, &mut sale);
+        } else if sale.accepts_payment_token(&token_id) {
+            self.process_payment_tokens_deposit(&sender_id, token_id, amount, &mut sale, allowlist);
             log!(
                 "DEPOSIT: {} payment tokens deposited from {} to sale {}",
                 amount,
                 &sender_id,
-                &msg
+                sale_id
             );
 
         } else {
-            panic!("Unknown token {} for sale {}", env::predecessor_account_id(), &msg);
+            panic!("Unknown token {} for sale {}", env::predecessor_account_id(), sale_id);
         }
 
         // Return unused amount
@@ -59,26 +69,38 @@ impl KatherineSaleContract {
     pub(crate) fn process_payment_tokens_deposit(
         &mut self,
         buyer_id: &AccountId,
+        token_id: Option<AccountId>,
         amount: Balance,
         sale: &mut Sale,
+        allowlist: Option<AllowlistProof>,
     ) {
-        sale.assert_min_deposit_amount(amount);
-        sale.assert_within_funding_period();
+        let allowlist_allocation = sale
+            .check_deposit(buyer_id, &token_id, amount, allowlist, None)
+            .unwrap_or_else(|err| panic!("{}", err));
+        if let Some(max_allocation) = allowlist_allocation {
+            sale.allowlist_allocations.insert(buyer_id, &max_allocation);
+        }
+        self.internal_register_deposit(buyer_id, token_id, amount, sale);
+    }
 
+    /// Update the sale and buyer with a deposit that passed all the checks.
+    pub(crate) fn internal_register_deposit(
+        &mut self,
+        buyer_id: &AccountId,
+        token_id: Option<AccountId>,
+        amount: Balance,
+        sale: &mut Sale,
+    ) {
         // For the payment token.
-        let new_deposit_amount = sale.get_buyer_deposit(buyer_id) + amount;
-        sale.deposits.insert(buyer_id, &new_deposit_amount);
-        sale.total_payment_token += amount;
+        sale.add_buyer_deposit(buyer_id, &token_id, amount);
+        sale.total_raised += sale.to_sale_value(&token_id, amount);
+        sale.get_payment_config_mut(&token_id).total_payment_token += amount;
 
         // For the sold token.
-        let sold_tokens = sale.from_payment_to_sold_token(amount);
+        let sold_tokens = sale.from_payment_to_sold_token(&token_id, amount);
         let new_claimable_amount = sale.get_buyer_claimable_sold_token(buyer_id) + sold_tokens;
         sale.claimable_sold_token_for_buyers.insert(buyer_id, &new_claimable_amount);
         sale.required_sold_token += sold_tokens;
-        require!(
-            sale.required_sold_token <= sale.max_available_sold_token,
-            "Not enough token for sale."
-        );
 
         // Update Sale and Buyer
         self.sales.replace(sale.id as u64, &sale);
@@ -97,6 +119,7 @@ impl KatherineSaleContract {
             get_current_epoch_millis() < sale.release_date_timestamp,
             "Too late. Sale is over."
         );
+        sale.assert_not_canceled();
         // let amount = kickstarter.less_to_24_decimals(amount);
         // let max_tokens_to_release = self.calculate_max_tokens_to_release(&kickstarter);
         // let min_tokens_to_allow_support = max_tokens_to_release
@@ -108,3 +131,59 @@ impl KatherineSaleContract {
         self.sales.replace(sale.id as u64, &sale);
     }
 }
+
+impl Sale {
+    /// Checks of a payment deposit, shared by the regular and the tier deposits.
+    /// Errors are returned so the tier callback can refund the buyer instead of
+    /// panicking. Returns the allocation of a newly allowlisted buyer.
+    pub(crate) fn check_deposit(
+        &self,
+        buyer_id: &AccountId,
+        token_id: &Option<AccountId>,
+        amount: Balance,
+        allowlist: Option<AllowlistProof>,
+        tier: Option<&SaleTier>,
+    ) -> Result<Option<Balance>, String> {
+        let min_deposit_amount = self.get_payment_config(token_id).min_deposit_amount;
+        if amount < min_deposit_amount {
+            return Err(format!("minimum deposit amount is {}", min_deposit_amount));
+        }
+
+        match tier {
+            Some(tier) => {
+                let now = get_current_epoch_millis();
+                if now >= self.close_date_timestamp
+                        || now < self.open_date_timestamp.saturating_sub(tier.early_access.0)
+                        || !self.accepts_deposits() {
+                    return Err(format!("Not within the funding period for {} tier.", tier.name));
+                }
+            },
+            None => {
+                if !self.is_within_funding_period() {
+                    return Err(String::from("Not within the funding period."));
+                }
+            }
+        }
+
+        // Deposits in every payment token count towards the buyer allocation.
+        let new_deposit_value = self.get_buyer_deposit_value(buyer_id)
+            + self.to_sale_value(token_id, amount);
+        let allowlist_allocation = self.check_allowlisted(buyer_id, allowlist, new_deposit_value)?;
+        if let Some(tier) = tier {
+            if new_deposit_value > tier.max_allocation.0 {
+                return Err(format!(
+                    "Max allocation for {} tier is {}.",
+                    tier.name,
+                    tier.max_allocation.0
+                ));
+            }
+        }
+
+        let required_sold_token = self.required_sold_token
+            + self.from_payment_to_sold_token(token_id, amount);
+        if !self.accepts_oversubscription() && required_sold_token > self.max_available_sold_token {
+            return Err(String::from("Not enough token for sale."));
+        }
+        Ok(allowlist_allocation)
+    }
+}
@@ -0,0 +1,145 @@
+use crate::*;
+use near_sdk::json_types::U128;
+use near_sdk::{env, log, near_bindgen, require, Promise};
+
+/// Sale reservoirs released by an early exit, to recover them if the refund fails.
+struct EarlyExit {
+    amount: Balance,
+    fee: Balance,
+    sold_tokens: Balance,
+}
+
+#[near_bindgen]
+impl KatherineSaleContract {
+    /// Buyers of a sale with `allow_early_exit` can withdraw part or all of their
+    /// deposit in a payment token before the close date. The `early_exit_fee` is
+    /// kept as a sale fee, and the claimable sold tokens are reduced in proportion.
+    /// Only callable during `stage 1`.
+    pub fn withdraw_deposit(
+        &mut self,
+        sale_id: u32,
+        payment_token_contract_address: Option<AccountId>,
+        amount: U128
+    ) -> Promise {
+        let mut sale = self.internal_get_sale(sale_id);
+        let buyer_id = env::predecessor_account_id();
+        let token_id = payment_token_contract_address;
+        let amount = amount.0;
+        require!(sale.allow_early_exit, "Early exit is not allowed.");
+        sale.assert_within_funding_period();
+
+        let deposit = sale.get_buyer_deposit(&buyer_id, &token_id);
+        require!(amount > 0 && amount <= deposit, "Not enough deposit.");
+        let exit = sale.internal_early_exit(&buyer_id, &token_id, amount);
+        let is_full_exit = sale.get_buyer_deposits(&buyer_id).is_empty();
+        if is_full_exit {
+            let mut buyer = self.internal_get_buyer(&buyer_id);
+            buyer.supporting_sales.remove(&sale.id);
+            self.buyers.insert(&buyer_id, &buyer);
+        }
+        self.sales.replace(sale.id as u64, &sale);
+
+        let refund = exit.amount - exit.fee;
+        log!(
+            "EXIT: {} payment tokens withdrawn by {} from sale {}, exit fee {}",
+            exit.amount, &buyer_id, sale_id, exit.fee
+        );
+        if let Some(token_id) = token_id {
+            ext_ft::ext(token_id.clone())
+                .with_static_gas(GAS_FOR_FT_TRANSFER)
+                .with_attached_deposit(1)
+                .ft_transfer(buyer_id.clone(), U128::from(refund), None).then(
+                    Self::ext(env::current_account_id())
+                        .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
+                        .buyer_withdraw_deposit_resolve(
+                            &buyer_id,
+                            &token_id,
+                            U128::from(exit.amount),
+                            U128::from(exit.fee),
+                            U128::from(exit.sold_tokens),
+                            sale_id
+                        )
+                )
+        } else {
+            if is_full_exit {
+                self.internal_remove_empty_buyer(&buyer_id);
+            }
+            Promise::new(buyer_id).transfer(refund)
+        }
+    }
+
+    #[private]
+    pub fn buyer_withdraw_deposit_resolve(
+        &mut self,
+        buyer_id: &AccountId,
+        token_id: &AccountId,
+        amount: U128,
+        fee: U128,
+        sold_tokens: U128,
+        sale_id: u32
+    ) {
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(_) => {
+                self.internal_remove_empty_buyer(buyer_id);
+                log!(
+                    "WITHDRAW: {} tokens of payment-token {} transferred back to {}",
+                    amount.0 - fee.0, token_id, buyer_id
+                );
+            },
+            PromiseResult::Failed => {
+                let mut buyer = self.internal_get_buyer(buyer_id);
+                buyer.supporting_sales.insert(&sale_id);
+                self.buyers.insert(buyer_id, &buyer);
+
+                let mut sale = self.internal_get_sale(sale_id);
+                // Important: Recover the deposit, claimable tokens and fee from user.
+                let token_id = Some(token_id.clone());
+                sale.add_buyer_deposit(buyer_id, &token_id, amount.0);
+                let claimable = sale.get_buyer_claimable_sold_token(buyer_id) + sold_tokens.0;
+                sale.claimable_sold_token_for_buyers.insert(buyer_id, &claimable);
+                sale.required_sold_token += sold_tokens.0;
+                sale.total_raised += sale.to_sale_value(&token_id, amount.0);
+                let config = sale.get_payment_config_mut(&token_id);
+                config.total_payment_token += amount.0;
+                config.total_fees -= fee.0;
+                self.sales.replace(sale.id as u64, &sale);
+                log!(
+                    "FAILED: {} tokens not transferred. Recovering sale {} state.",
+                    amount.0, sale_id
+                );
+            }
+        };
+    }
+}
+
+impl Sale {
+    /// Remove `amount` out of the buyer deposit in a payment token, with its share
+    /// of the claimable sold tokens, by value over all the buyer deposits. The exit
+    /// fee is moved to the `total_fees` of the payment token.
+    fn internal_early_exit(
+        &mut self,
+        buyer_id: &AccountId,
+        token_id: &Option<AccountId>,
+        amount: Balance,
+    ) -> EarlyExit {
+        let claimable = self.get_buyer_claimable_sold_token(buyer_id);
+        let value = self.to_sale_value(token_id, amount);
+        let sold_tokens = proportional(claimable, value, self.get_buyer_deposit_value(buyer_id));
+        let fee = proportional(amount, self.early_exit_fee as u128, BASIS_POINT as u128);
+
+        self.sub_buyer_deposit(buyer_id, token_id, amount);
+        if self.get_buyer_deposits(buyer_id).is_empty() {
+            self.claimable_sold_token_for_buyers.remove(buyer_id);
+        } else {
+            self.claimable_sold_token_for_buyers.insert(buyer_id, &(claimable - sold_tokens));
+        }
+        self.required_sold_token -= sold_tokens;
+        self.total_raised = self.total_raised.saturating_sub(value);
+        let config = self.get_payment_config_mut(token_id);
+        config.total_payment_token -= amount;
+        config.total_fees += fee;
+
+        EarlyExit { amount, fee, sold_tokens }
+    }
+}
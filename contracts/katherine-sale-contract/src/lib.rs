@@ -2,26 +2,38 @@ use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::{UnorderedMap, UnorderedSet, Vector};
 use near_sdk::json_types::{U128, U64};
 use near_sdk::{
-    require, env, assert_one_yocto, near_bindgen, AccountId, Balance,
+    require, env, assert_one_yocto, log, near_bindgen, AccountId, Balance,
     PanicOnDefault, PromiseResult, Promise
 };
 use std::convert::TryInto;
 
+use crate::allowlist::*;
+use crate::auction::*;
 use crate::buyer::*;
 use crate::constants::*;
+use crate::payment::*;
 use crate::sale::*;
+use crate::tiers::*;
 use crate::types::*;
 use crate::utils::*;
 use crate::interface::*;
+use crate::vesting::*;
 
+mod allowlist;
+mod auction;
 mod buyer;
 pub mod constants;
 mod deposit;
+mod early_exit;
 mod interface;
 mod internal;
+mod oversubscription;
+mod payment;
 mod sale;
+mod tiers;
 mod types;
 mod utils;
+mod vesting;
 mod withdraw;
 
 /// There are 5 possible stages of a Sale depending on the date:
@@ -58,6 +70,8 @@ pub struct KatherineSaleContract {
     /// % of the total sale for the owner_id.
     pub sale_fee: BasisPoints,
     pub treasury_id: AccountId,
+    /// Used to verify the locked voting power of the buyers in tiered sales.
+    pub staking_position_contract_address: Option<AccountId>,
 }
 
 #[near_bindgen]
@@ -85,6 +99,7 @@ impl KatherineSaleContract {
             payment_token_unit: payment_token_unit.0,
             treasury_id,
             sale_fee,
+            staking_position_contract_address: None,
         }
     }
 
@@ -106,6 +121,13 @@ impl KatherineSaleContract {
         self.treasury_id = new_value;
     }
 
+    #[payable]
+    pub fn update_staking_position_contract_address(&mut self, new_value: AccountId) {
+        assert_one_yocto();
+        self.assert_only_owner();
+        self.staking_position_contract_address = Some(new_value);
+    }
+
     /// This update will only affects the next sales, not currents.
     #[payable]
     pub fn update_min_deposit_amount_in_near(&mut self, new_value: U128) {
@@ -151,6 +173,8 @@ impl KatherineSaleContract {
     // * Sales operation *
     // *******************
 
+    /// Sellers submit their sales, that are pending until the owner approves
+    /// them with `approve_sale`. Sales created by the owner are approved.
     #[payable]
     pub fn create_sale(
         &mut self,
@@ -163,64 +187,141 @@ impl KatherineSaleContract {
         open_date_timestamp: U64,
         close_date_timestamp: U64,
         release_date_timestamp: U64,
+        options: Option<SaleOptions>,
     ) -> u32 {
-        self.assert_only_owner();
+        let options = options.unwrap_or_default();
+        options.assert_valid();
+        require!(
+            options.tiers.is_empty() || self.staking_position_contract_address.is_some(),
+            "Staking position contract is not set."
+        );
         self.assert_unique_slug(&slug);
         Sale::assert_storage_is_covered();
         let id = self.sales.len() as u32;
 
-        let (
-            min_deposit_amount,
-            payment_token_contract_address,
-            payment_token_unit
-        ) = if is_in_near {
-            (self.min_deposit_amount_in_near, None, NEAR)
+        let payment_config = if is_in_near {
+            PaymentConfig::new(
+                None,
+                one_payment_token_purchase_rate.0,
+                self.min_deposit_amount_in_near,
+                NEAR
+            )
         } else {
-            (
-                self.min_deposit_amount_in_payment_token,
+            PaymentConfig::new(
                 Some(self.payment_token_contract_address.clone()),
+                one_payment_token_purchase_rate.0,
+                self.min_deposit_amount_in_payment_token,
                 self.payment_token_unit
             )
         };
 
-        let sale = Sale::new(
+        // The account that submits the sale is the seller.
+        let mut sale = Sale::new(
             id,
             slug,
             sold_token_contract_address,
-            one_payment_token_purchase_rate.0,
+            env::predecessor_account_id(),
             max_available_sold_token.0,
             open_date_timestamp.0,
             close_date_timestamp.0,
             release_date_timestamp.0,
-            min_deposit_amount,
-            payment_token_contract_address,
-            payment_token_unit,
+            options,
+            payment_config,
             self.sale_fee,
         );
 
         sale.assert_input_timestamps();
+        sale.assert_valid_mode();
+        sale.approved = sale.seller_id == self.owner_id;
         self.sales.push(&sale);
         self.sale_id_by_slug
             .insert(&sale.slug, &sale.id);
-        self.active_sales.insert(&sale.id);
+        if sale.approved {
+            self.active_sales.insert(&sale.id);
+        }
         sale.id.into()
     }
 
+    /// The owner approves a sale submitted by a seller, before the close date.
+    /// To reject the sale, use `cancel_sale`, the slug of a rejected sale is released.
+    #[payable]
+    pub fn approve_sale(&mut self, sale_id: u32) {
+        assert_one_yocto();
+        self.assert_only_owner();
+        let mut sale = self.internal_get_sale(sale_id);
+        sale.assert_not_canceled();
+        require!(!sale.approved, "Sale already approved.");
+        require!(
+            get_current_epoch_millis() < sale.close_date_timestamp,
+            "Only before close period."
+        );
+
+        log!("APPROVE: sale {} approved.", sale_id);
+        sale.approved = true;
+        self.sales.replace(sale.id as u64, &sale);
+        self.active_sales.insert(&sale.id);
+    }
+
+    /// The owner can cancel a sale until the release date, if the payments were
+    /// not collected. Deposits are blocked, buyers can withdraw their deposits
+    /// right away, and the `sold_tokens_for_buyers` are returned to the `seller_id`.
+    #[payable]
+    pub fn cancel_sale(&mut self, sale_id: u32, reason: String) -> Option<Promise> {
+        assert_one_yocto();
+        self.assert_only_owner();
+        let mut sale = self.internal_get_sale(sale_id);
+        sale.assert_not_canceled();
+        require!(
+            get_current_epoch_millis() < sale.release_date_timestamp,
+            "Only before release period."
+        );
+        require!(!sale.payments_collected, "Payments already collected.");
+        require!(
+            sale.total_requested_sold_token == 0,
+            "Sale allocation already settled."
+        );
+
+        log!("CANCEL: sale {} canceled. {}", sale_id, &reason);
+        sale.cancel_reason = Some(reason);
+        self.remove_sale_from_active_list(sale_id);
+        if !sale.approved {
+            // Unapproved sales do not keep their slug reserved.
+            self.sale_id_by_slug.remove(&sale.slug);
+        }
+        if sale.sold_tokens_for_buyers > 0 {
+            let excess = sale.sold_tokens_for_buyers;
+            Some(self.seller_withdraw_excess_sold_tokens(excess, &mut sale))
+        } else {
+            self.sales.replace(sale.id as u64, &sale);
+            None
+        }
+    }
+
     // ***********************
     // * Payments using NEAR *
     // ***********************
 
     /// Buyers can purchase tokens only within the funding period.
     /// Same for buyers ft payment token deposit [deposit.rs]
-    /// Only callable during `stage 1`.
+    /// For private sales, the `allowlist` proof is required on the first deposit.
+    /// For tiered sales, the deposit is completed after verifying the buyer voting power.
+    /// Only callable during `stage 1`, or the tier early access.
     #[payable]
-    pub fn purchase_token_with_near(&mut self, sale_id: u32) {
+    pub fn purchase_token_with_near(
+        &mut self,
+        sale_id: u32,
+        allowlist: Option<AllowlistProof>
+    ) -> Option<Promise> {
         let mut sale = self.internal_get_sale(sale_id);
         let amount = env::attached_deposit();
         let buyer_id = env::predecessor_account_id();
 
-        require!(sale.is_near_accepted());
-        self.process_payment_tokens_deposit(&buyer_id, amount, &mut sale);
+        require!(sale.accepts_payment_token(&None), "NEAR is not accepted.");
+        if sale.has_tiers() {
+            return Some(self.internal_verify_tier_deposit(buyer_id, None, amount, &sale));
+        }
+        self.process_payment_tokens_deposit(&buyer_id, None, amount, &mut sale, allowlist);
+        None
     }
 
     // *******************
@@ -228,37 +329,58 @@ impl KatherineSaleContract {
     // *******************
 
     /// When a buyer withdraw form a sale ALL the claimable tokens are send to
-    /// the buyer, and the deposit is removed from `sale.deposits`.
-    /// Only callable during `stage 3`.
+    /// the buyer, and the deposits are removed from `sale.deposits`.
+    /// For covered sales with vesting, use `claim_vested`.
+    /// Only callable during `stage 3`, or after close if the sale failed, or
+    /// after the sale is canceled.
     pub fn withdraw_tokens(&mut self, sale_id: u32) -> Promise {
         let mut sale = self.internal_get_sale(sale_id);
-        sale.assert_after_release_period();
-
         let buyer_id = env::predecessor_account_id();
+        // Retry a failed oversubscription refund.
+        if let Some(refunds) = sale.excess_refunds.remove(&buyer_id) {
+            return self.internal_buyer_refund_excess(buyer_id, refunds, &mut sale);
+        }
+        let is_refundable = sale.is_refundable();
+        if !is_refundable {
+            sale.assert_after_release_period();
+            require!(
+                sale.vesting.is_none() || !sale.are_sold_tokens_covered(),
+                "Sale has vesting, use claim_vested."
+            );
+        }
+        sale.settle_oversubscription();
+
         // Important: Claimable tokens and buyer deposit are removed.
         let claimable = sale
             .claimable_sold_token_for_buyers
             .remove(&buyer_id)
             .expect("No claimable tokens.");
-        let deposit = sale.deposits.remove(&buyer_id).expect("No deposit.");
-        require!(claimable > 0 && deposit > 0);
+        let deposits = sale.remove_buyer_deposits(&buyer_id);
+        require!(claimable > 0 && !deposits.is_empty(), "No deposit.");
 
         let mut buyer = self.internal_get_buyer(&buyer_id);
         buyer.supporting_sales.remove(&sale.id);
         self.buyers.insert(&buyer_id, &buyer);
 
-        if sale.are_sold_tokens_covered() {
+        if !is_refundable && sale.are_sold_tokens_covered() && sale.total_requested_sold_token > 0 {
+            self.internal_buyer_withdraw_oversubscribed(
+                buyer_id,
+                claimable,
+                deposits,
+                &mut sale
+            )
+        } else if !is_refundable && sale.are_sold_tokens_covered() {
             self.internal_buyer_withdraw_sold_tokens(
                 buyer_id,
                 claimable,
-                deposit,
+                deposits,
                 &mut sale
             )
         } else {
             self.internal_buyer_withdraw_payment_token(
                 buyer_id,
                 claimable,
-                deposit,
+                deposits,
                 &mut sale
             )
         }
@@ -269,36 +391,46 @@ impl KatherineSaleContract {
     // *******************
 
     /// Only callable during `stage 2 and 3`, only if sold tokens are covered.
-    /// Payments are being send to the `treasury_id`.
+    /// Payments, net of the `sale_fee`, are being send to the sale `payout_id`.
     pub fn collect_payments(&mut self, sale_id: u32) -> Promise {
-        self.assert_only_owner();
         let mut sale = self.internal_get_sale(sale_id);
+        sale.assert_only_seller();
         sale.assert_after_close_period();
+        sale.assert_not_failed();
+        sale.assert_not_canceled();
+        sale.settle_oversubscription();
         self.remove_sale_from_active_list(sale_id);
-        require!(sale.total_payment_token > 0, "Nothing to collect.");
+        require!(sale.has_payments(), "Nothing to collect.");
         require!(sale.are_sold_tokens_covered(), "Deposit all the sold tokens.");
 
         self.internal_collect_payments(&mut sale)
     }
 
-    /// Only callable after the owner raw `collect_payments`.
+    /// Only callable after the seller raw `collect_payments`.
     /// Fees are being send to the `treasury_id`.
     pub fn collect_fees(&mut self, sale_id: u32) -> Promise {
         self.assert_only_owner();
         let mut sale = self.internal_get_sale(sale_id);
         sale.assert_after_close_period();
-        require!(sale.total_fees > 0, "Nothing to collect.");
+        require!(sale.has_fees(), "Nothing to collect.");
 
         self.internal_collect_fees(&mut sale)
     }
 
+    /// The excess of sold tokens is returned to the sale `seller_id`.
     pub fn withdraw_excess_sold_tokens(&mut self, sale_id: u32) -> Promise {
-        self.assert_only_owner();
         let mut sale = self.internal_get_sale(sale_id);
-        sale.assert_after_close_period();
+        sale.assert_only_seller();
+        if !sale.is_canceled() {
+            sale.assert_after_close_period();
+        }
+        sale.settle_oversubscription();
         self.remove_sale_from_active_list(sale_id);
         
-        let excess = if sale.are_sold_tokens_covered() {
+        let excess = if sale.is_refundable() {
+            // Nothing is delivered to the buyers of a failed or canceled sale.
+            sale.sold_tokens_for_buyers
+        } else if sale.are_sold_tokens_covered() {
             // Check if sale has more tokens than what it needs to cover deposits.
             // Return excess.
             sale.sold_tokens_for_buyers - sale.required_sold_token
@@ -318,9 +450,10 @@ impl KatherineSaleContract {
     // * View *
     // ********
 
-    pub fn get_sale_fee(&self, sale_id: u32) -> U128 {
+    /// Fees of the sale in a payment token. If None, the fees in NEAR.
+    pub fn get_sale_fee(&self, sale_id: u32, payment_token_contract_address: Option<AccountId>) -> U128 {
         let sale = self.internal_get_sale(sale_id);
-        U128::from(sale.total_fees)
+        U128::from(sale.get_payment_config(&payment_token_contract_address).total_fees)
     }
 
     pub fn get_active_sales(
@@ -409,7 +542,7 @@ impl KatherineSaleContract {
 
     pub fn get_number_of_buyers_for_sale(&self, sale_id: u32) -> u32 {
         let sale = self.internal_get_sale(sale_id);
-        sale.deposits.len().try_into().unwrap()
+        sale.claimable_sold_token_for_buyers.len().try_into().unwrap()
     }
 
     pub fn get_buyer_claimable_sold_token(
@@ -421,9 +554,10 @@ impl KatherineSaleContract {
         U128::from(sale.get_buyer_claimable_sold_token(&buyer_id))
     }
 
+    /// Deposits of the buyer in all the payment tokens, valued in the sale payment token.
     pub fn get_buyer_deposit(&self, buyer_id: AccountId, sale_id: u32) -> U128 {
         let sale = self.internal_get_sale(sale_id);
-        U128::from(sale.get_buyer_deposit(&buyer_id))
+        U128::from(sale.get_buyer_deposit_value(&buyer_id))
     }
 }
 
@@ -0,0 +1,286 @@
+use crate::*;
+use near_sdk::json_types::U128;
+use near_sdk::{env, log, near_bindgen, Promise};
+
+#[near_bindgen]
+impl KatherineSaleContract {
+    #[private]
+    pub fn buyer_withdraw_oversubscribed_resolve(
+        &mut self,
+        buyer_id: &AccountId,
+        claimable: U128,
+        deposits: Vec<PaymentTokenAmount>,
+        allocation: U128,
+        refunds: Vec<PaymentTokenAmount>,
+        sale_id: u32
+    ) -> Option<Promise> {
+        let allocation = allocation.0;
+        let mut sale = self.internal_get_sale(sale_id);
+
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(_) => {
+                log!(
+                    "WITHDRAW: {} tokens of sold-token {} transferred to {}",
+                    allocation, sale.get_sold_token(), buyer_id
+                );
+                if refunds.iter().any(|refund| refund.amount.0 > 0) {
+                    Some(self.internal_buyer_refund_excess(buyer_id.clone(), refunds, &mut sale))
+                } else {
+                    self.internal_remove_empty_buyer(buyer_id);
+                    None
+                }
+            },
+            PromiseResult::Failed => {
+                let mut buyer = self.internal_get_buyer(buyer_id);
+                buyer.supporting_sales.insert(&sale_id);
+                self.buyers.insert(buyer_id, &buyer);
+
+                // Important: Recover the requested tokens and deposits from user.
+                sale.restore_buyer_deposits(buyer_id, &deposits);
+                sale.claimable_sold_token_for_buyers.insert(buyer_id, &claimable.0);
+                sale.sold_tokens_for_buyers += allocation;
+                sale.required_sold_token += allocation;
+                for refund in refunds.iter() {
+                    sale.get_payment_config_mut(&refund.payment_token_contract_address)
+                        .excess_payment_token += refund.amount.0;
+                }
+                self.sales.replace(sale.id as u64, &sale);
+                log!(
+                    "FAILED: {} tokens not transferred. Recovering sale {} state.",
+                    allocation, sale_id
+                );
+                None
+            }
+        }
+    }
+
+    #[private]
+    pub fn buyer_refund_excess_resolve(
+        &mut self,
+        buyer_id: &AccountId,
+        token_id: &AccountId,
+        refund: U128,
+        sale_id: u32
+    ) {
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(_) => {
+                self.internal_remove_empty_buyer(buyer_id);
+                log!(
+                    "WITHDRAW: {} tokens of payment-token {} refunded to {}",
+                    refund.0, token_id, buyer_id
+                );
+            },
+            PromiseResult::Failed => {
+                let mut buyer = self.internal_get_buyer(buyer_id);
+                buyer.supporting_sales.insert(&sale_id);
+                self.buyers.insert(buyer_id, &buyer);
+
+                let mut sale = self.internal_get_sale(sale_id);
+                let mut refunds = sale.excess_refunds.get(buyer_id).unwrap_or_default();
+                refunds.push(PaymentTokenAmount {
+                    payment_token_contract_address: Some(token_id.clone()),
+                    amount: refund,
+                });
+                sale.excess_refunds.insert(buyer_id, &refunds);
+                self.sales.replace(sale.id as u64, &sale);
+                log!(
+                    "FAILED: {} tokens not refunded. Retry with withdraw_tokens for sale {}.",
+                    refund.0, sale_id
+                );
+            }
+        };
+    }
+
+    /// Sold tokens allocated to the buyer, and payment tokens to be refunded.
+    pub fn get_buyer_allocation(
+        &self,
+        buyer_id: AccountId,
+        sale_id: u32
+    ) -> (U128, Vec<PaymentTokenAmount>) {
+        let sale = self.internal_get_sale(sale_id);
+        let claimable = sale.get_buyer_claimable_sold_token(&buyer_id);
+        let (requested, allocated) = sale.get_allocation_ratio();
+        if requested != allocated && !sale.is_refundable() {
+            (
+                U128::from(proportional(claimable, allocated, requested)),
+                sale.get_excess_payments(&sale.get_buyer_deposits(&buyer_id), requested),
+            )
+        } else {
+            (U128::from(claimable), Vec::new())
+        }
+    }
+}
+
+impl Sale {
+    /// Oversubscribed sales and batch auctions accept deposits without limit.
+    #[inline]
+    pub(crate) fn accepts_oversubscription(&self) -> bool {
+        self.oversubscribed || self.is_batch_auction()
+    }
+
+    /// Sold tokens needed to cover the buyers, capped for oversubscribed sales.
+    pub(crate) fn get_required_allocation(&self) -> Balance {
+        if self.total_requested_sold_token > 0 || !self.accepts_oversubscription() {
+            self.required_sold_token
+        } else {
+            self.get_projected_allocation(self.required_sold_token)
+        }
+    }
+
+    /// Requested and allocated sold tokens for the pro-rata allocation.
+    pub(crate) fn get_allocation_ratio(&self) -> (Balance, Balance) {
+        let requested = if self.total_requested_sold_token > 0 {
+            self.total_requested_sold_token
+        } else if self.accepts_oversubscription() {
+            self.required_sold_token
+        } else {
+            return (0, 0);
+        };
+        (requested, self.get_projected_allocation(requested))
+    }
+
+    /// Payment tokens to refund from `amount`, if more tokens were requested than available.
+    pub(crate) fn get_excess_payment(&self, amount: Balance, requested: Balance) -> Balance {
+        if requested > self.max_available_sold_token {
+            proportional(amount, requested - self.max_available_sold_token, requested)
+        } else {
+            0
+        }
+    }
+
+    /// Payment tokens to refund from each deposit, if more tokens were requested than available.
+    pub(crate) fn get_excess_payments(
+        &self,
+        deposits: &[PaymentTokenAmount],
+        requested: Balance
+    ) -> Vec<PaymentTokenAmount> {
+        deposits
+            .iter()
+            .map(|deposit| PaymentTokenAmount {
+                payment_token_contract_address: deposit.payment_token_contract_address.clone(),
+                amount: U128::from(self.get_excess_payment(deposit.amount.0, requested)),
+            })
+            .collect()
+    }
+
+    /// After close, fix the pro-rata allocation of a covered oversubscribed sale
+    /// or batch auction. Required sold tokens are set to the allocation and the
+    /// payment tokens to be refunded are kept apart from the seller payments.
+    /// Uncovered sales are fully refunded.
+    pub(crate) fn settle_oversubscription(&mut self) {
+        if !self.accepts_oversubscription()
+            || get_current_epoch_millis() <= self.close_date_timestamp
+            || self.is_refundable()
+            || self.total_requested_sold_token > 0
+            || !self.are_sold_tokens_covered()
+        {
+            return;
+        }
+        let requested = self.required_sold_token;
+        let allocated = self.get_projected_allocation(requested);
+        if requested == allocated {
+            return;
+        }
+        // The refunds are kept for each payment token.
+        let excesses: Vec<(Option<AccountId>, Balance)> = self.payment_configs
+            .iter()
+            .map(|(token_id, config)| {
+                (token_id.clone(), self.get_excess_payment(config.total_payment_token, requested))
+            })
+            .collect();
+        for (token_id, excess) in excesses {
+            let config = self.get_payment_config_mut(&token_id);
+            config.total_payment_token -= excess;
+            config.excess_payment_token = excess;
+        }
+        self.total_requested_sold_token = requested;
+        self.required_sold_token = allocated;
+    }
+}
+
+impl KatherineSaleContract {
+    /// Deliver the allocated sold tokens, then refund the unused payment tokens.
+    pub(crate) fn internal_buyer_withdraw_oversubscribed(
+        &mut self,
+        buyer_id: AccountId,
+        claimable: u128,
+        deposits: Vec<PaymentTokenAmount>,
+        sale: &mut Sale
+    ) -> Promise {
+        let requested = sale.total_requested_sold_token;
+        let allocation = proportional(
+            claimable,
+            sale.get_projected_allocation(requested),
+            requested
+        );
+        let refunds = sale.get_excess_payments(&deposits, requested);
+
+        sale.sold_tokens_for_buyers -= allocation;
+        sale.required_sold_token -= allocation;
+        for refund in refunds.iter() {
+            sale.get_payment_config_mut(&refund.payment_token_contract_address)
+                .excess_payment_token -= refund.amount.0;
+        }
+        self.sales.replace(sale.id as u64, sale);
+
+        let refund_count = refunds.iter().filter(|refund| refund.amount.0 > 0).count();
+        ext_ft::ext(sale.get_sold_token())
+            .with_static_gas(GAS_FOR_FT_TRANSFER)
+            .with_attached_deposit(1)
+            .ft_transfer(buyer_id.clone(), U128::from(allocation), None).then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(
+                        GAS_FOR_RESOLVE_OVERSUBSCRIBED + GAS_FOR_REFUND_EXCESS * refund_count as u64
+                    )
+                    .buyer_withdraw_oversubscribed_resolve(
+                        &buyer_id,
+                        U128::from(claimable),
+                        deposits,
+                        U128::from(allocation),
+                        refunds,
+                        sale.id
+                    )
+            )
+    }
+
+    /// Refund the unused payment tokens, one transfer for each payment token.
+    pub(crate) fn internal_buyer_refund_excess(
+        &mut self,
+        buyer_id: AccountId,
+        refunds: Vec<PaymentTokenAmount>,
+        sale: &mut Sale
+    ) -> Promise {
+        self.sales.replace(sale.id as u64, sale);
+        let mut buyer = self.internal_get_buyer(&buyer_id);
+        buyer.supporting_sales.remove(&sale.id);
+        self.buyers.insert(&buyer_id, &buyer);
+
+        refunds
+            .into_iter()
+            .filter(|refund| refund.amount.0 > 0)
+            .map(|refund| match refund.payment_token_contract_address {
+                Some(token_id) => ext_ft::ext(token_id.clone())
+                    .with_static_gas(GAS_FOR_FT_TRANSFER)
+                    .with_attached_deposit(1)
+                    .ft_transfer(buyer_id.clone(), refund.amount, None).then(
+                        Self::ext(env::current_account_id())
+                            .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
+                            .buyer_refund_excess_resolve(
+                                &buyer_id,
+                                &token_id,
+                                refund.amount,
+                                sale.id
+                            )
+                    ),
+                None => {
+                    log!("WITHDRAW: {} NEAR refunded to {}", refund.amount.0, buyer_id);
+                    self.internal_remove_empty_buyer(&buyer_id);
+                    Promise::new(buyer_id.clone()).transfer(refund.amount.0)
+                }
+            })
+            .reduce(|promise, next| promise.and(next))
+            .expect("No refunds.")
+    }
+}
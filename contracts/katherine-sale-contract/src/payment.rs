@@ -0,0 +1,253 @@
+use crate::*;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, require};
+use std::collections::BTreeMap;
+
+/// Config and balances of a payment token accepted by the sale.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct PaymentConfig {
+    pub min_deposit_amount: Balance,
+    // If None, the payment token is in NEAR.
+    pub payment_token_contract_address: Option<AccountId>,
+    pub payment_token_unit: u128,
+    /// How many sold tokens can be purchase using one payment token, at the floor price.
+    pub one_payment_token_purchase_rate: u128,
+
+    /// Payments for the seller. The sum of the buyer deposits in this token.
+    pub total_payment_token: Balance,
+    /// Payment tokens kept for the oversubscription refunds.
+    pub excess_payment_token: Balance,
+    pub total_fees: Balance,
+}
+
+/// Additional payment token for a sale, set when the sale is created. The
+/// `payment_tokens` of the sale options are keyed by the token contract, or
+/// `NEAR_PAYMENT_TOKEN` for NEAR.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AcceptedPaymentToken {
+    pub one_payment_token_purchase_rate: U128,
+    pub min_deposit_amount: U128,
+    pub payment_token_unit: U128,
+}
+
+/// Deposit of a buyer in one payment token.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PaymentTokenAmount {
+    /// If None, the payment token is in NEAR.
+    pub payment_token_contract_address: Option<AccountId>,
+    pub amount: U128,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PaymentConfigJSON {
+    pub payment_token_contract_address: Option<AccountId>,
+    pub one_payment_token_purchase_rate: U128,
+    pub min_deposit_amount: U128,
+    pub payment_token_unit: U128,
+    pub total_payment_token: U128,
+    pub excess_payment_token: U128,
+    pub total_fees: U128,
+}
+
+impl PaymentConfig {
+    pub(crate) fn new(
+        payment_token_contract_address: Option<AccountId>,
+        one_payment_token_purchase_rate: u128,
+        min_deposit_amount: Balance,
+        payment_token_unit: u128,
+    ) -> Self {
+        require!(
+            one_payment_token_purchase_rate > 0 && payment_token_unit > 0,
+            "Invalid purchase rate."
+        );
+        Self {
+            min_deposit_amount,
+            payment_token_contract_address,
+            payment_token_unit,
+            one_payment_token_purchase_rate,
+            total_payment_token: 0,
+            excess_payment_token: 0,
+            total_fees: 0,
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> PaymentConfigJSON {
+        PaymentConfigJSON {
+            payment_token_contract_address: self.payment_token_contract_address.clone(),
+            one_payment_token_purchase_rate: U128::from(self.one_payment_token_purchase_rate),
+            min_deposit_amount: U128::from(self.min_deposit_amount),
+            payment_token_unit: U128::from(self.payment_token_unit),
+            total_payment_token: U128::from(self.total_payment_token),
+            excess_payment_token: U128::from(self.excess_payment_token),
+            total_fees: U128::from(self.total_fees),
+        }
+    }
+}
+
+impl PaymentConfig {
+    pub(crate) fn from_accepted_token(key: String, token: AcceptedPaymentToken) -> Self {
+        let payment_token_contract_address = if key == NEAR_PAYMENT_TOKEN {
+            None
+        } else {
+            Some(AccountId::try_from(key).expect("Invalid payment token."))
+        };
+        PaymentConfig::new(
+            payment_token_contract_address,
+            token.one_payment_token_purchase_rate.0,
+            token.min_deposit_amount.0,
+            token.payment_token_unit.0,
+        )
+    }
+}
+
+#[near_bindgen]
+impl KatherineSaleContract {
+    pub fn get_sale_payment_tokens(&self, sale_id: u32) -> Vec<PaymentConfigJSON> {
+        let sale = self.internal_get_sale(sale_id);
+        sale.payment_configs.values().map(|config| config.to_json()).collect()
+    }
+
+    /// Deposits of the buyer in each payment token.
+    pub fn get_buyer_deposits(&self, buyer_id: AccountId, sale_id: u32) -> Vec<PaymentTokenAmount> {
+        let sale = self.internal_get_sale(sale_id);
+        sale.get_buyer_deposits(&buyer_id)
+    }
+}
+
+impl Sale {
+    /// The sale payment token is the sale denomination. Soft cap, allowlist
+    /// and tier allocations are expressed in this token.
+    #[inline]
+    pub(crate) fn sale_payment_config(&self) -> &PaymentConfig {
+        self.get_payment_config(&self.payment_token_contract_address)
+    }
+
+    pub(crate) fn get_payment_config(&self, token_id: &Option<AccountId>) -> &PaymentConfig {
+        self.payment_configs.get(token_id).expect("Payment token not accepted.")
+    }
+
+    pub(crate) fn get_payment_config_mut(&mut self, token_id: &Option<AccountId>) -> &mut PaymentConfig {
+        self.payment_configs.get_mut(token_id).expect("Payment token not accepted.")
+    }
+
+    #[inline]
+    pub(crate) fn accepts_payment_token(&self, token_id: &Option<AccountId>) -> bool {
+        self.payment_configs.contains_key(token_id)
+    }
+
+    /// Deposit of the buyer in a payment token.
+    pub(crate) fn get_buyer_deposit(&self, buyer_id: &AccountId, token_id: &Option<AccountId>) -> Balance {
+        self.deposits.get(&(buyer_id.clone(), token_id.clone())).unwrap_or(0)
+    }
+
+    /// Deposits of the buyer in each payment token, skipping the empty ones.
+    pub(crate) fn get_buyer_deposits(&self, buyer_id: &AccountId) -> Vec<PaymentTokenAmount> {
+        self.payment_configs
+            .keys()
+            .map(|token_id| PaymentTokenAmount {
+                payment_token_contract_address: token_id.clone(),
+                amount: U128::from(self.get_buyer_deposit(buyer_id, token_id)),
+            })
+            .filter(|deposit| deposit.amount.0 > 0)
+            .collect()
+    }
+
+    /// Deposits of the buyer in all the payment tokens, valued in the sale payment token.
+    pub(crate) fn get_buyer_deposit_value(&self, buyer_id: &AccountId) -> Balance {
+        self.get_buyer_deposits(buyer_id)
+            .iter()
+            .map(|deposit| self.to_sale_value(&deposit.payment_token_contract_address, deposit.amount.0))
+            .sum()
+    }
+
+    /// Add `amount` to the deposit of the buyer in a payment token.
+    pub(crate) fn add_buyer_deposit(
+        &mut self,
+        buyer_id: &AccountId,
+        token_id: &Option<AccountId>,
+        amount: Balance
+    ) {
+        let deposit = self.get_buyer_deposit(buyer_id, token_id) + amount;
+        self.deposits.insert(&(buyer_id.clone(), token_id.clone()), &deposit);
+    }
+
+    /// Take `amount` out of the deposit of the buyer in a payment token.
+    pub(crate) fn sub_buyer_deposit(
+        &mut self,
+        buyer_id: &AccountId,
+        token_id: &Option<AccountId>,
+        amount: Balance
+    ) {
+        let key = (buyer_id.clone(), token_id.clone());
+        let deposit = self.get_buyer_deposit(buyer_id, token_id) - amount;
+        if deposit > 0 {
+            self.deposits.insert(&key, &deposit);
+        } else {
+            self.deposits.remove(&key);
+        }
+    }
+
+    /// Important: The deposits of the buyer are removed from the sale.
+    pub(crate) fn remove_buyer_deposits(&mut self, buyer_id: &AccountId) -> Vec<PaymentTokenAmount> {
+        let deposits = self.get_buyer_deposits(buyer_id);
+        for deposit in deposits.iter() {
+            self.deposits.remove(&(buyer_id.clone(), deposit.payment_token_contract_address.clone()));
+        }
+        deposits
+    }
+
+    /// Recover the deposits of the buyer, when a transfer fails.
+    pub(crate) fn restore_buyer_deposits(&mut self, buyer_id: &AccountId, deposits: &[PaymentTokenAmount]) {
+        for deposit in deposits.iter() {
+            self.add_buyer_deposit(buyer_id, &deposit.payment_token_contract_address, deposit.amount.0);
+        }
+    }
+
+    /// Value of a payment in the sale payment token, at the floor prices.
+    pub(crate) fn to_sale_value(&self, token_id: &Option<AccountId>, amount: Balance) -> Balance {
+        if &self.payment_token_contract_address == token_id {
+            return amount;
+        }
+        let sale_config = self.sale_payment_config();
+        let config = self.get_payment_config(token_id);
+        proportional(
+            proportional(amount, config.one_payment_token_purchase_rate, config.payment_token_unit),
+            sale_config.payment_token_unit,
+            sale_config.one_payment_token_purchase_rate
+        )
+    }
+
+    pub(crate) fn has_payments(&self) -> bool {
+        self.payment_configs.values().any(|config| config.total_payment_token > 0)
+    }
+
+    pub(crate) fn has_fees(&self) -> bool {
+        self.payment_configs.values().any(|config| config.total_fees > 0)
+    }
+}
+
+/// Payment configs of a sale keyed by payment token: the sale payment token,
+/// and the additional tokens of the sale options.
+pub(crate) fn build_payment_configs(
+    payment_config: PaymentConfig,
+    payment_tokens: BTreeMap<String, AcceptedPaymentToken>,
+) -> BTreeMap<Option<AccountId>, PaymentConfig> {
+    require!(payment_tokens.len() < MAX_PAYMENT_TOKENS, "Too many payment tokens.");
+    let mut payment_configs = BTreeMap::new();
+    payment_configs.insert(payment_config.payment_token_contract_address.clone(), payment_config);
+    for (key, token) in payment_tokens.into_iter() {
+        let config = PaymentConfig::from_accepted_token(key, token);
+        require!(
+            payment_configs
+                .insert(config.payment_token_contract_address.clone(), config)
+                .is_none(),
+            "Duplicated payment token."
+        );
+    }
+    payment_configs
+}
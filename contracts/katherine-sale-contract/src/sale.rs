@@ -1,17 +1,60 @@
 use crate::*;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::UnorderedMap;
-use near_sdk::{AccountId, require};
+use near_sdk::json_types::Base58CryptoHash;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{AccountId, CryptoHash, require};
+use std::collections::BTreeMap;
 
 use crate::constants::STORAGE_PER_SALE;
 
-#[derive(BorshDeserialize, BorshSerialize)]
-pub struct PaymentConfig {
-    pub min_deposit_amount: Balance,
-    // If None, the payment token is in NEAR.
-    pub payment_token_contract_address: Option<AccountId>,
-    pub payment_token_unit: u128,
-    pub sale_fee: BasisPoints,
+/// Optional sale settings, set when the sale is created.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(default)]
+pub struct SaleOptions {
+    /// Minimum raise in the sale payment token.
+    pub soft_cap: Option<U128>,
+    /// Not supported for oversubscribed sales, nor for batch auctions: the sold
+    /// tokens of the buyers are only known after the close date.
+    pub vesting: Option<VestingConfig>,
+    /// Accept deposits over `max_available_sold_token`, allocated pro-rata after close.
+    pub oversubscribed: bool,
+    /// Merkle root of the (account, max_allocation) allowlist for private rounds.
+    pub allowlist_root: Option<Base58CryptoHash>,
+    /// Staking tiers, sorted by min voting power. Only tier buyers can deposit.
+    pub tiers: Vec<SaleTier>,
+    /// Fixed price by default. The `one_payment_token_purchase_rate` is the floor
+    /// price of the auctions.
+    pub mode: SaleMode,
+    /// Payment tokens accepted besides the sale payment token, keyed by the
+    /// token contract or `NEAR_PAYMENT_TOKEN`.
+    pub payment_tokens: BTreeMap<String, AcceptedPaymentToken>,
+    /// Buyers can withdraw their deposit before the close date, paying the
+    /// `early_exit_fee` in basis points, up to `MAX_EARLY_EXIT_FEE`.
+    pub allow_early_exit: bool,
+    pub early_exit_fee: BasisPoints,
+    /// Receives the payments of the sale. If None, the payments are sent to the seller.
+    pub payout_id: Option<AccountId>,
+}
+
+impl SaleOptions {
+    pub(crate) fn assert_valid(&self) {
+        assert_valid_tiers(&self.tiers);
+        require!(self.early_exit_fee <= MAX_EARLY_EXIT_FEE, "Invalid early exit fee.");
+        require!(
+            self.tiers.is_empty() || self.allowlist_root.is_none(),
+            "Tiers are not supported for private sales."
+        );
+        if let Some(vesting) = &self.vesting {
+            vesting.assert_valid();
+            require!(!self.oversubscribed, "Vesting is not supported for oversubscribed sales.");
+            require!(
+                !matches!(self.mode, SaleMode::BatchAuction { .. }),
+                "Vesting is not supported for batch auctions."
+            );
+        }
+    }
 }
 
 #[derive(BorshDeserialize, BorshSerialize)]
@@ -25,18 +68,34 @@ pub struct Sale {
     /// The address of the token to be sold.
     pub sold_token_contract_address: AccountId,
 
+    /// The seller submits the sale, deposits the sold tokens, collects the
+    /// payments and withdraws the excess of sold tokens.
+    pub seller_id: AccountId,
+    /// Receives the payments of the sale, net of the `sale_fee`.
+    pub payout_id: AccountId,
+
     /// For the **buyers**
     pub max_available_sold_token: Balance, // Remains constant.
     pub required_sold_token: Balance,
 
     /// For the **seller**
-    pub total_payment_token: Balance,
+    /// Payment tokens raised in the funding period, only reduced by early exits.
+    /// Deposits in other payment tokens are valued in the sale payment token.
+    pub total_raised: Balance,
+    /// Minimum raise in payment tokens. If not reached by the close date, the
+    /// sale fails and buyers can get their deposits back right away.
+    pub soft_cap: Balance,
 
     /// Conversion rates
-    /// How many sold tokens can be purchase using one payment token or NEAR.
+    /// Each payment token has a `one_payment_token_purchase_rate`: how many
+    /// sold tokens can be purchase using one payment token or NEAR.
     /// CAUTION: Include in the conversion rate the token DECIMALS.
     /// "one" in NEAR is 1e24 // "one" in USDT is 1_000_000 [see payment_token_unit].
-    pub one_payment_token_purchase_rate: u128,
+    /// The sale payment token is inherit when sale is created. If None, the
+    /// sale is in NEAR.
+    pub payment_token_contract_address: Option<AccountId>,
+    pub payment_configs: BTreeMap<Option<AccountId>, PaymentConfig>,
+    pub sale_fee: BasisPoints,
 
     /// Opening date for Sale.
     pub open_date_timestamp: EpochMillis,
@@ -54,13 +113,48 @@ pub struct Sale {
     /// Consider that the sold token was converted form NEAR or payment token.
     /// The sum of the balances should be equal to `required_sold_token`.
     pub claimable_sold_token_for_buyers: UnorderedMap<AccountId, Balance>,
-    /// The sum of the balances should be equal to `total_payment_token`.
-    pub deposits: UnorderedMap<AccountId, Balance>,
-
-    /// The payment config is inherit when sale is created.
-    pub payment_config: PaymentConfig,
-
-    pub total_fees: u128,
+    /// Deposits of each buyer and payment token. For each token, the sum of the
+    /// balances should be equal to its `total_payment_token`.
+    pub deposits: UnorderedMap<(AccountId, Option<AccountId>), Balance>,
+
+    /// If None, all the sold tokens are released at `release_date_timestamp`.
+    pub vesting: Option<VestingConfig>,
+    /// Sold tokens already claimed by each buyer, when the sale has vesting.
+    pub claimed_sold_token: UnorderedMap<AccountId, Balance>,
+
+    /// Oversubscribed sales accept deposits without limit. After close, every
+    /// buyer gets `max_available_sold_token / total_requested_sold_token` of the
+    /// requested tokens, and the unused payment tokens are refunded.
+    pub oversubscribed: bool,
+    /// Set after close if the allocation is pro-rata, zero otherwise.
+    pub total_requested_sold_token: Balance,
+    /// Refunds that failed to be transferred, to retry with `withdraw_tokens`.
+    pub excess_refunds: UnorderedMap<AccountId, Vec<PaymentTokenAmount>>,
+
+    /// If set, only allowlisted buyers can deposit, up to their max allocation.
+    pub allowlist_root: Option<CryptoHash>,
+    /// Max allocation of the buyers that already proved they are allowlisted.
+    pub allowlist_allocations: UnorderedMap<AccountId, Balance>,
+
+    /// If not empty, the buyer deposits are capped by the staking tier.
+    pub tiers: Vec<SaleTier>,
+
+    /// Fixed price, or price discovery with a Dutch or batch auction.
+    pub mode: SaleMode,
+
+    /// Set when the owner cancels the sale. Buyers can get their deposits back
+    /// right away, and the sold tokens are returned to the seller.
+    pub cancel_reason: Option<String>,
+    /// A sale can only be canceled before the payments are collected.
+    pub payments_collected: bool,
+    /// Sales submitted by sellers do not accept deposits until the owner
+    /// approves them. Sales created by the owner are approved right away.
+    pub approved: bool,
+
+    /// Buyers can withdraw their deposits during the funding period, the exit
+    /// fee is added to the sale fees.
+    pub allow_early_exit: bool,
+    pub early_exit_fee: BasisPoints,
 }
 
 impl Sale {
@@ -68,25 +162,28 @@ impl Sale {
         id: u32,
         slug: String,
         sold_token_contract_address: AccountId,
-        one_payment_token_purchase_rate: u128,
+        seller_id: AccountId,
         max_available_sold_token: Balance,
         open_date_timestamp: EpochMillis,
         close_date_timestamp: EpochMillis,
         release_date_timestamp: EpochMillis,
-        // Create payment config.
-        min_deposit_amount: Balance,
-        payment_token_contract_address: Option<AccountId>,
-        payment_token_unit: u128,
+        options: SaleOptions,
+        payment_config: PaymentConfig,
         sale_fee: BasisPoints,
     ) -> Self {
+        let payment_token_contract_address = payment_config.payment_token_contract_address.clone();
+        let payment_configs = build_payment_configs(payment_config, options.payment_tokens);
+        let payout_id = options.payout_id.unwrap_or_else(|| seller_id.clone());
         Sale {
             id,
             slug,
             sold_token_contract_address,
-            one_payment_token_purchase_rate,
+            seller_id,
+            payout_id,
             max_available_sold_token,
             required_sold_token: 0,
-            total_payment_token: 0,
+            total_raised: 0,
+            soft_cap: options.soft_cap.map_or(0, |soft_cap| soft_cap.0),
             open_date_timestamp,
             close_date_timestamp,
             release_date_timestamp,
@@ -101,21 +198,38 @@ impl Sale {
                     hash_id: generate_hash_id(id.to_string())
                 }
             ),
-            payment_config: PaymentConfig {
-                min_deposit_amount,
-                payment_token_contract_address,
-                payment_token_unit,
-                sale_fee,
-            },
-            total_fees: 0,
+            payment_token_contract_address,
+            payment_configs,
+            sale_fee,
+            vesting: options.vesting,
+            claimed_sold_token: UnorderedMap::new(
+                StorageKey::ClaimedSoldTokens {
+                    hash_id: generate_hash_id(id.to_string())
+                }
+            ),
+            oversubscribed: options.oversubscribed,
+            total_requested_sold_token: 0,
+            excess_refunds: UnorderedMap::new(
+                StorageKey::ExcessRefunds {
+                    hash_id: generate_hash_id(id.to_string())
+                }
+            ),
+            allowlist_root: options.allowlist_root.map(|root| root.into()),
+            allowlist_allocations: UnorderedMap::new(
+                StorageKey::AllowlistAllocations {
+                    hash_id: generate_hash_id(id.to_string())
+                }
+            ),
+            tiers: options.tiers,
+            mode: options.mode,
+            cancel_reason: None,
+            payments_collected: false,
+            approved: false,
+            allow_early_exit: options.allow_early_exit,
+            early_exit_fee: options.early_exit_fee,
         }
     }
 
-    /// The sale will only have a payment token if it's not denominated in NEAR.
-    pub(crate) fn get_payment_token(&self) -> AccountId {
-        self.payment_config.payment_token_contract_address.clone().unwrap()
-    }
-
     pub(crate) fn get_sold_token(&self) -> AccountId {
         self.sold_token_contract_address.clone()
     }
@@ -135,38 +249,64 @@ impl Sale {
         self.claimable_sold_token_for_buyers.get(buyer_id).unwrap_or(0)
     }
 
-    pub(crate) fn get_buyer_deposit(
+    /// The auction price changes apply to all the payment tokens.
+    pub(crate) fn from_payment_to_sold_token(
         &self,
-        buyer_id: &AccountId
-    ) -> Balance {
-        self.deposits.get(buyer_id).unwrap_or(0)
-    }
-
-    pub(crate) fn from_payment_to_sold_token(&self, amount: u128) -> u128 {
-        proportional(
-            amount,
-            self.one_payment_token_purchase_rate,
-            self.payment_config.payment_token_unit
-        )
+        token_id: &Option<AccountId>,
+        amount: u128
+    ) -> u128 {
+        let config = self.get_payment_config(token_id);
+        let purchase_rate = proportional(
+            config.one_payment_token_purchase_rate,
+            self.get_deposit_purchase_rate(),
+            self.sale_payment_config().one_payment_token_purchase_rate
+        );
+        proportional(amount, purchase_rate, config.payment_token_unit)
     }
 
+    /// The sale is denominated in NEAR, other payment tokens might be accepted.
     #[inline]
     pub(crate) fn is_near_accepted(&self) -> bool {
-        self.payment_config.payment_token_contract_address.is_none()
+        self.payment_token_contract_address.is_none()
     }
 
     #[inline]
     pub(crate) fn is_active(&self) -> bool {
-        get_current_epoch_millis() < self.close_date_timestamp
+        get_current_epoch_millis() < self.close_date_timestamp && self.accepts_deposits()
     }
     
     pub(crate) fn is_within_funding_period(&self) -> bool {
         let now = get_current_epoch_millis();
-        now < self.close_date_timestamp && now >= self.open_date_timestamp
+        now < self.close_date_timestamp
+            && now >= self.open_date_timestamp
+            && self.accepts_deposits()
+    }
+
+    #[inline]
+    pub(crate) fn is_canceled(&self) -> bool {
+        self.cancel_reason.is_some()
+    }
+
+    /// Only approved sales that are not canceled accept deposits.
+    #[inline]
+    pub(crate) fn accepts_deposits(&self) -> bool {
+        self.approved && !self.is_canceled()
+    }
+
+    /// Buyers of a failed or canceled sale can get their deposits back.
+    #[inline]
+    pub(crate) fn is_refundable(&self) -> bool {
+        self.is_failed() || self.is_canceled()
+    }
+
+    /// The sale fails if the soft cap is not reached by the close date.
+    pub(crate) fn is_failed(&self) -> bool {
+        get_current_epoch_millis() > self.close_date_timestamp
+            && self.total_raised < self.soft_cap
     }
 
     pub(crate) fn are_sold_tokens_covered(&self) -> bool {
-        self.required_sold_token <= self.sold_tokens_for_buyers
+        self.get_required_allocation() <= self.sold_tokens_for_buyers
     }
 
     // **************** 
@@ -174,11 +314,12 @@ impl Sale {
     // **************** 
 
     #[inline]
-    pub(crate) fn assert_min_deposit_amount(&self, amount: Balance) {
+    pub(crate) fn assert_min_deposit_amount(&self, token_id: &Option<AccountId>, amount: Balance) {
+        let min_deposit_amount = self.get_payment_config(token_id).min_deposit_amount;
         assert!(
-            amount >= self.payment_config.min_deposit_amount,
+            amount >= min_deposit_amount,
             "minimum deposit amount is {}",
-            self.payment_config.min_deposit_amount
+            min_deposit_amount
         );
     }
 
@@ -208,6 +349,21 @@ impl Sale {
         );
     }
 
+    #[inline]
+    pub(crate) fn assert_not_failed(&self) {
+        require!(!self.is_failed(), "Sale did not reach the soft cap.");
+    }
+
+    #[inline]
+    pub(crate) fn assert_not_canceled(&self) {
+        require!(!self.is_canceled(), "Sale is canceled.");
+    }
+
+    #[inline]
+    pub(crate) fn assert_only_seller(&self) {
+        require!(env::predecessor_account_id() == self.seller_id, "Only seller.");
+    }
+
     #[inline]
     pub(crate) fn assert_after_close_period(&self) {
         require!(
@@ -217,25 +373,44 @@ impl Sale {
     }
 
     pub(crate) fn to_json(&self) -> SaleJSON {
+        let sale_config = self.sale_payment_config();
         SaleJSON {
             id: self.id,
             slug: self.slug.clone(),
             sold_token_contract_address: self.sold_token_contract_address.clone(),
+            seller_id: self.seller_id.clone(),
+            payout_id: self.payout_id.clone(),
             max_available_sold_token: U128::from(self.max_available_sold_token),
             required_sold_token: U128::from(self.required_sold_token),
-            total_payment_token: U128::from(self.total_payment_token),
-            one_payment_token_purchase_rate: U128::from(self.one_payment_token_purchase_rate),
+            total_payment_token: U128::from(sale_config.total_payment_token),
+            total_raised: U128::from(self.total_raised),
+            soft_cap: U128::from(self.soft_cap),
+            one_payment_token_purchase_rate: U128::from(sale_config.one_payment_token_purchase_rate),
             open_date_timestamp: U64::from(self.open_date_timestamp),
             close_date_timestamp: U64::from(self.close_date_timestamp),
             release_date_timestamp: U64::from(self.release_date_timestamp),
             sold_tokens_for_buyers: U128::from(self.sold_tokens_for_buyers),
-            min_deposit_amount: U128::from(self.payment_config.min_deposit_amount),
-            payment_token_contract_address: self.payment_config.payment_token_contract_address.clone(),
-            payment_token_unit: U128::from(self.payment_config.payment_token_unit),
-            sale_fee: self.payment_config.sale_fee,
-            total_fees: U128::from(self.total_fees),
+            min_deposit_amount: U128::from(sale_config.min_deposit_amount),
+            payment_token_contract_address: self.payment_token_contract_address.clone(),
+            payment_token_unit: U128::from(sale_config.payment_token_unit),
+            sale_fee: self.sale_fee,
+            total_fees: U128::from(sale_config.total_fees),
+            payment_tokens: self.payment_configs.values().map(|config| config.to_json()).collect(),
+            vesting: self.vesting.clone(),
+            oversubscribed: self.oversubscribed,
+            total_requested_sold_token: U128::from(self.total_requested_sold_token),
+            allowlist_root: self.allowlist_root.map(Base58CryptoHash::from),
+            tiers: self.tiers.clone(),
+            mode: self.mode.clone(),
+            current_purchase_rate: U128::from(self.get_current_purchase_rate()),
             is_in_near: self.is_near_accepted(),
-            is_active: self.is_active()
+            is_active: self.is_active(),
+            is_approved: self.approved,
+            is_failed: self.is_failed(),
+            is_canceled: self.is_canceled(),
+            cancel_reason: self.cancel_reason.clone(),
+            allow_early_exit: self.allow_early_exit,
+            early_exit_fee: self.early_exit_fee,
         }
     }
 }
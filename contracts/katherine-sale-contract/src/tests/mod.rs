@@ -1,8 +1,9 @@
 use super::*;
 use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
-use near_sdk::json_types::{U64, U128};
+use near_sdk::json_types::{Base58CryptoHash, U64, U128};
 // use near_sdk::serde_json;
 use near_sdk::testing_env;
+use std::collections::BTreeMap;
 use near_sdk::test_utils::{accounts, VMContextBuilder};
 
 mod utils;
@@ -36,6 +37,34 @@ fn create_sale(
     slug: &str,
     is_in_near: bool
 ) {
+    create_sale_with_options(contract, slug, is_in_near, SaleOptions::default());
+}
+
+/// The seller submits the sale, and the owner approves it.
+fn create_sale_with_options(
+    contract: &mut KatherineSaleContract,
+    slug: &str,
+    is_in_near: bool,
+    options: SaleOptions,
+) {
+    let mut context = get_context(seller_account());
+    testing_env!(context.attached_deposit(STORAGE_PER_SALE).build());
+    let sale_id = submit_sale(contract, slug, is_in_near, options);
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(1)
+        .build()
+    );
+    contract.approve_sale(sale_id);
+}
+
+fn submit_sale(
+    contract: &mut KatherineSaleContract,
+    slug: &str,
+    is_in_near: bool,
+    mut options: SaleOptions,
+) -> u32 {
+    options.payout_id.get_or_insert_with(payout_account);
     // let unit = if is_in_near {NEAR} else {USDT_UNIT};
     let unit = NEAR;
     // We'll assume that the  sold token will always have 24 decimals
@@ -56,7 +85,9 @@ fn create_sale(
         U64::from(nanos_to_millis(to_ts(10))),
         // release_date_timestamp: EpochMillis,
         U64::from(nanos_to_millis(to_ts(15))),
-    );
+        // options: Option<SaleOptions>,
+        Some(options),
+    )
 }
 
 // Check the docs: https://docs.near.org/sdk/rust/testing/integration-tests#
@@ -98,13 +129,13 @@ fn test_near_deposit() {
         .block_timestamp(to_ts(0))
         .build()
     );
-    contract.purchase_token_with_near(0);
+    contract.purchase_token_with_near(0, None);
 
     testing_env!(context.is_view(true).build());
     assert_eq!(6 * NEAR, contract.get_buyer_claimable_sold_token(accounts(1), 0).0);
     assert_eq!(3 * NEAR, contract.get_buyer_deposit(accounts(1), 0).0);
     assert_eq!(6 * NEAR, contract.sales.get(0).unwrap().required_sold_token);
-    assert_eq!(3 * NEAR, contract.sales.get(0).unwrap().total_payment_token);
+    assert_eq!(3 * NEAR, contract.sales.get(0).unwrap().sale_payment_config().total_payment_token);
     assert_eq!(0, contract.sales.get(0).unwrap().sold_tokens_for_buyers);
 
     testing_env!(context
@@ -114,13 +145,13 @@ fn test_near_deposit() {
         .block_timestamp(to_ts(1))
         .build()
     );
-    contract.purchase_token_with_near(0);
+    contract.purchase_token_with_near(0, None);
 
     testing_env!(context.is_view(true).build());
     assert_eq!(2 * NEAR, contract.get_buyer_claimable_sold_token(accounts(2), 0).0);
     assert_eq!(1 * NEAR, contract.get_buyer_deposit(accounts(2), 0).0);
     assert_eq!(8 * NEAR, contract.sales.get(0).unwrap().required_sold_token);
-    assert_eq!(4 * NEAR, contract.sales.get(0).unwrap().total_payment_token);
+    assert_eq!(4 * NEAR, contract.sales.get(0).unwrap().sale_payment_config().total_payment_token);
 
     testing_env!(context
         .predecessor_account_id(sold_token_contract())
@@ -151,7 +182,7 @@ fn abstract_near_deposit() -> (VMContextBuilder, KatherineSaleContract) {
         .block_timestamp(to_ts(0))
         .build()
     );
-    contract.purchase_token_with_near(0);
+    contract.purchase_token_with_near(0, None);
 
     testing_env!(context
         .predecessor_account_id(accounts(2))
@@ -160,7 +191,7 @@ fn abstract_near_deposit() -> (VMContextBuilder, KatherineSaleContract) {
         .block_timestamp(to_ts(1))
         .build()
     );
-    contract.purchase_token_with_near(0);
+    contract.purchase_token_with_near(0, None);
 
     testing_env!(context
         .predecessor_account_id(sold_token_contract())
@@ -181,7 +212,7 @@ fn test_near_deposit_with_withdraws_fail_before_close() {
     
     // Seller: withdraw payment tokens
     testing_env!(context
-        .predecessor_account_id(owner_account())
+        .predecessor_account_id(seller_account())
         .is_view(false)
         // .attached_deposit(1 * NEAR)
         .block_timestamp(to_ts(9))
@@ -196,7 +227,7 @@ fn test_near_deposit_with_withdraws() {
     
     // Seller: withdraw payment tokens
     testing_env!(context
-        .predecessor_account_id(owner_account())
+        .predecessor_account_id(seller_account())
         .is_view(false)
         // .attached_deposit(1 * NEAR)
         .block_timestamp(to_ts(11))
@@ -206,7 +237,7 @@ fn test_near_deposit_with_withdraws() {
     testing_env!(context.is_view(true).build());
     assert_eq!(8 * NEAR, contract.sales.get(0).unwrap().required_sold_token);
     assert_eq!(8 * NEAR, contract.sales.get(0).unwrap().sold_tokens_for_buyers);
-    assert_eq!(0, contract.sales.get(0).unwrap().total_payment_token);
+    assert_eq!(0, contract.sales.get(0).unwrap().sale_payment_config().total_payment_token);
 
     // Deposit excessive sold tokens
     testing_env!(context
@@ -220,7 +251,7 @@ fn test_near_deposit_with_withdraws() {
     assert_eq!(10 * NEAR, contract.sales.get(0).unwrap().sold_tokens_for_buyers);
 
     testing_env!(context
-        .predecessor_account_id(owner_account())
+        .predecessor_account_id(seller_account())
         .is_view(false)
         // .attached_deposit(1 * NEAR)
         .block_timestamp(to_ts(13))
@@ -230,7 +261,7 @@ fn test_near_deposit_with_withdraws() {
     testing_env!(context.is_view(true).build());
     assert_eq!(8 * NEAR, contract.sales.get(0).unwrap().required_sold_token);
     assert_eq!(8 * NEAR, contract.sales.get(0).unwrap().sold_tokens_for_buyers);
-    assert_eq!(0, contract.sales.get(0).unwrap().total_payment_token);
+    assert_eq!(0, contract.sales.get(0).unwrap().sale_payment_config().total_payment_token);
 }
 
 #[test]
@@ -259,7 +290,7 @@ fn test_usdt_deposit() {
     assert_eq!(8 * NEAR, contract.get_buyer_claimable_sold_token(accounts(1), 0).0);
     assert_eq!(4 * USDT_UNIT, contract.get_buyer_deposit(accounts(1), 0).0);
     assert_eq!(8 * NEAR, contract.sales.get(0).unwrap().required_sold_token);
-    assert_eq!(4 * USDT_UNIT, contract.sales.get(0).unwrap().total_payment_token);
+    assert_eq!(4 * USDT_UNIT, contract.sales.get(0).unwrap().sale_payment_config().total_payment_token);
     assert_eq!(0, contract.sales.get(0).unwrap().sold_tokens_for_buyers);
 
     testing_env!(context
@@ -276,7 +307,7 @@ fn test_usdt_deposit() {
     assert_eq!(2 * NEAR, contract.get_buyer_claimable_sold_token(accounts(2), 0).0);
     assert_eq!(1 * USDT_UNIT, contract.get_buyer_deposit(accounts(2), 0).0);
     assert_eq!(10 * NEAR, contract.sales.get(0).unwrap().required_sold_token);
-    assert_eq!(5 * USDT_UNIT, contract.sales.get(0).unwrap().total_payment_token);
+    assert_eq!(5 * USDT_UNIT, contract.sales.get(0).unwrap().sale_payment_config().total_payment_token);
 
     testing_env!(context
         .predecessor_account_id(sold_token_contract())
@@ -339,7 +370,7 @@ fn test_usdt_deposit_with_withdraws() {
     
     // Seller: withdraw payment tokens
     testing_env!(context
-        .predecessor_account_id(owner_account())
+        .predecessor_account_id(seller_account())
         .is_view(false)
         // .attached_deposit(1 * NEAR)
         .block_timestamp(to_ts(11))
@@ -349,7 +380,7 @@ fn test_usdt_deposit_with_withdraws() {
     testing_env!(context.is_view(true).build());
     assert_eq!(10 * NEAR, contract.sales.get(0).unwrap().required_sold_token);
     assert_eq!(10 * NEAR, contract.sales.get(0).unwrap().sold_tokens_for_buyers);
-    assert_eq!(0, contract.sales.get(0).unwrap().total_payment_token);
+    assert_eq!(0, contract.sales.get(0).unwrap().sale_payment_config().total_payment_token);
 }
 
 #[test]
@@ -373,5 +404,1276 @@ fn test_fail_near_deposit() {
         .block_timestamp(to_ts(0))
         .build()
     );
-    contract.purchase_token_with_near(0);
+    contract.purchase_token_with_near(0, None);
+}
+
+#[test]
+fn test_near_deposit_with_vesting() {
+    let mut context = get_context(owner_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(STORAGE_PER_SALE)
+        .build()
+    );
+    // 25% at release, 5 days of cliff, then 10 days of linear vesting.
+    create_sale_with_options(
+        &mut contract,
+        "test-sale-1",
+        true,
+        SaleOptions {
+            vesting: Some(VestingConfig {
+                tge_release: 2_500,
+                cliff_duration: U64::from(nanos_to_millis(to_nanos(5))),
+                vesting_duration: U64::from(nanos_to_millis(to_nanos(10))),
+            }),
+            ..Default::default()
+        }
+    );
+
+    testing_env!(context
+        .predecessor_account_id(accounts(1))
+        .attached_deposit(2 * NEAR)
+        .block_timestamp(to_ts(0))
+        .build()
+    );
+    contract.purchase_token_with_near(0, None);
+
+    testing_env!(context
+        .predecessor_account_id(sold_token_contract())
+        .attached_deposit(0)
+        .block_timestamp(to_ts(1))
+        .build()
+    );
+    contract.ft_on_transfer(accounts(3), U128::from(4 * NEAR), 0.to_string());
+
+    // Release date: only the TGE percentage.
+    testing_env!(context
+        .predecessor_account_id(accounts(1))
+        .block_timestamp(to_ts(15))
+        .build()
+    );
+    assert_eq!(NEAR, contract.get_buyer_vesting(accounts(1), 0).claimable.0);
+    contract.claim_vested(0);
+    let vesting = contract.get_buyer_vesting(accounts(1), 0);
+    assert_eq!(4 * NEAR, vesting.total.0);
+    assert_eq!(NEAR, vesting.claimed.0);
+    assert_eq!(3 * NEAR, vesting.locked.0);
+    assert_eq!(0, vesting.claimable.0);
+
+    // Half of the linear vesting.
+    testing_env!(context.block_timestamp(to_ts(25)).build());
+    assert_eq!(2_500_000_000_000_000_000_000_000, contract.get_buyer_vesting(accounts(1), 0).vested.0);
+    contract.claim_vested(0);
+    assert_eq!(1_500_000_000_000_000_000_000_000, contract.sales.get(0).unwrap().required_sold_token);
+
+    // Fully vested, the buyer deposit is removed.
+    testing_env!(context.block_timestamp(to_ts(30)).build());
+    contract.claim_vested(0);
+    let sale = contract.sales.get(0).unwrap();
+    assert_eq!(0, sale.required_sold_token);
+    assert_eq!(0, sale.sold_tokens_for_buyers);
+    assert_eq!(0, contract.get_buyer_deposit(accounts(1), 0).0);
+    assert_eq!(4 * NEAR, contract.get_buyer_vesting(accounts(1), 0).claimed.0);
+}
+
+#[test]
+#[should_panic(expected = "Sale has vesting, use claim_vested.")]
+fn test_withdraw_tokens_fail_with_vesting() {
+    let mut context = get_context(owner_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(STORAGE_PER_SALE)
+        .build()
+    );
+    create_sale_with_options(
+        &mut contract,
+        "test-sale-1",
+        true,
+        SaleOptions {
+            vesting: Some(VestingConfig {
+                tge_release: 0,
+                cliff_duration: U64::from(0),
+                vesting_duration: U64::from(nanos_to_millis(to_nanos(10))),
+            }),
+            ..Default::default()
+        }
+    );
+
+    testing_env!(context
+        .predecessor_account_id(accounts(1))
+        .attached_deposit(2 * NEAR)
+        .block_timestamp(to_ts(0))
+        .build()
+    );
+    contract.purchase_token_with_near(0, None);
+
+    testing_env!(context
+        .predecessor_account_id(sold_token_contract())
+        .attached_deposit(0)
+        .block_timestamp(to_ts(1))
+        .build()
+    );
+    contract.ft_on_transfer(accounts(3), U128::from(4 * NEAR), 0.to_string());
+
+    testing_env!(context
+        .predecessor_account_id(accounts(1))
+        .block_timestamp(to_ts(16))
+        .build()
+    );
+    contract.withdraw_tokens(0);
+}
+
+fn abstract_near_deposit_with_soft_cap() -> (VMContextBuilder, KatherineSaleContract) {
+    let mut context = get_context(owner_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(STORAGE_PER_SALE)
+        .build()
+    );
+    create_sale_with_options(
+        &mut contract,
+        "test-sale-1",
+        true,
+        SaleOptions {
+            soft_cap: Some(U128::from(4 * NEAR)),
+            ..Default::default()
+        }
+    );
+
+    testing_env!(context
+        .predecessor_account_id(accounts(1))
+        .attached_deposit(3 * NEAR)
+        .block_timestamp(to_ts(0))
+        .build()
+    );
+    contract.purchase_token_with_near(0, None);
+
+    testing_env!(context
+        .predecessor_account_id(sold_token_contract())
+        .attached_deposit(0)
+        .block_timestamp(to_ts(1))
+        .build()
+    );
+    contract.ft_on_transfer(accounts(3), U128::from(6 * NEAR), 0.to_string());
+
+    (context, contract)
+}
+
+#[test]
+fn test_near_deposit_under_soft_cap_refund() {
+    let (mut context, mut contract) = abstract_near_deposit_with_soft_cap();
+    assert!(!contract.get_sale(0).is_failed);
+
+    // Right after close, before the release date.
+    testing_env!(context
+        .predecessor_account_id(accounts(1))
+        .block_timestamp(to_ts(11))
+        .build()
+    );
+    assert!(contract.get_sale(0).is_failed);
+    contract.withdraw_tokens(0);
+    let sale = contract.sales.get(0).unwrap();
+    assert_eq!(0, sale.required_sold_token);
+    assert_eq!(0, sale.sale_payment_config().total_payment_token);
+    assert_eq!(3 * NEAR, sale.total_raised);
+    assert_eq!(0, contract.get_buyer_deposit(accounts(1), 0).0);
+
+    // The seller gets all the sold tokens back.
+    testing_env!(context.predecessor_account_id(seller_account()).build());
+    contract.withdraw_excess_sold_tokens(0);
+    assert_eq!(0, contract.sales.get(0).unwrap().sold_tokens_for_buyers);
+}
+
+#[test]
+#[should_panic(expected = "Sale did not reach the soft cap.")]
+fn test_collect_payments_fail_under_soft_cap() {
+    let (mut context, mut contract) = abstract_near_deposit_with_soft_cap();
+
+    testing_env!(context
+        .predecessor_account_id(seller_account())
+        .block_timestamp(to_ts(11))
+        .build()
+    );
+    contract.collect_payments(0);
+}
+
+#[test]
+fn test_near_deposit_oversubscribed() {
+    let mut context = get_context(owner_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(STORAGE_PER_SALE)
+        .build()
+    );
+    create_sale_with_options(
+        &mut contract,
+        "test-sale-1",
+        true,
+        SaleOptions {
+            oversubscribed: true,
+            ..Default::default()
+        }
+    );
+
+    // 20 sold tokens requested, 10 available.
+    testing_env!(context
+        .predecessor_account_id(accounts(1))
+        .attached_deposit(6 * NEAR)
+        .block_timestamp(to_ts(0))
+        .build()
+    );
+    contract.purchase_token_with_near(0, None);
+    testing_env!(context
+        .predecessor_account_id(accounts(2))
+        .attached_deposit(4 * NEAR)
+        .block_timestamp(to_ts(1))
+        .build()
+    );
+    contract.purchase_token_with_near(0, None);
+    assert_eq!(20 * NEAR, contract.sales.get(0).unwrap().required_sold_token);
+
+    testing_env!(context
+        .predecessor_account_id(sold_token_contract())
+        .attached_deposit(0)
+        .block_timestamp(to_ts(2))
+        .build()
+    );
+    contract.ft_on_transfer(accounts(3), U128::from(10 * NEAR), 0.to_string());
+
+    testing_env!(context
+        .predecessor_account_id(seller_account())
+        .block_timestamp(to_ts(11))
+        .build()
+    );
+    let (allocation, refund) = contract.get_buyer_allocation(accounts(1), 0);
+    assert_eq!(6 * NEAR, allocation.0);
+    assert_eq!(3 * NEAR, refund[0].amount.0);
+    contract.collect_payments(0);
+    let sale = contract.sales.get(0).unwrap();
+    assert_eq!(20 * NEAR, sale.total_requested_sold_token);
+    assert_eq!(10 * NEAR, sale.required_sold_token);
+    assert_eq!(5 * NEAR, sale.sale_payment_config().excess_payment_token);
+
+    testing_env!(context
+        .predecessor_account_id(accounts(2))
+        .block_timestamp(to_ts(16))
+        .build()
+    );
+    contract.withdraw_tokens(0);
+    let sale = contract.sales.get(0).unwrap();
+    assert_eq!(6 * NEAR, sale.required_sold_token);
+    assert_eq!(6 * NEAR, sale.sold_tokens_for_buyers);
+    assert_eq!(3 * NEAR, sale.sale_payment_config().excess_payment_token);
+}
+
+/// Allowlist of two buyers: accounts(1) up to 3 USDT and accounts(2) up to 1 USDT.
+fn abstract_private_sale() -> (VMContextBuilder, KatherineSaleContract, Base58CryptoHash) {
+    let leaf_1 = allowlist_leaf(&accounts(1), 3 * USDT_UNIT);
+    let leaf_2 = allowlist_leaf(&accounts(2), USDT_UNIT);
+    let (left, right) = if leaf_1 <= leaf_2 { (leaf_1, leaf_2) } else { (leaf_2, leaf_1) };
+    let root = env::keccak256_array(&[left, right].concat());
+
+    let mut context = get_context(owner_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(STORAGE_PER_SALE)
+        .build()
+    );
+    create_sale_with_options(
+        &mut contract,
+        "test-sale-1",
+        false,
+        SaleOptions {
+            allowlist_root: Some(Base58CryptoHash::from(root)),
+            ..Default::default()
+        }
+    );
+    (context, contract, Base58CryptoHash::from(leaf_2))
+}
+
+#[test]
+fn test_usdt_deposit_private_sale() {
+    let (mut context, mut contract, proof_1) = abstract_private_sale();
+
+    testing_env!(context
+        .predecessor_account_id(usdt_token_contract())
+        .block_timestamp(to_ts(0))
+        .build()
+    );
+    let msg = near_sdk::serde_json::json!({
+        "sale_id": 0,
+        "allowlist": { "max_allocation": (3 * USDT_UNIT).to_string(), "proof": [proof_1] },
+    });
+    contract.ft_on_transfer(accounts(1), U128::from(2 * USDT_UNIT), msg.to_string());
+    assert_eq!(Some(U128::from(3 * USDT_UNIT)), contract.get_buyer_max_allocation(accounts(1), 0));
+
+    // Next deposits do not need the proof.
+    contract.ft_on_transfer(accounts(1), U128::from(USDT_UNIT), 0.to_string());
+    assert_eq!(3 * USDT_UNIT, contract.get_buyer_deposit(accounts(1), 0).0);
+}
+
+#[test]
+#[should_panic(expected = "Max allocation for buyer is 3000000.")]
+fn test_fail_usdt_deposit_over_allocation() {
+    let (mut context, mut contract, proof_1) = abstract_private_sale();
+
+    testing_env!(context
+        .predecessor_account_id(usdt_token_contract())
+        .block_timestamp(to_ts(0))
+        .build()
+    );
+    let msg = near_sdk::serde_json::json!({
+        "sale_id": 0,
+        "allowlist": { "max_allocation": (3 * USDT_UNIT).to_string(), "proof": [proof_1] },
+    });
+    contract.ft_on_transfer(accounts(1), U128::from(4 * USDT_UNIT), msg.to_string());
+}
+
+#[test]
+#[should_panic(expected = "Private sale, allowlist proof required.")]
+fn test_fail_usdt_deposit_not_allowlisted() {
+    let (mut context, mut contract, _) = abstract_private_sale();
+
+    testing_env!(context
+        .predecessor_account_id(usdt_token_contract())
+        .block_timestamp(to_ts(0))
+        .build()
+    );
+    contract.ft_on_transfer(accounts(3), U128::from(USDT_UNIT), 0.to_string());
+}
+
+/// Bronze up to 1 USDT from the open date, and Gold up to 5 USDT one day before.
+fn abstract_tiered_sale() -> (VMContextBuilder, KatherineSaleContract) {
+    let mut context = get_context(owner_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(1)
+        .build()
+    );
+    contract.update_staking_position_contract_address(staking_position_contract());
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(STORAGE_PER_SALE)
+        .build()
+    );
+    create_sale_with_options(
+        &mut contract,
+        "test-sale-1",
+        false,
+        SaleOptions {
+            tiers: vec![
+                SaleTier {
+                    name: String::from("Bronze"),
+                    min_voting_power: U128::from(10 * NEAR),
+                    max_allocation: U128::from(USDT_UNIT),
+                    early_access: U64::from(0),
+                },
+                SaleTier {
+                    name: String::from("Gold"),
+                    min_voting_power: U128::from(100 * NEAR),
+                    max_allocation: U128::from(5 * USDT_UNIT),
+                    early_access: U64::from(nanos_to_millis(to_nanos(1))),
+                },
+            ],
+            ..Default::default()
+        }
+    );
+    (context, contract)
+}
+
+/// Resolve the deposit with a single locked position of the buyer.
+fn resolve_tier_deposit(
+    context: &mut VMContextBuilder,
+    contract: &mut KatherineSaleContract,
+    buyer_id: AccountId,
+    amount: u128,
+    voting_power: u128,
+) -> U128 {
+    let locking_positions = near_sdk::serde_json::json!([{
+        "index": 0,
+        "amount": voting_power.to_string(),
+        "locking_period": 30,
+        "voting_power": voting_power.to_string(),
+        "unlocking_started_at": null,
+        "is_unlocked": false,
+        "is_unlocking": false,
+        "is_locked": true,
+    }]);
+    testing_env!(
+        context.predecessor_account_id(accounts(0)).build(),
+        near_sdk::VMConfig::test(),
+        near_sdk::RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![PromiseResult::Successful(locking_positions.to_string().into_bytes())],
+    );
+    contract.buyer_tier_deposit_resolve(buyer_id, Some(usdt_token_contract()), U128::from(amount), 0)
+}
+
+#[test]
+fn test_usdt_deposit_tiered_sale() {
+    let (mut context, mut contract) = abstract_tiered_sale();
+
+    testing_env!(context
+        .predecessor_account_id(usdt_token_contract())
+        .block_timestamp(to_ts(0))
+        .build()
+    );
+    contract.ft_on_transfer(accounts(1), U128::from(USDT_UNIT), 0.to_string());
+    let unused = resolve_tier_deposit(&mut context, &mut contract, accounts(1), USDT_UNIT, 10 * NEAR);
+    assert_eq!(0, unused.0);
+    assert_eq!(USDT_UNIT, contract.get_buyer_deposit(accounts(1), 0).0);
+
+    // Bronze allocation is used.
+    let unused = resolve_tier_deposit(&mut context, &mut contract, accounts(1), USDT_UNIT, 10 * NEAR);
+    assert_eq!(USDT_UNIT, unused.0);
+    assert_eq!(USDT_UNIT, contract.get_buyer_deposit(accounts(1), 0).0);
+
+    // Under the Bronze tier.
+    let unused = resolve_tier_deposit(&mut context, &mut contract, accounts(2), USDT_UNIT, NEAR);
+    assert_eq!(USDT_UNIT, unused.0);
+    assert_eq!(0, contract.get_buyer_deposit(accounts(2), 0).0);
+}
+
+#[test]
+fn test_usdt_deposit_tiered_sale_early_access() {
+    let (mut context, mut contract) = abstract_tiered_sale();
+    let early = to_ts(0) - to_nanos(1) / 2;
+
+    testing_env!(context
+        .predecessor_account_id(usdt_token_contract())
+        .block_timestamp(early)
+        .build()
+    );
+    contract.ft_on_transfer(accounts(1), U128::from(3 * USDT_UNIT), 0.to_string());
+    let unused = resolve_tier_deposit(&mut context, &mut contract, accounts(1), 3 * USDT_UNIT, 100 * NEAR);
+    assert_eq!(0, unused.0);
+    assert_eq!(3 * USDT_UNIT, contract.get_buyer_deposit(accounts(1), 0).0);
+
+    // Bronze buyers wait for the open date.
+    let unused = resolve_tier_deposit(&mut context, &mut contract, accounts(2), USDT_UNIT, 10 * NEAR);
+    assert_eq!(USDT_UNIT, unused.0);
+    assert_eq!(0, contract.get_buyer_deposit(accounts(2), 0).0);
+}
+
+#[test]
+#[should_panic(expected = "Staking position contract is not set.")]
+fn test_fail_create_tiered_sale_without_staking() {
+    let mut context = get_context(owner_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(STORAGE_PER_SALE)
+        .build()
+    );
+    create_sale_with_options(
+        &mut contract,
+        "test-sale-1",
+        false,
+        SaleOptions {
+            tiers: vec![SaleTier {
+                name: String::from("Bronze"),
+                min_voting_power: U128::from(NEAR),
+                max_allocation: U128::from(USDT_UNIT),
+                early_access: U64::from(0),
+            }],
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn test_near_deposit_dutch_auction() {
+    let mut context = get_context(owner_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(STORAGE_PER_SALE)
+        .build()
+    );
+    create_sale_with_options(
+        &mut contract,
+        "test-sale-1",
+        true,
+        SaleOptions {
+            mode: SaleMode::DutchAuction { start_purchase_rate: U128::from(NEAR) },
+            ..Default::default()
+        }
+    );
+
+    // The price falls from 1 to 0.5 NEAR per sold token.
+    testing_env!(context
+        .predecessor_account_id(accounts(1))
+        .attached_deposit(2 * NEAR)
+        .block_timestamp(to_ts(0))
+        .build()
+    );
+    assert_eq!(NEAR, contract.get_current_purchase_rate(0).0);
+    contract.purchase_token_with_near(0, None);
+    assert_eq!(2 * NEAR, contract.get_buyer_claimable_sold_token(accounts(1), 0).0);
+
+    testing_env!(context
+        .predecessor_account_id(accounts(2))
+        .attached_deposit(2 * NEAR)
+        .block_timestamp(to_ts(5))
+        .build()
+    );
+    assert_eq!(3 * NEAR / 2, contract.get_current_purchase_rate(0).0);
+    contract.purchase_token_with_near(0, None);
+    assert_eq!(3 * NEAR, contract.get_buyer_claimable_sold_token(accounts(2), 0).0);
+    assert_eq!(5 * NEAR, contract.sales.get(0).unwrap().required_sold_token);
+}
+
+fn abstract_batch_auction() -> (VMContextBuilder, KatherineSaleContract) {
+    let mut context = get_context(owner_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(STORAGE_PER_SALE)
+        .build()
+    );
+    // Price from 1 NEAR down to 0.5 NEAR per sold token.
+    create_sale_with_options(
+        &mut contract,
+        "test-sale-1",
+        true,
+        SaleOptions {
+            mode: SaleMode::BatchAuction { max_price_purchase_rate: U128::from(NEAR) },
+            ..Default::default()
+        }
+    );
+    (context, contract)
+}
+
+#[test]
+fn test_near_deposit_batch_auction() {
+    let (mut context, mut contract) = abstract_batch_auction();
+
+    // 8 NEAR for 10 sold tokens, clearing price is 0.8 NEAR.
+    testing_env!(context
+        .predecessor_account_id(accounts(1))
+        .attached_deposit(4 * NEAR)
+        .block_timestamp(to_ts(0))
+        .build()
+    );
+    contract.purchase_token_with_near(0, None);
+    testing_env!(context
+        .predecessor_account_id(accounts(2))
+        .attached_deposit(4 * NEAR)
+        .block_timestamp(to_ts(1))
+        .build()
+    );
+    contract.purchase_token_with_near(0, None);
+    assert_eq!(5 * NEAR / 4, contract.get_current_purchase_rate(0).0);
+
+    testing_env!(context
+        .predecessor_account_id(sold_token_contract())
+        .attached_deposit(0)
+        .block_timestamp(to_ts(2))
+        .build()
+    );
+    contract.ft_on_transfer(accounts(3), U128::from(10 * NEAR), 0.to_string());
+
+    testing_env!(context
+        .predecessor_account_id(seller_account())
+        .block_timestamp(to_ts(11))
+        .build()
+    );
+    let (allocation, refund) = contract.get_buyer_allocation(accounts(1), 0);
+    assert_eq!(5 * NEAR, allocation.0);
+    assert_eq!(0, refund[0].amount.0);
+    contract.collect_payments(0);
+    let sale = contract.sales.get(0).unwrap();
+    assert_eq!(8 * NEAR, sale.total_requested_sold_token);
+    assert_eq!(10 * NEAR, sale.required_sold_token);
+    assert_eq!(0, sale.sale_payment_config().excess_payment_token);
+
+    testing_env!(context
+        .predecessor_account_id(accounts(2))
+        .block_timestamp(to_ts(16))
+        .build()
+    );
+    contract.withdraw_tokens(0);
+    let sale = contract.sales.get(0).unwrap();
+    assert_eq!(5 * NEAR, sale.required_sold_token);
+    assert_eq!(5 * NEAR, sale.sold_tokens_for_buyers);
+}
+
+#[test]
+fn test_near_deposit_batch_auction_over_max_price() {
+    let (mut context, mut contract) = abstract_batch_auction();
+
+    // 15 sold tokens requested at the max price, 10 available.
+    testing_env!(context
+        .predecessor_account_id(accounts(1))
+        .attached_deposit(6 * NEAR)
+        .block_timestamp(to_ts(0))
+        .build()
+    );
+    contract.purchase_token_with_near(0, None);
+    testing_env!(context
+        .predecessor_account_id(accounts(2))
+        .attached_deposit(9 * NEAR)
+        .block_timestamp(to_ts(1))
+        .build()
+    );
+    contract.purchase_token_with_near(0, None);
+    assert_eq!(NEAR, contract.get_current_purchase_rate(0).0);
+
+    let (allocation, refund) = contract.get_buyer_allocation(accounts(1), 0);
+    assert_eq!(4 * NEAR, allocation.0);
+    assert_eq!(2 * NEAR, refund[0].amount.0);
+}
+
+#[test]
+#[should_panic(expected = "Auction price must be over the floor price.")]
+fn test_fail_create_dutch_auction_under_floor_price() {
+    let mut context = get_context(owner_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(STORAGE_PER_SALE)
+        .build()
+    );
+    create_sale_with_options(
+        &mut contract,
+        "test-sale-1",
+        true,
+        SaleOptions {
+            mode: SaleMode::DutchAuction { start_purchase_rate: U128::from(3 * NEAR) },
+            ..Default::default()
+        }
+    );
+}
+
+fn create_sale_with_vesting(mut options: SaleOptions) {
+    let mut context = get_context(owner_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(STORAGE_PER_SALE)
+        .build()
+    );
+    options.vesting = Some(VestingConfig {
+        tge_release: 2_500,
+        cliff_duration: U64::from(nanos_to_millis(to_nanos(5))),
+        vesting_duration: U64::from(nanos_to_millis(to_nanos(10))),
+    });
+    create_sale_with_options(&mut contract, "test-sale-1", true, options);
+}
+
+#[test]
+#[should_panic(expected = "Vesting is not supported for oversubscribed sales.")]
+fn test_fail_create_oversubscribed_sale_with_vesting() {
+    create_sale_with_vesting(SaleOptions {
+        oversubscribed: true,
+        ..Default::default()
+    });
+}
+
+#[test]
+#[should_panic(expected = "Vesting is not supported for batch auctions.")]
+fn test_fail_create_batch_auction_with_vesting() {
+    create_sale_with_vesting(SaleOptions {
+        mode: SaleMode::BatchAuction { max_price_purchase_rate: U128::from(NEAR) },
+        ..Default::default()
+    });
+}
+
+/// NEAR sale also accepting USDT, 1 sold token for 1 USDT.
+fn abstract_multi_token_sale() -> (VMContextBuilder, KatherineSaleContract) {
+    let mut context = get_context(owner_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(STORAGE_PER_SALE)
+        .build()
+    );
+    create_sale_with_options(
+        &mut contract,
+        "test-sale-1",
+        true,
+        SaleOptions {
+            payment_tokens: BTreeMap::from([(
+                usdt_token_contract().to_string(),
+                AcceptedPaymentToken {
+                    one_payment_token_purchase_rate: U128::from(NEAR),
+                    min_deposit_amount: U128::from(MIN_DEPOSIT_AMOUNT_IN_PAYMENT_TOKEN),
+                    payment_token_unit: U128::from(USDT_UNIT),
+                }
+            )]),
+            ..Default::default()
+        }
+    );
+    (context, contract)
+}
+
+#[test]
+fn test_multi_token_deposit_with_collect() {
+    let (mut context, mut contract) = abstract_multi_token_sale();
+
+    testing_env!(context
+        .predecessor_account_id(usdt_token_contract())
+        .block_timestamp(to_ts(0))
+        .build()
+    );
+    contract.ft_on_transfer(accounts(1), U128::from(2 * USDT_UNIT), 0.to_string());
+    testing_env!(context
+        .predecessor_account_id(accounts(2))
+        .attached_deposit(NEAR)
+        .block_timestamp(to_ts(1))
+        .build()
+    );
+    contract.purchase_token_with_near(0, None);
+
+    assert_eq!(2 * NEAR, contract.get_buyer_claimable_sold_token(accounts(1), 0).0);
+    assert_eq!(2 * NEAR, contract.get_buyer_claimable_sold_token(accounts(2), 0).0);
+    assert_eq!(
+        vec![PaymentTokenAmount {
+            payment_token_contract_address: Some(usdt_token_contract()),
+            amount: U128::from(2 * USDT_UNIT),
+        }],
+        contract.get_buyer_deposits(accounts(1), 0)
+    );
+    // 2 USDT buy the same sold tokens as 1 NEAR.
+    assert_eq!(NEAR, contract.get_buyer_deposit(accounts(1), 0).0);
+    assert_eq!(NEAR, contract.get_buyer_deposit(accounts(2), 0).0);
+    let sale = contract.sales.get(0).unwrap();
+    assert_eq!(NEAR, sale.sale_payment_config().total_payment_token);
+    assert_eq!(2 * USDT_UNIT, sale.get_payment_config(&Some(usdt_token_contract())).total_payment_token);
+    // USDT deposits are valued in NEAR.
+    assert_eq!(2 * NEAR, sale.total_raised);
+
+    testing_env!(context
+        .predecessor_account_id(sold_token_contract())
+        .attached_deposit(0)
+        .block_timestamp(to_ts(2))
+        .build()
+    );
+    contract.ft_on_transfer(accounts(3), U128::from(4 * NEAR), 0.to_string());
+
+    testing_env!(context
+        .predecessor_account_id(seller_account())
+        .block_timestamp(to_ts(11))
+        .build()
+    );
+    contract.collect_payments(0);
+    let sale = contract.sales.get(0).unwrap();
+    assert_eq!(0, sale.sale_payment_config().total_payment_token);
+    assert_eq!(0, sale.get_payment_config(&Some(usdt_token_contract())).total_payment_token);
+    assert_eq!(NEAR / 40, contract.get_sale_fee(0, None).0);
+    assert_eq!(USDT_UNIT / 20, contract.get_sale_fee(0, Some(usdt_token_contract())).0);
+}
+
+#[test]
+fn test_multi_token_deposit_same_buyer_with_refund() {
+    let (mut context, mut contract) = abstract_multi_token_sale();
+
+    testing_env!(context
+        .predecessor_account_id(usdt_token_contract())
+        .block_timestamp(to_ts(0))
+        .build()
+    );
+    contract.ft_on_transfer(accounts(1), U128::from(2 * USDT_UNIT), 0.to_string());
+    testing_env!(context
+        .predecessor_account_id(accounts(1))
+        .attached_deposit(NEAR)
+        .block_timestamp(to_ts(1))
+        .build()
+    );
+    contract.purchase_token_with_near(0, None);
+
+    // Both deposits count for the buyer, valued in NEAR.
+    assert_eq!(2, contract.get_buyer_deposits(accounts(1), 0).len());
+    assert_eq!(2 * NEAR, contract.get_buyer_deposit(accounts(1), 0).0);
+    assert_eq!(4 * NEAR, contract.get_buyer_claimable_sold_token(accounts(1), 0).0);
+    assert_eq!(1, contract.get_number_of_buyers_for_sale(0));
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(1)
+        .block_timestamp(to_ts(2))
+        .build()
+    );
+    contract.cancel_sale(0, String::from("Project pulled out."));
+
+    // Every payment token is refunded.
+    testing_env!(context
+        .predecessor_account_id(accounts(1))
+        .attached_deposit(0)
+        .block_timestamp(to_ts(3))
+        .build()
+    );
+    contract.withdraw_tokens(0);
+    assert!(contract.get_buyer_deposits(accounts(1), 0).is_empty());
+    let sale = contract.sales.get(0).unwrap();
+    assert_eq!(0, sale.required_sold_token);
+    assert_eq!(0, sale.get_payment_config(&None).total_payment_token);
+    assert_eq!(0, sale.get_payment_config(&Some(usdt_token_contract())).total_payment_token);
+
+    // The USDT refund failed, the USDT deposit and its claimable tokens are recovered.
+    testing_env!(
+        context.predecessor_account_id(accounts(0)).build(),
+        near_sdk::VMConfig::test(),
+        near_sdk::RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![PromiseResult::Failed],
+    );
+    contract.buyer_withdraw_payment_tokens_resolve(
+        &accounts(1),
+        &usdt_token_contract(),
+        U128::from(2 * NEAR),
+        U128::from(2 * USDT_UNIT),
+        0
+    );
+    assert_eq!(NEAR, contract.get_buyer_deposit(accounts(1), 0).0);
+    assert_eq!(2 * NEAR, contract.get_buyer_claimable_sold_token(accounts(1), 0).0);
+    let sale = contract.sales.get(0).unwrap();
+    assert_eq!(2 * NEAR, sale.required_sold_token);
+    assert_eq!(2 * USDT_UNIT, sale.get_payment_config(&Some(usdt_token_contract())).total_payment_token);
+}
+
+#[test]
+#[should_panic(expected = "Duplicated payment token.")]
+fn test_fail_create_sale_duplicated_payment_token() {
+    let mut context = get_context(owner_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(STORAGE_PER_SALE)
+        .build()
+    );
+    create_sale_with_options(
+        &mut contract,
+        "test-sale-1",
+        true,
+        SaleOptions {
+            payment_tokens: BTreeMap::from([(
+                String::from(NEAR_PAYMENT_TOKEN),
+                AcceptedPaymentToken {
+                    one_payment_token_purchase_rate: U128::from(NEAR),
+                    min_deposit_amount: U128::from(MIN_DEPOSIT_AMOUNT_IN_NEAR),
+                    payment_token_unit: U128::from(NEAR),
+                }
+            )]),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn test_cancel_sale_with_refunds() {
+    let (mut context, mut contract) = abstract_near_deposit();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(1)
+        .block_timestamp(to_ts(5))
+        .build()
+    );
+    contract.cancel_sale(0, String::from("Project pulled out."));
+    let sale = contract.get_sale(0);
+    assert!(sale.is_canceled);
+    assert!(!sale.is_active);
+    assert_eq!(Some(String::from("Project pulled out.")), sale.cancel_reason);
+    // Sold tokens are returned to the seller.
+    assert_eq!(0, sale.sold_tokens_for_buyers.0);
+
+    // Buyers withdraw the deposit before the close date.
+    testing_env!(context
+        .predecessor_account_id(accounts(1))
+        .attached_deposit(0)
+        .block_timestamp(to_ts(6))
+        .build()
+    );
+    contract.withdraw_tokens(0);
+    let sale = contract.sales.get(0).unwrap();
+    assert_eq!(NEAR, sale.sale_payment_config().total_payment_token);
+    assert_eq!(2 * NEAR, sale.required_sold_token);
+    assert_eq!(0, contract.get_buyer_deposit(accounts(1), 0).0);
+}
+
+#[test]
+#[should_panic(expected = "Not within the funding period.")]
+fn test_fail_near_deposit_canceled_sale() {
+    let (mut context, mut contract) = abstract_near_deposit();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(1)
+        .block_timestamp(to_ts(5))
+        .build()
+    );
+    contract.cancel_sale(0, String::from("Project pulled out."));
+
+    testing_env!(context
+        .predecessor_account_id(accounts(2))
+        .attached_deposit(NEAR)
+        .block_timestamp(to_ts(6))
+        .build()
+    );
+    contract.purchase_token_with_near(0, None);
+}
+
+#[test]
+#[should_panic(expected = "Only before release period.")]
+fn test_fail_cancel_sale_after_release() {
+    let (mut context, mut contract) = abstract_near_deposit();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(1)
+        .block_timestamp(to_ts(16))
+        .build()
+    );
+    contract.cancel_sale(0, String::from("Too late."));
+}
+
+fn abstract_early_exit_sale(is_in_near: bool) -> (VMContextBuilder, KatherineSaleContract) {
+    let mut context = get_context(owner_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(STORAGE_PER_SALE)
+        .build()
+    );
+    create_sale_with_options(
+        &mut contract,
+        "test-sale-1",
+        is_in_near,
+        SaleOptions {
+            allow_early_exit: true,
+            // 1% exit fee.
+            early_exit_fee: 100,
+            ..Default::default()
+        }
+    );
+    (context, contract)
+}
+
+#[test]
+fn test_near_deposit_early_exit() {
+    let (mut context, mut contract) = abstract_early_exit_sale(true);
+
+    testing_env!(context
+        .predecessor_account_id(accounts(1))
+        .attached_deposit(3 * NEAR)
+        .block_timestamp(to_ts(0))
+        .build()
+    );
+    contract.purchase_token_with_near(0, None);
+
+    testing_env!(context
+        .predecessor_account_id(accounts(1))
+        .attached_deposit(0)
+        .block_timestamp(to_ts(2))
+        .build()
+    );
+    contract.withdraw_deposit(0, None, U128::from(NEAR));
+    assert_eq!(2 * NEAR, contract.get_buyer_deposit(accounts(1), 0).0);
+    assert_eq!(4 * NEAR, contract.get_buyer_claimable_sold_token(accounts(1), 0).0);
+    let sale = contract.sales.get(0).unwrap();
+    assert_eq!(4 * NEAR, sale.required_sold_token);
+    assert_eq!(2 * NEAR, sale.total_raised);
+    assert_eq!(2 * NEAR, sale.sale_payment_config().total_payment_token);
+    assert_eq!(NEAR / 100, contract.get_sale_fee(0, None).0);
+
+    // Full exit, the buyer leaves the sale.
+    contract.withdraw_deposit(0, None, U128::from(2 * NEAR));
+    assert_eq!(0, contract.get_buyer_deposit(accounts(1), 0).0);
+    assert_eq!(0, contract.get_buyer_claimable_sold_token(accounts(1), 0).0);
+    assert!(contract.get_buyer_sales(accounts(1)).is_empty());
+    let sale = contract.sales.get(0).unwrap();
+    assert_eq!(0, sale.required_sold_token);
+    assert_eq!(0, sale.sale_payment_config().total_payment_token);
+    assert_eq!(3 * NEAR / 100, contract.get_sale_fee(0, None).0);
+}
+
+#[test]
+fn test_usdt_deposit_early_exit_failed_refund() {
+    let (mut context, mut contract) = abstract_early_exit_sale(false);
+
+    testing_env!(context
+        .predecessor_account_id(usdt_token_contract())
+        .block_timestamp(to_ts(0))
+        .build()
+    );
+    contract.ft_on_transfer(accounts(1), U128::from(4 * USDT_UNIT), 0.to_string());
+
+    testing_env!(context
+        .predecessor_account_id(accounts(1))
+        .block_timestamp(to_ts(2))
+        .build()
+    );
+    contract.withdraw_deposit(0, Some(usdt_token_contract()), U128::from(USDT_UNIT));
+    assert_eq!(3 * USDT_UNIT, contract.get_buyer_deposit(accounts(1), 0).0);
+    assert_eq!(6 * NEAR, contract.get_buyer_claimable_sold_token(accounts(1), 0).0);
+    assert_eq!(USDT_UNIT / 100, contract.get_sale_fee(0, Some(usdt_token_contract())).0);
+
+    // The refund transfer failed, the deposit is recovered.
+    testing_env!(
+        context.predecessor_account_id(accounts(0)).build(),
+        near_sdk::VMConfig::test(),
+        near_sdk::RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![PromiseResult::Failed],
+    );
+    contract.buyer_withdraw_deposit_resolve(
+        &accounts(1),
+        &usdt_token_contract(),
+        U128::from(USDT_UNIT),
+        U128::from(USDT_UNIT / 100),
+        U128::from(2 * NEAR),
+        0
+    );
+    assert_eq!(4 * USDT_UNIT, contract.get_buyer_deposit(accounts(1), 0).0);
+    assert_eq!(8 * NEAR, contract.get_buyer_claimable_sold_token(accounts(1), 0).0);
+    let sale = contract.sales.get(0).unwrap();
+    assert_eq!(8 * NEAR, sale.required_sold_token);
+    assert_eq!(4 * USDT_UNIT, sale.total_raised);
+    assert_eq!(4 * USDT_UNIT, sale.sale_payment_config().total_payment_token);
+    assert_eq!(0, contract.get_sale_fee(0, Some(usdt_token_contract())).0);
+}
+
+#[test]
+#[should_panic(expected = "Invalid early exit fee.")]
+fn test_fail_create_sale_early_exit_fee_too_high() {
+    let mut context = get_context(owner_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(STORAGE_PER_SALE)
+        .build()
+    );
+    create_sale_with_options(
+        &mut contract,
+        "test-sale-1",
+        true,
+        SaleOptions {
+            allow_early_exit: true,
+            early_exit_fee: BASIS_POINT,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "Early exit is not allowed.")]
+fn test_fail_withdraw_deposit_without_early_exit() {
+    let (mut context, mut contract) = abstract_near_deposit();
+
+    testing_env!(context
+        .predecessor_account_id(accounts(1))
+        .attached_deposit(0)
+        .block_timestamp(to_ts(2))
+        .build()
+    );
+    contract.withdraw_deposit(0, None, U128::from(NEAR));
+}
+
+#[test]
+fn test_seller_collect_payments_to_payout() {
+    let (mut context, mut contract) = abstract_near_deposit();
+
+    testing_env!(context.is_view(true).build());
+    let sale = contract.get_sale(0);
+    assert_eq!(seller_account(), sale.seller_id);
+    assert_eq!(payout_account(), sale.payout_id);
+
+    testing_env!(context
+        .predecessor_account_id(seller_account())
+        .is_view(false)
+        .block_timestamp(to_ts(11))
+        .build()
+    );
+    contract.collect_payments(0);
+    // The payout receives 4 NEAR net of the 2.5% sale fee.
+    assert_eq!(4 * NEAR / 40, contract.get_sale_fee(0, None).0);
+
+    // The owner only collects the fees.
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .block_timestamp(to_ts(12))
+        .build()
+    );
+    contract.collect_fees(0);
+    assert_eq!(0, contract.get_sale_fee(0, None).0);
+}
+
+#[test]
+#[should_panic(expected = "Only seller.")]
+fn test_fail_owner_collect_payments() {
+    let (mut context, mut contract) = abstract_near_deposit();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .block_timestamp(to_ts(11))
+        .build()
+    );
+    contract.collect_payments(0);
+}
+
+#[test]
+#[should_panic(expected = "Only seller.")]
+fn test_fail_owner_withdraw_excess_sold_tokens() {
+    let (mut context, mut contract) = abstract_near_deposit();
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .block_timestamp(to_ts(16))
+        .build()
+    );
+    contract.withdraw_excess_sold_tokens(0);
+}
+
+#[test]
+fn test_seller_submit_sale_pending_until_approved() {
+    let mut context = get_context(seller_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+
+    testing_env!(context.attached_deposit(STORAGE_PER_SALE).build());
+    submit_sale(&mut contract, "test-sale-1", true, SaleOptions::default());
+    let sale = contract.get_sale(0);
+    assert_eq!(seller_account(), sale.seller_id);
+    assert!(!sale.is_approved);
+    assert!(!sale.is_active);
+    assert!(contract.get_active_sales(0, 10).is_empty());
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(1)
+        .build()
+    );
+    contract.approve_sale(0);
+    let sale = contract.get_sale(0);
+    assert!(sale.is_approved);
+    assert!(sale.is_active);
+    assert_eq!(1, contract.get_active_sales(0, 10).len());
+}
+
+#[test]
+fn test_owner_create_sale_is_approved() {
+    let mut context = get_context(owner_account());
+    testing_env!(context.attached_deposit(STORAGE_PER_SALE).build());
+    let mut contract = new_katherine_contract();
+
+    submit_sale(&mut contract, "test-sale-1", true, SaleOptions::default());
+    let sale = contract.get_sale(0);
+    assert_eq!(owner_account(), sale.seller_id);
+    assert!(sale.is_approved);
+}
+
+#[test]
+#[should_panic(expected = "Not within the funding period.")]
+fn test_fail_near_deposit_pending_sale() {
+    let mut context = get_context(seller_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+
+    testing_env!(context.attached_deposit(STORAGE_PER_SALE).build());
+    submit_sale(&mut contract, "test-sale-1", true, SaleOptions::default());
+
+    testing_env!(context
+        .predecessor_account_id(accounts(1))
+        .attached_deposit(NEAR)
+        .block_timestamp(to_ts(1))
+        .build()
+    );
+    contract.purchase_token_with_near(0, None);
+}
+
+#[test]
+#[should_panic(expected = "Only owner.")]
+fn test_fail_seller_approve_sale() {
+    let mut context = get_context(seller_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+
+    testing_env!(context.attached_deposit(STORAGE_PER_SALE).build());
+    submit_sale(&mut contract, "test-sale-1", true, SaleOptions::default());
+
+    testing_env!(context.attached_deposit(1).build());
+    contract.approve_sale(0);
+}
+
+#[test]
+fn test_rejected_sale_releases_slug() {
+    let mut context = get_context(seller_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+
+    testing_env!(context.attached_deposit(STORAGE_PER_SALE).build());
+    submit_sale(&mut contract, "test-sale-1", true, SaleOptions::default());
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(1)
+        .build()
+    );
+    contract.cancel_sale(0, String::from("Rejected."));
+
+    testing_env!(context
+        .predecessor_account_id(seller_account())
+        .attached_deposit(STORAGE_PER_SALE)
+        .build()
+    );
+    let sale_id = submit_sale(&mut contract, "test-sale-1", true, SaleOptions::default());
+    assert_eq!(1, sale_id);
+    assert_eq!(1, contract.get_sale_id_from_slug(String::from("test-sale-1")));
+}
+
+#[test]
+#[should_panic(expected = "Slug already exists. Choose a different one!")]
+fn test_fail_canceled_approved_sale_keeps_slug() {
+    let mut context = get_context(owner_account());
+    testing_env!(context.build());
+    let mut contract = new_katherine_contract();
+    create_sale(&mut contract, "test-sale-1", true);
+
+    testing_env!(context
+        .predecessor_account_id(owner_account())
+        .attached_deposit(1)
+        .build()
+    );
+    contract.cancel_sale(0, String::from("Project pulled out."));
+
+    testing_env!(context.attached_deposit(STORAGE_PER_SALE).build());
+    submit_sale(&mut contract, "test-sale-1", true, SaleOptions::default());
 }
@@ -23,10 +23,22 @@ pub fn treasury_account() -> AccountId {
     AccountId::new_unchecked("treasury.katherine.near".to_string())
 }
 
+pub fn staking_position_contract() -> AccountId {
+    AccountId::new_unchecked("staking.katherine.near".to_string())
+}
+
 pub fn owner_account() -> AccountId {
     AccountId::new_unchecked("owner.katherine.near".to_string())
 }
 
+pub fn seller_account() -> AccountId {
+    AccountId::new_unchecked("seller.katherine.near".to_string())
+}
+
+pub fn payout_account() -> AccountId {
+    AccountId::new_unchecked("payout.katherine.near".to_string())
+}
+
 // pub fn ntoy(near_amount: u128) -> u128 {
 //     return near_amount * 10u128.pow(24);
 // }
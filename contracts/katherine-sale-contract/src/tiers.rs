@@ -0,0 +1,173 @@
+use crate::*;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json::json;
+use near_sdk::{env, log, near_bindgen, require, Promise};
+
+/// Staking tier of a sale, e.g. Bronze, Silver and Gold. The buyer tier is the
+/// highest one with `min_voting_power` under the buyer locked voting power.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SaleTier {
+    pub name: String,
+    /// Locked voting power required in the staking position contract.
+    pub min_voting_power: U128,
+    /// Guaranteed max deposit of the tier buyers, in the sale payment token.
+    pub max_allocation: U128,
+    /// Time before the `open_date_timestamp` when the tier buyers can deposit.
+    pub early_access: U64,
+}
+
+/// Subset of the staking position contract `LockingPositionJSON`.
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LockingPositionJSON {
+    pub voting_power: U128,
+    pub is_locked: bool,
+}
+
+pub(crate) fn assert_valid_tiers(tiers: &[SaleTier]) {
+    require!(tiers.len() <= MAX_SALE_TIERS, "Too many sale tiers.");
+    for (index, tier) in tiers.iter().enumerate() {
+        require!(tier.max_allocation.0 > 0, "Invalid tier max allocation.");
+        require!(
+            index == 0 || tiers[index - 1].min_voting_power.0 < tier.min_voting_power.0,
+            "Tiers must be sorted by min voting power."
+        );
+    }
+}
+
+#[near_bindgen]
+impl KatherineSaleContract {
+    /// Complete the deposit of a tiered sale once the buyer voting power is known.
+    /// If the deposit is rejected, the payment is refunded.
+    #[private]
+    pub fn buyer_tier_deposit_resolve(
+        &mut self,
+        buyer_id: AccountId,
+        token_id: Option<AccountId>,
+        amount: U128,
+        sale_id: u32
+    ) -> U128 {
+        let amount = amount.0;
+        let mut sale = self.internal_get_sale(sale_id);
+
+        let result = match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(result) => {
+                near_sdk::serde_json::from_slice::<Vec<LockingPositionJSON>>(&result)
+                    .map(|locking_positions| {
+                        locking_positions
+                            .iter()
+                            .filter(|locking_position| locking_position.is_locked)
+                            .map(|locking_position| locking_position.voting_power.0)
+                            .sum::<Balance>()
+                    })
+                    .map_err(|_| String::from("Invalid locking positions."))
+            },
+            PromiseResult::Failed => Err(String::from("Staking position is not available.")),
+        }
+        .and_then(|voting_power| {
+            let tier = sale.get_tier(voting_power).ok_or_else(|| {
+                format!("Locked voting power {} is under the sale tiers.", voting_power)
+            })?;
+            // Tiers are not supported for private sales, no allowlist proof is needed.
+            sale.check_deposit(&buyer_id, &token_id, amount, None, Some(tier))
+        });
+
+        match result {
+            Ok(_) => {
+                self.internal_register_deposit(&buyer_id, token_id, amount, &mut sale);
+                log!(
+                    "DEPOSIT: {} payment tokens deposited from {} to sale {}",
+                    amount,
+                    &buyer_id,
+                    sale_id
+                );
+                // Return unused amount
+                U128::from(0)
+            },
+            Err(err) => {
+                log!(
+                    "REFUND: {} payment tokens returned to {}. {}",
+                    amount,
+                    &buyer_id,
+                    err
+                );
+                // The FT refunds are done by the token contract in `ft_resolve_transfer`.
+                if token_id.is_none() {
+                    Promise::new(buyer_id).transfer(amount);
+                }
+                U128::from(amount)
+            }
+        }
+    }
+
+    /// Tier of a buyer with the given locked voting power.
+    pub fn get_sale_tier(&self, sale_id: u32, voting_power: U128) -> Option<SaleTier> {
+        let sale = self.internal_get_sale(sale_id);
+        sale.get_tier(voting_power.0).cloned()
+    }
+}
+
+impl KatherineSaleContract {
+    /// For tiered sales, the buyer locked voting power is verified before the deposit.
+    pub(crate) fn internal_verify_tier_deposit(
+        &self,
+        buyer_id: AccountId,
+        token_id: Option<AccountId>,
+        amount: Balance,
+        sale: &Sale,
+    ) -> Promise {
+        // Fail before the cross-contract call, `check_deposit` runs in the callback.
+        sale.assert_min_deposit_amount(&token_id, amount);
+        require!(
+            sale.is_within_tier_funding_period(),
+            "Not within the funding period."
+        );
+        let staking_position_contract_address = self.staking_position_contract_address
+            .clone()
+            .expect("Staking position contract is not set.");
+
+        Promise::new(staking_position_contract_address)
+            .function_call(
+                String::from("get_all_locking_positions"),
+                json!({ "account_id": buyer_id }).to_string().into_bytes(),
+                0,
+                GAS_FOR_GET_VOTING_POWER
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_TIER_DEPOSIT)
+                    .buyer_tier_deposit_resolve(buyer_id, token_id, U128::from(amount), sale.id)
+            )
+    }
+}
+
+impl Sale {
+    #[inline]
+    pub(crate) fn has_tiers(&self) -> bool {
+        !self.tiers.is_empty()
+    }
+
+    pub(crate) fn get_tier(&self, voting_power: Balance) -> Option<&SaleTier> {
+        self.tiers
+            .iter()
+            .rev()
+            .find(|tier| tier.min_voting_power.0 <= voting_power)
+    }
+
+    /// The funding period starts with the earliest access of all tiers.
+    pub(crate) fn is_within_tier_funding_period(&self) -> bool {
+        let early_access = self.tiers
+            .iter()
+            .map(|tier| tier.early_access.0)
+            .max()
+            .unwrap_or(0);
+        let now = get_current_epoch_millis();
+        now < self.close_date_timestamp
+            && now >= self.open_date_timestamp.saturating_sub(early_access)
+            && self.accepts_deposits()
+    }
+}
@@ -1,9 +1,13 @@
 use near_sdk::{BorshStorageKey, CryptoHash};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::AccountId;
-use near_sdk::json_types::{U128, U64};
+use near_sdk::json_types::{Base58CryptoHash, U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
 use uint::construct_uint;
+use crate::auction::SaleMode;
+use crate::payment::PaymentConfigJSON;
+use crate::tiers::SaleTier;
+use crate::vesting::VestingConfig;
 
 pub type BasisPoints = u32;
 pub type EpochMillis = u64;
@@ -22,6 +26,9 @@ pub enum StorageKey {
     Buyers,
     Sales,
     SalesById,
+    ClaimedSoldTokens { hash_id: CryptoHash },
+    ExcessRefunds { hash_id: CryptoHash },
+    AllowlistAllocations { hash_id: CryptoHash },
 }
 
 #[derive(Serialize, Deserialize)]
@@ -30,9 +37,13 @@ pub struct SaleJSON {
     pub id: u32,
     pub slug: String,
     pub sold_token_contract_address: AccountId,
+    pub seller_id: AccountId,
+    pub payout_id: AccountId,
     pub max_available_sold_token: U128,
     pub required_sold_token: U128,
     pub total_payment_token: U128,
+    pub total_raised: U128,
+    pub soft_cap: U128,
     pub one_payment_token_purchase_rate: U128,
     pub open_date_timestamp: U64,
     pub close_date_timestamp: U64,
@@ -44,7 +55,21 @@ pub struct SaleJSON {
     pub payment_token_unit: U128,
     pub sale_fee: BasisPoints,
     pub total_fees: U128,
+    pub payment_tokens: Vec<PaymentConfigJSON>,
+    pub vesting: Option<VestingConfig>,
+    pub oversubscribed: bool,
+    pub total_requested_sold_token: U128,
+    pub allowlist_root: Option<Base58CryptoHash>,
+    pub tiers: Vec<SaleTier>,
+    pub mode: SaleMode,
+    pub current_purchase_rate: U128,
+    pub allow_early_exit: bool,
+    pub early_exit_fee: BasisPoints,
     
     pub is_in_near: bool,
     pub is_active: bool,
+    pub is_approved: bool,
+    pub is_failed: bool,
+    pub is_canceled: bool,
+    pub cancel_reason: Option<String>,
 }
@@ -0,0 +1,199 @@
+use crate::*;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, log, near_bindgen, require, Promise};
+
+/// Release of the sold tokens after the `release_date_timestamp`:
+/// a TGE percentage at release, then a cliff, then linear vesting.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VestingConfig {
+    /// % of the tokens released at the release date.
+    pub tge_release: BasisPoints,
+    /// Time after the release date before the linear vesting starts.
+    pub cliff_duration: U64,
+    /// Duration of the linear vesting of the remaining tokens.
+    pub vesting_duration: U64,
+}
+
+impl VestingConfig {
+    pub(crate) fn assert_valid(&self) {
+        require!(self.tge_release <= BASIS_POINT, "Invalid TGE release.");
+    }
+
+    /// Tokens vested out of the `total` buyer allocation.
+    pub(crate) fn vested_amount(
+        &self,
+        total: Balance,
+        release_date_timestamp: EpochMillis,
+        now: EpochMillis,
+    ) -> Balance {
+        if now < release_date_timestamp {
+            return 0;
+        }
+        let tge = proportional(total, self.tge_release as u128, BASIS_POINT as u128);
+        let vesting_start = release_date_timestamp + self.cliff_duration.0;
+        if now < vesting_start {
+            return tge;
+        }
+        let elapsed = now - vesting_start;
+        if elapsed >= self.vesting_duration.0 {
+            return total;
+        }
+        tge + proportional(
+            total - tge,
+            elapsed as u128,
+            self.vesting_duration.0 as u128,
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BuyerVestingJSON {
+    pub total: U128,
+    pub vested: U128,
+    pub claimed: U128,
+    pub locked: U128,
+    pub claimable: U128,
+}
+
+#[near_bindgen]
+impl KatherineSaleContract {
+    /// Claim the sold tokens vested so far. Only for sales with vesting.
+    /// Only callable during `stage 3`, only if sold tokens are covered.
+    pub fn claim_vested(&mut self, sale_id: u32) -> Promise {
+        let mut sale = self.internal_get_sale(sale_id);
+        sale.assert_after_release_period();
+        require!(sale.vesting.is_some(), "Sale has no vesting.");
+        sale.assert_not_failed();
+        sale.assert_not_canceled();
+        require!(sale.are_sold_tokens_covered(), "Sold tokens are not covered.");
+
+        let buyer_id = env::predecessor_account_id();
+        let amount = sale.get_buyer_vested_claimable(&buyer_id);
+        require!(amount > 0, "No vested tokens to claim.");
+
+        let remaining = sale.get_buyer_claimable_sold_token(&buyer_id) - amount;
+        let claimed = sale.get_buyer_claimed_sold_token(&buyer_id) + amount;
+        sale.claimed_sold_token.insert(&buyer_id, &claimed);
+        // Important: Once everything is claimed, the buyer deposit is removed.
+        let mut removed_deposits = Vec::new();
+        if remaining == 0 {
+            sale.claimable_sold_token_for_buyers.remove(&buyer_id);
+            removed_deposits = sale.remove_buyer_deposits(&buyer_id);
+            require!(!removed_deposits.is_empty(), "No deposit.");
+            let mut buyer = self.internal_get_buyer(&buyer_id);
+            buyer.supporting_sales.remove(&sale.id);
+            self.buyers.insert(&buyer_id, &buyer);
+        } else {
+            sale.claimable_sold_token_for_buyers.insert(&buyer_id, &remaining);
+        }
+        sale.sold_tokens_for_buyers -= amount;
+        sale.required_sold_token -= amount;
+        self.sales.replace(sale.id as u64, &sale);
+
+        let amount = U128::from(amount);
+        let token_id = sale.get_sold_token();
+        ext_ft::ext(token_id.clone())
+            .with_static_gas(GAS_FOR_FT_TRANSFER)
+            .with_attached_deposit(1)
+            .ft_transfer(buyer_id.clone(), amount, None).then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
+                    .buyer_claim_vested_resolve(
+                        &buyer_id,
+                        &token_id,
+                        amount,
+                        removed_deposits,
+                        sale.id
+                    )
+            )
+    }
+
+    #[private]
+    pub fn buyer_claim_vested_resolve(
+        &mut self,
+        buyer_id: &AccountId,
+        token_id: &AccountId,
+        amount: U128,
+        removed_deposits: Vec<PaymentTokenAmount>,
+        sale_id: u32
+    ) {
+        let amount = amount.0;
+        let mut buyer = self.internal_get_buyer(buyer_id);
+
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(_) => {
+                if buyer.is_empty() {
+                    self.buyers.remove(buyer_id);
+                    log!("GODSPEED: {} is no longer part of Katherine!", &buyer_id);
+                }
+                log!(
+                    "CLAIM: {} vested tokens of sold-token {} transferred to {}",
+                    amount, token_id, buyer_id
+                );
+            },
+            PromiseResult::Failed => {
+                buyer.supporting_sales.insert(&sale_id);
+                self.buyers.insert(buyer_id, &buyer);
+
+                let mut sale = self.internal_get_sale(sale_id);
+                // Important: Recover the claimable tokens and deposit from user.
+                let claimable = sale.get_buyer_claimable_sold_token(buyer_id) + amount;
+                let claimed = sale.get_buyer_claimed_sold_token(buyer_id) - amount;
+                sale.claimable_sold_token_for_buyers.insert(buyer_id, &claimable);
+                sale.claimed_sold_token.insert(buyer_id, &claimed);
+                sale.restore_buyer_deposits(buyer_id, &removed_deposits);
+                sale.sold_tokens_for_buyers += amount;
+                sale.required_sold_token += amount;
+                self.sales.replace(sale.id as u64, &sale);
+                log!(
+                    "FAILED: {} tokens not transferred. Recovering sale {} state.",
+                    amount, sale_id
+                );
+            }
+        };
+    }
+
+    pub fn get_buyer_vesting(&self, buyer_id: AccountId, sale_id: u32) -> BuyerVestingJSON {
+        let sale = self.internal_get_sale(sale_id);
+        let claimed = sale.get_buyer_claimed_sold_token(&buyer_id);
+        let total = sale.get_buyer_claimable_sold_token(&buyer_id) + claimed;
+        let vested = sale.get_buyer_vested_amount(&buyer_id);
+        BuyerVestingJSON {
+            total: U128::from(total),
+            vested: U128::from(vested),
+            claimed: U128::from(claimed),
+            locked: U128::from(total - vested),
+            claimable: U128::from(vested - claimed),
+        }
+    }
+}
+
+impl Sale {
+    pub(crate) fn get_buyer_claimed_sold_token(&self, buyer_id: &AccountId) -> Balance {
+        self.claimed_sold_token.get(buyer_id).unwrap_or(0)
+    }
+
+    /// Without vesting, everything is vested at release.
+    pub(crate) fn get_buyer_vested_amount(&self, buyer_id: &AccountId) -> Balance {
+        let total = self.get_buyer_claimable_sold_token(buyer_id)
+            + self.get_buyer_claimed_sold_token(buyer_id);
+        match &self.vesting {
+            Some(vesting) => vesting.vested_amount(
+                total,
+                self.release_date_timestamp,
+                get_current_epoch_millis(),
+            ),
+            None if get_current_epoch_millis() >= self.release_date_timestamp => total,
+            None => 0,
+        }
+    }
+
+    pub(crate) fn get_buyer_vested_claimable(&self, buyer_id: &AccountId) -> Balance {
+        self.get_buyer_vested_amount(buyer_id) - self.get_buyer_claimed_sold_token(buyer_id)
+    }
+}
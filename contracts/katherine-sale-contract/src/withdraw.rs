@@ -7,7 +7,7 @@ use near_sdk::{Promise, env, log, near_bindgen};
 /// by withdraws:
 ///     - sale.sold_tokens_for_buyers
 ///     - sale.required_sold_token
-///     - sale.total_payment_token
+///     - sale.payment_configs[token].total_payment_token
 
 #[near_bindgen]
 impl KatherineSaleContract {
@@ -20,7 +20,7 @@ impl KatherineSaleContract {
         &mut self,
         buyer_id: AccountId,
         claimable: u128,
-        deposit: u128,
+        deposits: Vec<PaymentTokenAmount>,
         sale: &mut Sale
     ) -> Promise {
         // Removing claimable tokens. `total_payment_token` stays the same.
@@ -29,7 +29,6 @@ impl KatherineSaleContract {
         self.sales.replace(sale.id as u64, &sale);
 
         let claimable = U128::from(claimable);
-        let deposit = U128::from(deposit);
         let token_id = sale.get_sold_token();
         ext_ft::ext(token_id.clone())
             .with_static_gas(GAS_FOR_FT_TRANSFER)
@@ -41,7 +40,7 @@ impl KatherineSaleContract {
                         &buyer_id,
                         &token_id,
                         claimable,
-                        deposit,
+                        deposits,
                         sale.id
                     )
             )
@@ -53,7 +52,7 @@ impl KatherineSaleContract {
         buyer_id: &AccountId,
         token_id: &AccountId,
         claimable: U128,
-        deposit: U128,
+        deposits: Vec<PaymentTokenAmount>,
         sale_id: u32
     ) {
         let claimable = claimable.0;
@@ -76,8 +75,8 @@ impl KatherineSaleContract {
                 self.buyers.insert(&buyer_id, &buyer);
 
                 let mut sale = self.internal_get_sale(sale_id);
-                // Important: Recover the claimable tokens and deposit from user.
-                sale.deposits.insert(buyer_id, &deposit.0);
+                // Important: Recover the claimable tokens and deposits from user.
+                sale.restore_buyer_deposits(buyer_id, &deposits);
                 sale.claimable_sold_token_for_buyers.insert(&buyer_id, &claimable);
                 sale.sold_tokens_for_buyers += claimable;
                 sale.required_sold_token += claimable;
@@ -96,36 +95,59 @@ impl KatherineSaleContract {
 
     /// Payment tokens will only be returned to the buyer if the seller never
     /// deposited the full `required_sold_token` before the release date.
+    /// Every payment token of the buyer is returned with its share of the
+    /// claimable tokens, to recover them if the transfer fails.
     pub(crate) fn internal_buyer_withdraw_payment_token(
         &mut self,
         buyer_id: AccountId,
         claimable: u128,
-        deposit: u128,
+        deposits: Vec<PaymentTokenAmount>,
         sale: &mut Sale
     ) -> Promise {
-        // Removing claimable tokens and returning the deposit to the buyer.
+        // Removing claimable tokens and returning the deposits to the buyer.
         // `sold_tokens_for_buyers` stays the same for the seller to reclaim.
         sale.required_sold_token -= claimable;
-        sale.total_payment_token -= deposit;
+        let total_value: Balance = deposits
+            .iter()
+            .map(|deposit| sale.to_sale_value(&deposit.payment_token_contract_address, deposit.amount.0))
+            .sum();
+        let mut remaining_claimable = claimable;
+        let mut refunds = Vec::new();
+        for (index, deposit) in deposits.iter().enumerate() {
+            let token_id = deposit.payment_token_contract_address.clone();
+            let amount = deposit.amount.0;
+            // The last deposit takes the rounding remainder of the claimable tokens.
+            let claimable_share = if index + 1 == deposits.len() {
+                remaining_claimable
+            } else {
+                proportional(claimable, sale.to_sale_value(&token_id, amount), total_value)
+            };
+            remaining_claimable -= claimable_share;
+            sale.get_payment_config_mut(&token_id).total_payment_token -= amount;
+            refunds.push((token_id, claimable_share, amount));
+        }
         self.sales.replace(sale.id as u64, &sale);
 
-        if sale.is_near_accepted() {
-            log!(
-                "WITHDRAW: {} NEAR transferred back to {}",
-                claimable, buyer_id
-            );
-            Promise::new(buyer_id).transfer(deposit)
-        } else {
-            // If sale is not in near then expect a token address.
-            let token_id = sale.get_payment_token();
-            self.buyer_withdraw_ft_payment_token(
-                buyer_id,
-                claimable,
-                deposit,
-                token_id,
-                sale.id
-            )
-        }
+        refunds
+            .into_iter()
+            .map(|(token_id, claimable_share, deposit)| match token_id {
+                Some(token_id) => self.buyer_withdraw_ft_payment_token(
+                    buyer_id.clone(),
+                    claimable_share,
+                    deposit,
+                    token_id,
+                    sale.id
+                ),
+                None => {
+                    log!(
+                        "WITHDRAW: {} NEAR transferred back to {}",
+                        deposit, buyer_id
+                    );
+                    Promise::new(buyer_id.clone()).transfer(deposit)
+                }
+            })
+            .reduce(|promise, next| promise.and(next))
+            .unwrap()
     }
 
     fn buyer_withdraw_ft_payment_token(
@@ -184,12 +206,14 @@ impl KatherineSaleContract {
                 self.buyers.insert(&buyer_id, &buyer);
 
                 let deposit = deposit.0;
+                let token_id = Some(token_id.clone());
                 let mut sale = self.internal_get_sale(sale_id);
                 // Important: Recover the claimable tokens and deposit from user.
-                sale.deposits.insert(buyer_id, &deposit);
-                sale.claimable_sold_token_for_buyers.insert(&buyer_id, &claimable);
+                sale.add_buyer_deposit(buyer_id, &token_id, deposit);
+                let new_claimable = sale.get_buyer_claimable_sold_token(buyer_id) + claimable;
+                sale.claimable_sold_token_for_buyers.insert(buyer_id, &new_claimable);
                 sale.required_sold_token += claimable;
-                sale.total_payment_token += deposit;
+                sale.get_payment_config_mut(&token_id).total_payment_token += deposit;
                 self.sales.replace(sale.id as u64, &sale);
                 log!(
                     "FAILED: {} tokens not transferred. Recovering sale {} state.",
@@ -203,48 +227,63 @@ impl KatherineSaleContract {
     // * Collect payments & fees: Seller *
     // ***********************************
 
+    /// The payments of every payment token are sent to the sale `payout_id`.
+    /// The sale fee is added to the early exit fees.
     pub(crate) fn internal_collect_payments(&mut self, sale: &mut Sale) -> Promise {
-        let fee = proportional(
-            sale.total_payment_token,
-            sale.payment_config.sale_fee as u128,
-            BASIS_POINT as u128
-        );
-        let to_send = sale.total_payment_token - fee;
-        sale.total_payment_token = 0;
-        sale.total_fees = fee;
+        let sale_fee = sale.sale_fee as u128;
+        let mut payments = Vec::new();
+        for config in sale.payment_configs.values_mut() {
+            if config.total_payment_token == 0 {
+                continue;
+            }
+            let fee = proportional(config.total_payment_token, sale_fee, BASIS_POINT as u128);
+            payments.push((
+                config.payment_token_contract_address.clone(),
+                config.total_payment_token - fee,
+                fee
+            ));
+            config.total_payment_token = 0;
+            config.total_fees += fee;
+        }
+        sale.payments_collected = true;
         self.sales.replace(sale.id as u64, &sale);
 
-        if sale.is_near_accepted() {
-            Promise::new(self.treasury_id.clone()).transfer(to_send)
-        } else {
-            // If sale is not in near then expect a token address.
-            let token_id = sale.get_payment_token();
-            self.internal_seller_withdraw_payment_token(
-                token_id,
-                to_send,
-                sale.id
-            )
-        }
+        payments
+            .into_iter()
+            .map(|(token_id, to_send, fee)| match token_id {
+                Some(token_id) => self.internal_seller_withdraw_payment_token(
+                    token_id,
+                    to_send,
+                    fee,
+                    sale
+                ),
+                None => Promise::new(sale.payout_id.clone()).transfer(to_send),
+            })
+            .reduce(|promise, next| promise.and(next))
+            .unwrap()
     }
 
     fn internal_seller_withdraw_payment_token(
         &mut self,
         token_id: AccountId,
         amount: u128,
-        sale_id: u32
+        fee: u128,
+        sale: &Sale
     ) -> Promise {
         let amount = U128::from(amount);
+        let fee = U128::from(fee);
 
         ext_ft::ext(token_id.clone())
             .with_static_gas(GAS_FOR_FT_TRANSFER)
             .with_attached_deposit(1)
-            .ft_transfer(self.treasury_id.clone(), amount, None).then(
+            .ft_transfer(sale.payout_id.clone(), amount, None).then(
                 Self::ext(env::current_account_id())
                     .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
                     .seller_withdraw_payment_tokens_resolve(
                         &token_id,
                         amount,
-                        sale_id
+                        fee,
+                        sale.id
                     )
             )
     }
@@ -254,24 +293,28 @@ impl KatherineSaleContract {
         &mut self,
         token_id: &AccountId,
         amount: U128,
+        fee: U128,
         sale_id: u32
     ) {
         let amount = amount.0;
+        let mut sale = self.internal_get_sale(sale_id);
 
         match env::promise_result(0) {
             PromiseResult::NotReady => unreachable!(),
             PromiseResult::Successful(_) => {
                 log!(
                     "WITHDRAW: {} tokens of payment-token {} transferred to {}",
-                    amount, token_id, &self.treasury_id
+                    amount, token_id, &sale.payout_id
                 );
             },
             PromiseResult::Failed => {
-                let mut sale = self.internal_get_sale(sale_id);
+                let config = sale.get_payment_config_mut(&Some(token_id.clone()));
 
-                // Important: Recover the claimable tokens and deposit from user.
-                sale.total_payment_token = amount + sale.total_fees;
-                sale.total_fees = 0;
+                // Important: Recover the payments and the sale fee of the token,
+                // unless the fees were already collected.
+                let fee = std::cmp::min(fee.0, config.total_fees);
+                config.total_payment_token = amount + fee;
+                config.total_fees -= fee;
                 self.sales.replace(sale.id as u64, &sale);
                 log!(
                     "FAILED: {} tokens not transferred. Recovering sale {} state.",
@@ -281,22 +324,29 @@ impl KatherineSaleContract {
         };
     }
 
+    /// The fees of every payment token are sent to the `treasury_id`.
     pub(crate) fn internal_collect_fees(&mut self, sale: &mut Sale) -> Promise {
-        let to_send = sale.total_fees;
-        sale.total_fees = 0;
+        let mut fees = Vec::new();
+        for config in sale.payment_configs.values_mut() {
+            if config.total_fees > 0 {
+                fees.push((config.payment_token_contract_address.clone(), config.total_fees));
+                config.total_fees = 0;
+            }
+        }
         self.sales.replace(sale.id as u64, &sale);
 
-        if sale.is_near_accepted() {
-            Promise::new(self.treasury_id.clone()).transfer(to_send)
-        } else {
-            // If sale is not in near then expect a token address.
-            let token_id = sale.get_payment_token();
-            self.internal_seller_withdraw_fee(
-                token_id,
-                to_send,
-                sale.id
-            )
-        }
+        fees
+            .into_iter()
+            .map(|(token_id, to_send)| match token_id {
+                Some(token_id) => self.internal_seller_withdraw_fee(
+                    token_id,
+                    to_send,
+                    sale.id
+                ),
+                None => Promise::new(self.treasury_id.clone()).transfer(to_send),
+            })
+            .reduce(|promise, next| promise.and(next))
+            .unwrap()
     }
 
     fn internal_seller_withdraw_fee(
@@ -340,7 +390,7 @@ impl KatherineSaleContract {
             },
             PromiseResult::Failed => {
                 let mut sale = self.internal_get_sale(sale_id);
-                sale.total_fees = amount;
+                sale.get_payment_config_mut(&Some(token_id.clone())).total_fees += amount;
                 self.sales.replace(sale.id as u64, &sale);
                 log!(
                     "FAILED: {} tokens not transferred. Recovering sale {} state.",
@@ -368,7 +418,7 @@ impl KatherineSaleContract {
         ext_ft::ext(token_id.clone())
             .with_static_gas(GAS_FOR_FT_TRANSFER)
             .with_attached_deposit(1)
-            .ft_transfer(self.owner_id.clone(), excess, None).then(
+            .ft_transfer(sale.seller_id.clone(), excess, None).then(
                 Self::ext(env::current_account_id())
                     .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
                     .seller_withdraw_excess_sold_tokens_resolve(
@@ -387,17 +437,17 @@ impl KatherineSaleContract {
         sale_id: u32
     ) {
         let excess = excess.0;
+        let mut sale = self.internal_get_sale(sale_id);
 
         match env::promise_result(0) {
             PromiseResult::NotReady => unreachable!(),
             PromiseResult::Successful(_) => {
                 log!(
                     "WITHDRAW: {} tokens of sold-token {} transferred to {}",
-                    excess, token_id, &self.owner_id
+                    excess, token_id, &sale.seller_id
                 );
             },
             PromiseResult::Failed => {
-                let mut sale = self.internal_get_sale(sale_id);
                 sale.sold_tokens_for_buyers += excess;
                 self.sales.replace(sale.id as u64, &sale);
                 log!(
@@ -0,0 +1,202 @@
+use crate::*;
+use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{PromiseOrValue, PromiseResult};
+use treasury::TreasuryMovementKind;
+
+/// Bond committed by the creator of a proposal. It is refunded when the proposal
+/// reaches quorum, and slashed to the treasury when the proposal is rejected as
+/// spam (voting ends without quorum). On cancel, the bond is refunded when the
+/// creator withdraws the proposal, and slashed when an operator cancels it.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ProposalBond {
+    pub creator_id: AccountId,
+    pub near_amount: Balance,
+    pub asset_token_amount: Balance,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalBondJSON {
+    pub proposal_id: ProposalId,
+    pub creator_id: AccountId,
+    pub near_amount: U128,
+    pub asset_token_amount: U128,
+}
+
+impl ProposalBond {
+    pub(crate) fn to_json(&self, proposal_id: ProposalId) -> ProposalBondJSON {
+        ProposalBondJSON {
+            proposal_id,
+            creator_id: self.creator_id.clone(),
+            near_amount: U128::from(self.near_amount),
+            asset_token_amount: U128::from(self.asset_token_amount),
+        }
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenReceiver for ProposalsContract {
+    /// Asset tokens received with the bond msg are kept as bond deposit of the
+    /// sender, and are locked as bond the next time the sender creates a proposal.
+    /// Whitelisted tokens received with the treasury msg fund the treasury.
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        let token_id = env::predecessor_account_id();
+        if msg == TREASURY_DEPOSIT_MSG {
+            require!(self.is_treasury_token(&token_id), "Token is not whitelisted");
+            self.internal_treasury_deposit(
+                TreasuryMovementKind::Deposit,
+                &TreasuryAsset::FungibleToken(token_id),
+                amount.0,
+                &sender_id,
+                None,
+            );
+            return PromiseOrValue::Value(U128::from(0));
+        }
+
+        require!(
+            token_id == self.asset_token_contract_address,
+            "This contract only works with the asset token"
+        );
+        require!(msg == PROPOSAL_BOND_MSG, "Invalid ft_on_transfer msg");
+
+        let deposit = self.internal_get_bond_deposit(&sender_id) + amount.0;
+        self.bond_deposits.insert(&sender_id, &deposit);
+        log!("BOND DEPOSIT: {} asset tokens from {}", amount.0, &sender_id);
+
+        // Return unused amount
+        PromiseOrValue::Value(U128::from(0))
+    }
+}
+
+#[near_bindgen]
+impl ProposalsContract {
+    /// Withdraw asset tokens deposited for bonds that are not locked in a proposal.
+    pub fn withdraw_bond_deposit(&mut self, amount: U128) -> Promise {
+        let account_id = env::predecessor_account_id();
+        let deposit = self.internal_get_bond_deposit(&account_id);
+        require!(deposit >= amount.0, "Not enough bond deposit");
+        self.internal_set_bond_deposit(&account_id, deposit - amount.0);
+        self.transfer_asset_token_bond(account_id, amount.0)
+    }
+
+    #[private]
+    pub fn after_transfer_bond_callback(&mut self, account_id: AccountId, amount: U128) {
+        let amount = amount.0;
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(_) => {
+                log!("BOND WITHDRAW: {} asset tokens to {}", amount, &account_id);
+            }
+            PromiseResult::Failed => {
+                log!(
+                    "FAILED: {} asset tokens not transferred. Recovering {} bond deposit.",
+                    amount,
+                    &account_id
+                );
+                let deposit = self.internal_get_bond_deposit(&account_id) + amount;
+                self.bond_deposits.insert(&account_id, &deposit);
+            }
+        }
+    }
+}
+
+impl ProposalsContract {
+    pub(crate) fn internal_get_bond_deposit(&self, account_id: &AccountId) -> Balance {
+        self.bond_deposits.get(account_id).unwrap_or(0)
+    }
+
+    fn internal_set_bond_deposit(&mut self, account_id: &AccountId, amount: Balance) {
+        if amount == 0 {
+            self.bond_deposits.remove(account_id);
+        } else {
+            self.bond_deposits.insert(account_id, &amount);
+        }
+    }
+
+    /// Take the asset token bond from the account deposit.
+    pub(crate) fn internal_lock_asset_token_bond(&mut self, account_id: &AccountId) -> Balance {
+        let cost = self.proposal_cost_in_asset_token;
+        if cost == 0 {
+            return 0;
+        }
+        let deposit = self.internal_get_bond_deposit(account_id);
+        require!(
+            deposit >= cost,
+            format!(
+                "The required asset token bond is {}, deposit it with ft_transfer_call",
+                cost
+            )
+        );
+        self.internal_set_bond_deposit(account_id, deposit - cost);
+        cost
+    }
+
+    /// Give back the bond when the proposal could not be created.
+    pub(crate) fn internal_return_unused_bond(
+        &mut self,
+        account_id: &AccountId,
+        near_amount: Balance,
+        asset_token_amount: Balance,
+    ) {
+        if asset_token_amount > 0 {
+            let deposit = self.internal_get_bond_deposit(account_id) + asset_token_amount;
+            self.bond_deposits.insert(account_id, &deposit);
+        }
+        if near_amount > 0 {
+            Promise::new(account_id.clone()).transfer(near_amount);
+        }
+    }
+
+    /// Refund the pending bond to the proposal creator.
+    pub(crate) fn internal_refund_bond(&mut self, proposal_id: ProposalId) {
+        if let Some(bond) = self.bonds.remove(&proposal_id) {
+            log!("BOND REFUND: proposal {} bond returned to {}", proposal_id, &bond.creator_id);
+            if bond.near_amount > 0 {
+                Promise::new(bond.creator_id.clone()).transfer(bond.near_amount);
+            }
+            if bond.asset_token_amount > 0 {
+                self.transfer_asset_token_bond(bond.creator_id, bond.asset_token_amount);
+            }
+        }
+    }
+
+    /// Slash the pending bond, the funds go to the treasury.
+    pub(crate) fn internal_slash_bond(&mut self, proposal_id: ProposalId) {
+        if let Some(bond) = self.bonds.remove(&proposal_id) {
+            log!("BOND SLASH: proposal {} bond from {} slashed", proposal_id, &bond.creator_id);
+            self.internal_treasury_deposit(
+                TreasuryMovementKind::SlashedBond,
+                &TreasuryAsset::Near,
+                bond.near_amount,
+                &bond.creator_id,
+                Some(proposal_id),
+            );
+            self.internal_treasury_deposit(
+                TreasuryMovementKind::SlashedBond,
+                &TreasuryAsset::FungibleToken(self.asset_token_contract_address.clone()),
+                bond.asset_token_amount,
+                &bond.creator_id,
+                Some(proposal_id),
+            );
+        }
+    }
+
+    fn transfer_asset_token_bond(&mut self, account_id: AccountId, amount: Balance) -> Promise {
+        ext_ft::ext(self.asset_token_contract_address.clone())
+            .with_static_gas(GAS_FOR_FT_TRANSFER)
+            .with_attached_deposit(1)
+            .ft_transfer(account_id.clone(), U128::from(amount), None)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
+                    .after_transfer_bond_callback(account_id, U128::from(amount)),
+            )
+    }
+}
@@ -0,0 +1,96 @@
+use crate::*;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
+pub const DEFAULT_CATEGORY: &str = "default";
+
+/// Votes counted to reach the quorum.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum QuorumModel {
+    ForAndAbstain,
+    AllVotes,
+    ForOnly,
+}
+
+/// Share of For votes, over For and Against, needed to accept a proposal.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ApprovalThreshold {
+    SimpleMajority,
+    TwoThirdsSupermajority,
+}
+
+/// Rules applied to every proposal of a category, such as treasury,
+/// parameter change or emergency proposals.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalCategory {
+    pub name: String,
+    /// Percent of the total voting power, in basis points.
+    pub quorum_floor: BasisPoints,
+    pub quorum_model: QuorumModel,
+    pub approval_threshold: ApprovalThreshold,
+    pub voting_period: EpochMillis,
+    /// Time after the end of the voting period before an accepted proposal can be executed.
+    pub execution_delay: EpochMillis,
+    /// Council emergency actions can accept proposals of the category without voting.
+    #[serde(default)]
+    pub emergency: bool,
+}
+
+impl ProposalCategory {
+    pub(crate) fn assert_valid(&self) {
+        require!(!self.name.is_empty(), "Category name is empty.");
+        require!(
+            self.quorum_floor <= ONE_HUNDRED,
+            "Incorrect quorum basis points."
+        );
+    }
+
+    pub(crate) fn quorum_votes(&self, proposal_vote: &ProposalVote) -> VotingPower {
+        match self.quorum_model {
+            QuorumModel::ForAndAbstain => proposal_vote.for_votes + proposal_vote.abstain_votes,
+            QuorumModel::AllVotes => {
+                proposal_vote.for_votes + proposal_vote.against_votes + proposal_vote.abstain_votes
+            }
+            QuorumModel::ForOnly => proposal_vote.for_votes,
+        }
+    }
+
+    pub(crate) fn is_approved(&self, proposal_vote: &ProposalVote) -> bool {
+        let for_votes = proposal_vote.for_votes;
+        let against_votes = proposal_vote.against_votes;
+        match self.approval_threshold {
+            ApprovalThreshold::SimpleMajority => for_votes > against_votes,
+            ApprovalThreshold::TwoThirdsSupermajority => {
+                for_votes > 0 && for_votes * 3 >= (for_votes + against_votes) * 2
+            }
+        }
+    }
+}
+
+impl ProposalsContract {
+    /// Rules for proposals without category, from the contract global settings.
+    pub(crate) fn internal_default_category(&self) -> ProposalCategory {
+        ProposalCategory {
+            name: DEFAULT_CATEGORY.to_string(),
+            quorum_floor: self.quorum_floor,
+            quorum_model: QuorumModel::ForAndAbstain,
+            approval_threshold: ApprovalThreshold::SimpleMajority,
+            voting_period: self.voting_period,
+            execution_delay: 0,
+            emergency: false,
+        }
+    }
+
+    pub(crate) fn internal_get_category(&self, name: &Option<String>) -> ProposalCategory {
+        match name {
+            Some(name) => self
+                .categories
+                .get(name)
+                .expect("Proposal category does not exist"),
+            None => self.internal_default_category(),
+        }
+    }
+}
@@ -7,6 +7,44 @@ pub const ONE_HUNDRED: u16 = 10_000;
 /// Amount of gas for fungible token transfers.
 pub const GAS_FOR_GET_VOTING_POWER: Gas = Gas(10 * TGAS);
 pub const GAS_FOR_RESOLVE_VOTE: Gas = Gas(11 * TGAS);
+pub const GAS_FOR_LOCK_UNLOCKING: Gas = Gas(5 * TGAS);
+/// Proposals on voting reconciled within the gas of the staking notification.
+pub const MAX_PROPOSALS_RECONCILED_ON_NOTIFICATION: u32 = 10;
+/// The vote callback could ask the staking contract to lock unlocking.
+pub const GAS_FOR_RESOLVE_VOTE_PROPOSAL: Gas = Gas(18 * TGAS);
+pub const GAS_FOR_FT_TRANSFER: Gas = Gas(47 * TGAS);
+pub const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(11 * TGAS);
+
+/// Default proposer rate limits.
+pub const DEFAULT_PROPOSAL_COOLDOWN: u64 = 24 * 60 * 60 * 1000;
+pub const DEFAULT_MAX_OPEN_PROPOSALS: u32 = 3;
+
+/// Default voting period of the proposals fast-tracked by the council.
+pub const DEFAULT_FAST_TRACK_VOTING_PERIOD: u64 = 24 * 60 * 60 * 1000;
+
+/// Weight of the newest turnout in the moving average while adaptive quorum is disabled.
+/// The average keeps updating with this smoothing, so it is ready once the mode is enabled.
+pub const DEFAULT_TURNOUT_SMOOTHING: u16 = 2_000;
+
+/// Length limits, in bytes, of the proposal content stored on-chain.
+pub const MAX_TITLE_LEN: usize = 200;
+pub const MAX_SHORT_DESCRIPTION_LEN: usize = 1_000;
+pub const MAX_BODY_LEN: usize = 20_000;
+pub const MAX_DATA_LEN: usize = 5_000;
+pub const MAX_EXTRA_LEN: usize = 5_000;
+pub const MAX_CONTENT_URI_LEN: usize = 512;
+
+/// Number of voters kept in the participation leaderboard.
+pub const LEADERBOARD_SIZE: usize = 50;
+
+/// Each signed vote needs a cross-contract call to get the voting power and its
+/// callback, 28 TGas. Six votes use 168 TGas, leaving room for the signature checks
+/// within the 300 TGas limit of a transaction.
+pub const MAX_SIGNED_VOTES_PER_BATCH: usize = 6;
+
+/// `ft_on_transfer` msg to deposit asset tokens for proposal bonds.
+pub const PROPOSAL_BOND_MSG: &str = "proposal-bond";
+pub const TREASURY_DEPOSIT_MSG: &str = "treasury-deposit";
 
 #[derive(BorshSerialize, BorshDeserialize, BorshStorageKey)]
 pub enum StorageKey {
@@ -17,4 +55,23 @@ pub enum StorageKey {
     Voters,
     Proposers,
     Votes { hash_id: CryptoHash },
+    BondDeposits,
+    Bonds,
+    ProposalsByState,
+    ProposalsByStateIds { hash_id: CryptoHash },
+    Categories,
+    TurnoutHistory,
+    Revisions,
+    ProposalRevisions { hash_id: CryptoHash },
+    VoteKeys,
+    VoteNonces,
+    TreasuryBalances,
+    TreasuryTokens,
+    TreasuryPayouts,
+    SpendingLimits,
+    TreasuryStreams,
+    TreasuryMovements,
+    VoterStats,
+    Council,
+    EmergencyActions,
 }
\ No newline at end of file
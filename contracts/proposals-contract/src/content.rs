@@ -0,0 +1,58 @@
+use crate::*;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::Base58CryptoHash;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::CryptoHash;
+
+/// Reference to the proposal body stored off-chain, e.g. an IPFS CID or an URL,
+/// anchored with the sha256 hash of the content.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OffchainContent {
+    pub uri: String,
+    pub sha256: Base58CryptoHash,
+}
+
+impl OffchainContent {
+    pub(crate) fn verify(&self, content: &str) -> bool {
+        env::sha256_array(content.as_bytes()) == CryptoHash::from(self.sha256)
+    }
+}
+
+fn assert_max_len(field: &str, value: &str, max_len: usize) {
+    assert!(
+        value.len() <= max_len,
+        "Proposal {} exceeds {} bytes",
+        field,
+        max_len
+    );
+}
+
+/// Content of a proposal, submitted on creation and on every update.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalContent {
+    pub title: String,
+    pub short_description: String,
+    pub body: String,
+    pub data: String,
+    pub extra: String,
+    /// With off-chain content the body is kept off-chain, and `body` must be empty.
+    pub offchain_content: Option<OffchainContent>,
+}
+
+impl ProposalContent {
+    /// Check the length limits of the content stored on-chain.
+    pub(crate) fn assert_valid(&self) {
+        assert_max_len("title", &self.title, MAX_TITLE_LEN);
+        assert_max_len("short_description", &self.short_description, MAX_SHORT_DESCRIPTION_LEN);
+        assert_max_len("body", &self.body, MAX_BODY_LEN);
+        assert_max_len("data", &self.data, MAX_DATA_LEN);
+        assert_max_len("extra", &self.extra, MAX_EXTRA_LEN);
+        if let Some(offchain_content) = &self.offchain_content {
+            require!(!offchain_content.uri.is_empty(), "Off-chain content uri is empty");
+            assert_max_len("content uri", &offchain_content.uri, MAX_CONTENT_URI_LEN);
+            require!(self.body.is_empty(), "Body must be empty when it is stored off-chain");
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use crate::*;
+use crate::events::emit_event;
+use near_sdk::serde_json::json;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::Gas;
+use treasury::TreasuryMovementKind;
+
+/// Actions passed by the council without waiting for a voting period.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum EmergencyAction {
+    /// Stop the creation of new proposals.
+    PauseProposals,
+    /// Call another contract, e.g. to pause the staking position contract.
+    /// The deposit is paid from the NEAR treasury balance.
+    CallContract {
+        contract_id: AccountId,
+        method_name: String,
+        args: Base64VecU8,
+        deposit: U128,
+        gas: U64,
+    },
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum CouncilActionKind {
+    FastTrackApproval,
+    FastTracked,
+    EmergencyProposed,
+    EmergencyApproval,
+    EmergencyExecuted,
+}
+
+/// Council signature or decision, kept in the proposal history.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CouncilRecord {
+    pub kind: CouncilActionKind,
+    pub member_id: AccountId,
+    pub timestamp: EpochMillis,
+}
+
+#[near_bindgen]
+impl ProposalsContract {
+    // *********
+    // * Admin *
+    // *********
+
+    /// Replace the council members and the M-of-N approvals required.
+    pub fn update_council(&mut self, members: Vec<AccountId>, threshold: u32) {
+        self.assert_only_admin();
+        require!(
+            threshold > 0 && threshold as usize <= members.len(),
+            "Invalid council threshold."
+        );
+        self.council.clear();
+        for member in members {
+            if !self.council.insert(&member) {
+                panic!("Duplicated account ids in council list.")
+            }
+        }
+        self.council_threshold = threshold;
+    }
+
+    /// Update the voting period duration of fast-tracked proposals in milliseconds.
+    pub fn update_fast_track_voting_period(&mut self, new_value: U64) {
+        self.assert_only_admin();
+        self.fast_track_voting_period = new_value.0;
+    }
+
+    // ***********
+    // * Council *
+    // ***********
+
+    /// Sign the fast-track of a proposal. Once the threshold is reached the voting
+    /// starts with the shortened voting period and a two-thirds supermajority.
+    /// A Draft proposal is moved to Active, the council signatures act as review.
+    pub fn approve_fast_track(&mut self, proposal_id: ProposalId) -> Option<Promise> {
+        self.assert_only_council();
+        self.assert_proposal_is_active_or_draft(proposal_id);
+        let mut proposal = self.internal_get_proposal(&proposal_id);
+        self.internal_add_council_approval(&mut proposal, CouncilActionKind::FastTrackApproval);
+
+        if self.internal_count_council_approvals(&proposal, CouncilActionKind::FastTrackApproval)
+            < self.council_threshold
+        {
+            self.proposals.insert(&proposal_id, &proposal);
+            return None;
+        }
+        proposal.category.voting_period = self.fast_track_voting_period;
+        proposal.category.approval_threshold = ApprovalThreshold::TwoThirdsSupermajority;
+        self.internal_push_council_record(&mut proposal, CouncilActionKind::FastTracked);
+        if proposal.status == ProposalState::Draft {
+            self.internal_update_proposal_status(
+                &mut proposal,
+                ProposalState::Active,
+                env::signer_account_id(),
+            );
+        }
+        self.proposals.insert(&proposal_id, &proposal);
+        emit_event("proposal_fast_tracked", json!({ "proposal_id": proposal_id }));
+        Some(self.internal_start_voting_period(proposal_id))
+    }
+
+    /// Attach an emergency action to an open proposal, counted as the first signature.
+    pub fn propose_emergency_action(&mut self, proposal_id: ProposalId, action: EmergencyAction) {
+        self.assert_only_council();
+        let mut proposal = self.internal_get_proposal(&proposal_id);
+        require!(proposal.status.is_open(), "Proposal is not open.");
+        require!(
+            proposal.category.emergency,
+            "Proposal category does not allow emergency actions."
+        );
+        require!(
+            self.emergency_actions.get(&proposal_id).is_none(),
+            "Proposal already has an emergency action."
+        );
+        self.emergency_actions.insert(&proposal_id, &action);
+        self.internal_push_council_record(&mut proposal, CouncilActionKind::EmergencyProposed);
+        self.proposals.insert(&proposal_id, &proposal);
+        self.approve_emergency_action(proposal_id);
+    }
+
+    /// Sign the emergency action. It is executed when the threshold is reached,
+    /// and the proposal is moved to Executed.
+    pub fn approve_emergency_action(&mut self, proposal_id: ProposalId) {
+        self.assert_only_council();
+        let action = self
+            .emergency_actions
+            .get(&proposal_id)
+            .expect("Proposal has no emergency action.");
+        let mut proposal = self.internal_get_proposal(&proposal_id);
+        require!(proposal.status.is_open(), "Proposal is not open.");
+        self.internal_add_council_approval(&mut proposal, CouncilActionKind::EmergencyApproval);
+
+        if self.internal_count_council_approvals(&proposal, CouncilActionKind::EmergencyApproval)
+            < self.council_threshold
+        {
+            self.proposals.insert(&proposal_id, &proposal);
+            return;
+        }
+        self.emergency_actions.remove(&proposal_id);
+        self.internal_push_council_record(&mut proposal, CouncilActionKind::EmergencyExecuted);
+        let actor = env::signer_account_id();
+        self.internal_update_proposal_status(&mut proposal, ProposalState::Accepted, actor.clone());
+        self.internal_update_proposal_status(&mut proposal, ProposalState::Executed, actor);
+        self.internal_refund_bond(proposal_id);
+        self.internal_execute_emergency_action(proposal_id, action);
+        emit_event("emergency_action_executed", json!({ "proposal_id": proposal_id }));
+    }
+
+    // *********
+    // * View  *
+    // *********
+
+    pub fn get_council(&self) -> Vec<AccountId> {
+        self.council.to_vec()
+    }
+
+    pub fn get_council_threshold(&self) -> u32 {
+        self.council_threshold
+    }
+
+    pub fn get_fast_track_voting_period(&self) -> U64 {
+        U64::from(self.fast_track_voting_period)
+    }
+
+    pub fn get_emergency_action(&self, proposal_id: ProposalId) -> Option<EmergencyAction> {
+        self.emergency_actions.get(&proposal_id)
+    }
+}
+
+impl ProposalsContract {
+    pub(crate) fn assert_only_council(&self) {
+        require!(
+            self.council.contains(&env::signer_account_id()),
+            "Only council members can call this function."
+        );
+    }
+
+    fn internal_push_council_record(&self, proposal: &mut Proposal, kind: CouncilActionKind) {
+        proposal.council_history.push(CouncilRecord {
+            kind,
+            member_id: env::signer_account_id(),
+            timestamp: get_current_epoch_millis(),
+        });
+    }
+
+    fn internal_add_council_approval(&self, proposal: &mut Proposal, kind: CouncilActionKind) {
+        let member_id = env::signer_account_id();
+        require!(
+            !proposal
+                .council_history
+                .iter()
+                .any(|record| record.kind == kind && record.member_id == member_id),
+            "Council member already approved."
+        );
+        self.internal_push_council_record(proposal, kind);
+    }
+
+    /// Only the signatures of the current council members are counted.
+    fn internal_count_council_approvals(&self, proposal: &Proposal, kind: CouncilActionKind) -> u32 {
+        proposal
+            .council_history
+            .iter()
+            .filter(|record| record.kind == kind && self.council.contains(&record.member_id))
+            .count() as u32
+    }
+
+    fn internal_execute_emergency_action(&mut self, proposal_id: ProposalId, action: EmergencyAction) {
+        match action {
+            EmergencyAction::PauseProposals => {
+                self.open_for_new_proposals = false;
+            }
+            EmergencyAction::CallContract {
+                contract_id,
+                method_name,
+                args,
+                deposit,
+                gas,
+            } => {
+                if deposit.0 > 0 {
+                    self.internal_treasury_withdraw(
+                        TreasuryMovementKind::EmergencyCall,
+                        &TreasuryAsset::Near,
+                        deposit.0,
+                        &contract_id,
+                        Some(proposal_id),
+                    );
+                }
+                let call = Promise::new(contract_id.clone())
+                    .function_call(method_name, args.into(), deposit.0, Gas(gas.0));
+                if deposit.0 > 0 {
+                    // A failed call refunds the deposit, back to the treasury.
+                    call.then(
+                        Self::ext(env::current_account_id())
+                            .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
+                            .after_treasury_transfer_callback(
+                                TreasuryAsset::Near,
+                                contract_id,
+                                deposit,
+                                None,
+                            ),
+                    );
+                }
+            }
+        }
+    }
+}
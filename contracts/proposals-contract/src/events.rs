@@ -0,0 +1,18 @@
+use crate::*;
+use near_sdk::serde_json::{json, Value};
+
+const EVENT_STANDARD: &str = "aaxxii-proposals";
+const EVENT_VERSION: &str = "1.0.0";
+
+/// Log a NEP-297 event, so indexers and bots can follow the proposals lifecycle.
+pub(crate) fn emit_event(event: &str, data: Value) {
+    log!(
+        "EVENT_JSON:{}",
+        json!({
+            "standard": EVENT_STANDARD,
+            "version": EVENT_VERSION,
+            "event": event,
+            "data": [data],
+        })
+    );
+}
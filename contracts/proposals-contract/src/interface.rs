@@ -9,6 +9,18 @@ pub trait ExtStakingPosition {
     fn get_available_voting_power(&self, account_id: AccountId);
     fn get_all_locking_positions(&self, account_id: AccountId);
     fn get_total_voting_power(&self);
+    fn lock_unlocking_until(&mut self, staker_id: AccountId, until: EpochMillis);
+    fn reset_unlocking_lock(&mut self, staker_id: AccountId, until: Option<EpochMillis>);
+}
+
+#[ext_contract(ext_ft)]
+pub trait FungibleTokenCore {
+    fn ft_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    );
 }
 
 #[ext_contract(ext_self)]
@@ -23,7 +23,7 @@ impl ProposalsContract {
     pub(crate) fn assert_only_operator_or_creator(&self, proposal_id: ProposalId) {
         let proposal = self.internal_get_proposal(&proposal_id);
         require!(
-            proposal.creator_id == env::signer_account_id()
+            proposal.creator_id == env::predecessor_account_id()
                 || self.operator_ids.contains(&env::signer_account_id()),
             "Only the admin or proposal creator can call this function."
         );
@@ -32,7 +32,7 @@ impl ProposalsContract {
     pub(crate) fn assert_only_creator(&self, proposal_id: ProposalId) {
         let proposal = self.internal_get_proposal(&proposal_id);
         require!(
-            proposal.creator_id == env::signer_account_id(),
+            proposal.creator_id == env::predecessor_account_id(),
             "Only the proposal creator can call this function."
         );
     }
@@ -70,54 +70,57 @@ impl ProposalsContract {
         self.min_voting_power_amount <= voting_power
     }
 
-    pub(crate) fn internal_proposal_is_active(&self, proposal_id: ProposalId) -> bool {
-        let proposal = self.internal_get_proposal(&proposal_id);
-        // check if proposal has vote_start_timestamp
-        match proposal.vote_start_timestamp {
-            Some(date) => {
-                get_current_epoch_millis() <= date
-                    && !proposal.draft
-                    && !proposal.canceled
-                    && !proposal.executed
+    /// The proposer must wait `proposal_cooldown` after its last proposal.
+    pub(crate) fn internal_proposer_is_on_cooldown(&self, proposer_id: &AccountId) -> bool {
+        match self.internal_get_proposer(proposer_id.clone()).last() {
+            Some(last_id) => {
+                let last_proposal = self.internal_get_proposal(last_id);
+                get_current_epoch_millis()
+                    < last_proposal.creation_timestamp + self.proposal_cooldown
             }
-            None => !proposal.draft && !proposal.canceled && !proposal.executed,
+            None => false,
         }
     }
 
-    pub(crate) fn internal_proposal_is_active_or_draft(&self, proposal_id: ProposalId) -> bool {
-        let proposal = self.internal_get_proposal(&proposal_id);
-        // check if proposal has vote_start_timestamp
-        match proposal.vote_start_timestamp {
-            Some(date) => {
-                get_current_epoch_millis() <= date && !proposal.canceled && !proposal.executed
-            }
-            None => !proposal.canceled && !proposal.executed,
-        }
+    /// Proposals in Draft, Active or on voting are open.
+    pub(crate) fn internal_count_open_proposals(&self, proposer_id: &AccountId) -> u32 {
+        self.internal_get_proposer(proposer_id.clone())
+            .iter()
+            .filter(|proposal_id| self.internal_get_proposal(proposal_id).status.is_open())
+            .count() as u32
     }
 
-    pub(crate) fn internal_proposal_is_on_voting(&self, proposal_id: &ProposalId) -> bool {
-        let proposal = self.internal_get_proposal(&proposal_id);
-        log!("proposal {} on voting?", proposal.proposal_id.to_string());
-        // check if proposal has vote_start_timestamp
-        match proposal.vote_start_timestamp {
-            Some(date) => {
-                get_current_epoch_millis() >= date
-                    && get_current_epoch_millis() <= proposal.vote_end_timestamp.unwrap()
-                    && !proposal.draft
-                    && !proposal.canceled
-                    && !proposal.executed
-            }
-            None => false,
-        }
+    pub(crate) fn internal_proposer_has_max_open_proposals(&self, proposer_id: &AccountId) -> bool {
+        self.internal_count_open_proposals(proposer_id) >= self.max_open_proposals
     }
 
-    pub(crate) fn assert_proposal_threshold(&self, voting_power: u128) {
+    pub(crate) fn assert_proposer_limits(&self, proposer_id: &AccountId) {
         require!(
-            self.internal_check_proposal_threshold(voting_power),
-            "Proposal threshold does not reached"
+            !self.internal_proposer_is_on_cooldown(proposer_id),
+            "Proposer must wait for the cooldown period to submit a new proposal"
+        );
+        require!(
+            !self.internal_proposer_has_max_open_proposals(proposer_id),
+            "Proposer has reached the maximum of open proposals"
+        );
+    }
+
+    pub(crate) fn internal_proposal_is_active_or_draft(&self, proposal_id: ProposalId) -> bool {
+        matches!(
+            self.internal_get_proposal(&proposal_id).status,
+            ProposalState::Draft | ProposalState::Active
         )
     }
 
+    pub(crate) fn internal_proposal_is_on_voting(&self, proposal_id: &ProposalId) -> bool {
+        let proposal = self.internal_get_proposal(proposal_id);
+        if proposal.status != ProposalState::VotingProcess {
+            return false;
+        }
+        let now = get_current_epoch_millis();
+        now >= proposal.vote_start_timestamp.unwrap() && now <= proposal.vote_end_timestamp.unwrap()
+    }
+
     pub(crate) fn assert_proposal_is_active_or_draft(&self, proposal_id: ProposalId) {
         require!(
             self.internal_proposal_is_active_or_draft(proposal_id),
@@ -197,8 +200,12 @@ impl ProposalsContract {
         }
     }
 
-    pub(crate) fn internal_get_quorum(&self, total_voting_power: u128) -> u128 {
-        total_voting_power * u128::from(self.quorum_floor) / 100 / 100
+    pub(crate) fn internal_get_quorum(
+        &self,
+        total_voting_power: u128,
+        quorum_floor: BasisPoints,
+    ) -> u128 {
+        total_voting_power * u128::from(quorum_floor) / 100 / 100
     }
 
     pub(crate) fn internal_is_quorum_reached(&self, proposal_id: ProposalId) -> bool {
@@ -209,7 +216,7 @@ impl ProposalsContract {
             Some(quorum) => quorum,
             None => panic!("Proposal quorum has not been set"),
         };
-        quorum <= proposal_vote.for_votes + proposal_vote.abstain_votes
+        quorum <= proposal.category.quorum_votes(&proposal_vote)
     }
 
     pub(crate) fn internal_get_voter(&self, voter_id: &VoterId) -> Voter {
@@ -222,11 +229,11 @@ impl ProposalsContract {
             .unwrap_or(Vec::<ProposalId>::new())
     }
 
-    // pub(crate) fn assert_proposal_storage_is_covered(&self) {
-    //     assert!(
-    //         env::attached_deposit() >= self.proposal_storage_near,
-    //         "The required NEAR to create a proposal is {}",
-    //         self.proposal_storage_near
-    //     );
-    // }
+    pub(crate) fn assert_proposal_storage_is_covered(&self) {
+        assert!(
+            env::attached_deposit() >= self.proposal_storage_near,
+            "The required NEAR to create a proposal is {}",
+            self.proposal_storage_near
+        );
+    }
 }
@@ -1,26 +1,46 @@
 use crate::constants::*;
 use crate::interface::*;
+use bond::{ProposalBond, ProposalBondJSON};
+use category::{ApprovalThreshold, ProposalCategory};
+use council::{CouncilRecord, EmergencyAction};
+use content::{OffchainContent, ProposalContent};
+use quorum::{AdaptiveQuorumConfig, TurnoutRecord};
+use reconcile::VoteReconciliationMode;
+use revision::{ProposalRevision, ProposalRevisionJSON};
+use treasury::{PaymentStream, SpendingLimit, TreasuryAsset, TreasuryMovement, TreasuryPayout};
 use proposals::{Proposal, ProposalJSON, ProposalState};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{unordered_map::UnorderedMap, UnorderedSet};
+use near_sdk::collections::{unordered_map::UnorderedMap, UnorderedSet, Vector};
 use near_sdk::json_types::U128;
-use near_sdk::json_types::U64;
-use near_sdk::{env, log, near_bindgen, require, AccountId, Balance, PanicOnDefault, Promise};
+use near_sdk::json_types::{Base58CryptoHash, U64};
+use near_sdk::{env, log, near_bindgen, require, AccountId, Balance, PanicOnDefault, Promise, PublicKey};
 use types::*;
 use utils::get_current_epoch_millis;
 use vote::{Vote, VoteJson, VoteType};
 use vote_counting::{ProposalVote, ProposalVoteJson};
 use voter::{Voter, VoterJson};
+use voter_stats::{LeaderboardEntry, VoterStats};
 
+mod bond;
+mod category;
 mod constants;
+mod content;
+mod council;
+mod events;
 mod interface;
 mod internal;
 mod proposals;
+mod quorum;
+mod reconcile;
+mod revision;
+mod signed_vote;
+mod treasury;
 mod types;
 mod utils;
 mod vote;
 mod vote_counting;
 mod voter;
+mod voter_stats;
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
@@ -32,19 +52,67 @@ pub struct ProposalsContract {
     pub proposals: UnorderedMap<ProposalId, Proposal>,
     pub votes: UnorderedMap<ProposalId, ProposalVote>,
     pub voters: UnorderedMap<AccountId, Voter>,
+    /// Participation records, kept after the votes are removed.
+    pub voter_stats: UnorderedMap<AccountId, VoterStats>,
+    pub leaderboard: Vec<LeaderboardEntry>,
+    /// Number of proposals that started voting.
+    pub voting_rounds: u64,
+    /// Keys registered by the voters to sign gasless votes, and last nonce used.
+    pub vote_keys: UnorderedMap<AccountId, PublicKey>,
+    pub vote_nonces: UnorderedMap<AccountId, u64>,
     pub proposers: UnorderedMap<AccountId, Vec<ProposalId>>,
+    /// Content history of every proposal.
+    pub revisions: UnorderedMap<ProposalId, Vector<ProposalRevision>>,
+    /// Index of proposal ids by their current state.
+    pub proposals_by_state: UnorderedMap<ProposalState, UnorderedSet<ProposalId>>,
+    /// Rules for each proposal category. Proposals without category use the global settings.
+    pub categories: UnorderedMap<String, ProposalCategory>,
     /// Duration of the voting period.
     pub voting_period: EpochMillis,
 
     /// Parameters to allow an Account to create a new Proposal. (proposal threshold)
-    pub min_asset_token_amount: Balance,
     pub min_st_near_amount: Balance,
     pub min_voting_power_amount: VotingPower,
 
-    /// Cost of committing a new proposal. Base Token is burned. Near is for storage.
+    /// Bond required to commit a new proposal, in asset token and NEAR.
+    /// Refunded if the proposal reaches quorum, slashed otherwise.
     pub proposal_cost_in_asset_token: Balance,
     pub proposal_storage_near: Balance,
 
+    /// Asset tokens deposited by accounts, to be locked as proposal bonds.
+    pub bond_deposits: UnorderedMap<AccountId, Balance>,
+    /// Pending bonds of the proposals that are not resolved yet.
+    pub bonds: UnorderedMap<ProposalId, ProposalBond>,
+
+    /// Treasury balances, slashed bonds included. Funds are only paid out by
+    /// executed proposals, or by operators within the spending limits.
+    pub treasury_balances: UnorderedMap<TreasuryAsset, Balance>,
+    /// Fungible tokens accepted by the treasury, besides the asset token.
+    pub treasury_tokens: UnorderedSet<AccountId>,
+    pub treasury_payouts: UnorderedMap<ProposalId, TreasuryPayout>,
+    pub spending_limits: UnorderedMap<TreasuryAsset, SpendingLimit>,
+    pub treasury_streams: Vector<PaymentStream>,
+    pub treasury_movements: Vector<TreasuryMovement>,
+
+    /// Rate limits for proposers: time between proposals of the same account,
+    /// and max number of open (Draft, Active or on voting) proposals per account.
+    pub proposal_cooldown: EpochMillis,
+    pub max_open_proposals: u32,
+
+    /// NEAR available to reward the accounts finalizing proposals, and reward per proposal.
+    pub keeper_budget: Balance,
+    pub keeper_reward: Balance,
+
+    /// Votes are shrunk, or unlocking is blocked, when voters unlock voting power mid-vote.
+    pub vote_reconciliation: VoteReconciliationMode,
+
+    /// Council that can fast-track proposals and pass emergency actions with
+    /// M-of-N signatures.
+    pub council: UnorderedSet<AccountId>,
+    pub council_threshold: u32,
+    pub fast_track_voting_period: EpochMillis,
+    pub emergency_actions: UnorderedMap<ProposalId, EmergencyAction>,
+
     /// The creation of new Proposals could be stopped.
     pub open_for_new_proposals: bool,
 
@@ -52,6 +120,11 @@ pub struct ProposalsContract {
     /// If a quorum is set to 50%, this means that 50% of all circulating tokens need to vote yes for the proposal to pass.
     /// Percent is denominated in basis points 100% equals 10_000 basis points.
     pub quorum_floor: BasisPoints,
+
+    /// Optional adaptive quorum, derived from the turnout moving average within bounds.
+    pub adaptive_quorum: Option<AdaptiveQuorumConfig>,
+    pub turnout_ema_bp: Option<BasisPoints>,
+    pub turnout_history: Vector<TurnoutRecord>,
 }
 
 #[near_bindgen]
@@ -80,16 +153,43 @@ impl ProposalsContract {
             staking_position_contract_address,
             proposals: UnorderedMap::new(StorageKey::Proposals),
             voting_period: voting_period.0,
-            min_asset_token_amount: 0,
             min_st_near_amount: 0,
             min_voting_power_amount: min_voting_power_amount.0,
             proposal_cost_in_asset_token: 0,
             proposal_storage_near: proposal_storage_near.0,
+            proposal_cooldown: DEFAULT_PROPOSAL_COOLDOWN,
+            max_open_proposals: DEFAULT_MAX_OPEN_PROPOSALS,
+            keeper_budget: 0,
+            keeper_reward: 0,
+            vote_reconciliation: VoteReconciliationMode::ShrinkVotes,
+            council: UnorderedSet::new(StorageKey::Council),
+            council_threshold: 0,
+            fast_track_voting_period: DEFAULT_FAST_TRACK_VOTING_PERIOD,
+            emergency_actions: UnorderedMap::new(StorageKey::EmergencyActions),
             open_for_new_proposals: true,
             quorum_floor,
+            adaptive_quorum: None,
+            turnout_ema_bp: None,
+            turnout_history: Vector::new(StorageKey::TurnoutHistory),
             votes: UnorderedMap::new(StorageKey::ProposalVotes),
             voters: UnorderedMap::new(StorageKey::Voters),
+            voter_stats: UnorderedMap::new(StorageKey::VoterStats),
+            leaderboard: Vec::new(),
+            voting_rounds: 0,
+            vote_keys: UnorderedMap::new(StorageKey::VoteKeys),
+            vote_nonces: UnorderedMap::new(StorageKey::VoteNonces),
             proposers: UnorderedMap::new(StorageKey::Proposers),
+            revisions: UnorderedMap::new(StorageKey::Revisions),
+            proposals_by_state: UnorderedMap::new(StorageKey::ProposalsByState),
+            categories: UnorderedMap::new(StorageKey::Categories),
+            bond_deposits: UnorderedMap::new(StorageKey::BondDeposits),
+            bonds: UnorderedMap::new(StorageKey::Bonds),
+            treasury_balances: UnorderedMap::new(StorageKey::TreasuryBalances),
+            treasury_tokens: UnorderedSet::new(StorageKey::TreasuryTokens),
+            treasury_payouts: UnorderedMap::new(StorageKey::TreasuryPayouts),
+            spending_limits: UnorderedMap::new(StorageKey::SpendingLimits),
+            treasury_streams: Vector::new(StorageKey::TreasuryStreams),
+            treasury_movements: Vector::new(StorageKey::TreasuryMovements),
         };
 
         for operator in operator_ids {
@@ -141,9 +241,19 @@ impl ProposalsContract {
         self.admin_id = new_value;
     }
 
-    pub fn pay_to_account(&mut self, amount: U128, to: AccountId) -> Promise {
+    /// Create or replace the rules of a proposal category.
+    /// Existing proposals keep the rules they were created with.
+    pub fn upsert_proposal_category(&mut self, category: ProposalCategory) {
         self.assert_only_admin();
-        Promise::new(to).transfer(amount.0)
+        category.assert_valid();
+        self.categories.insert(&category.name, &category);
+    }
+
+    pub fn remove_proposal_category(&mut self, name: String) {
+        self.assert_only_admin();
+        if self.categories.remove(&name).is_none() {
+            panic!("Proposal category does not exist.");
+        }
     }
 
     // ************
@@ -156,24 +266,81 @@ impl ProposalsContract {
         self.voting_period = new_value.0;
     }
 
-    /// Update minimum asset amount to submit a proposal.
-    pub fn update_min_asset_token_amount(&mut self, new_value: U128) {
-        self.assert_only_operator();
-        self.min_asset_token_amount = new_value.0;
-    }
-
     /// Update minimum voting power to submit a proposal (proposal threshold).
     pub fn update_min_voting_power_amount(&mut self, new_value: U128) {
         self.assert_only_operator();
         self.min_voting_power_amount = new_value.0;
     }
 
+    /// Update the asset token bond to submit a proposal.
+    pub fn update_proposal_cost_in_asset_token(&mut self, new_value: U128) {
+        self.assert_only_operator();
+        self.proposal_cost_in_asset_token = new_value.0;
+    }
+
     /// Update the storage cost in NEAR to submit a proposal.
     pub fn update_proposal_storage_near(&mut self, new_value: U128) {
         self.assert_only_operator();
         self.proposal_storage_near = new_value.0;
     }
 
+    /// Update the time in milliseconds an account must wait between proposals.
+    pub fn update_proposal_cooldown(&mut self, new_value: U64) {
+        self.assert_only_operator();
+        self.proposal_cooldown = new_value.0;
+    }
+
+    /// Update the max number of open proposals per account.
+    pub fn update_max_open_proposals(&mut self, new_value: u32) {
+        self.assert_only_operator();
+        self.max_open_proposals = new_value;
+    }
+
+    /// Review a Draft proposal and move it to Active, so the creator can start the voting period.
+    pub fn approve_proposal(&mut self, proposal_id: ProposalId) {
+        self.assert_only_operator();
+        self.assert_proposal_is_active_or_draft(proposal_id);
+        let mut proposal = self.internal_get_proposal(&proposal_id);
+        require!(proposal.status == ProposalState::Draft, "Proposal is not in draft state");
+        self.internal_update_proposal_status(
+            &mut proposal,
+            ProposalState::Active,
+            env::signer_account_id(),
+        );
+    }
+
+    /// Mark an Accepted proposal as Executed, once its on-chain actions are performed.
+    pub fn set_proposal_executed(&mut self, proposal_id: ProposalId) {
+        self.assert_only_operator();
+        let mut proposal = self.internal_get_proposal(&proposal_id);
+        // Proposals without a voting period are never executable here.
+        let end = proposal
+            .vote_end_timestamp
+            .expect("Proposal voting period has not ended");
+        require!(
+            get_current_epoch_millis() >= end + proposal.category.execution_delay,
+            "Proposal execution delay has not passed"
+        );
+        self.internal_update_proposal_status(
+            &mut proposal,
+            ProposalState::Executed,
+            env::signer_account_id(),
+        );
+        self.internal_execute_treasury_payout(proposal_id);
+    }
+
+    /// Update the NEAR paid to keepers for each finalized proposal.
+    pub fn update_keeper_reward(&mut self, new_value: U128) {
+        self.assert_only_operator();
+        self.keeper_reward = new_value.0;
+    }
+
+    /// Update how votes are reconciled when voters unlock voting power mid-vote.
+    pub fn update_vote_reconciliation(&mut self, new_value: VoteReconciliationMode) {
+        self.assert_only_operator();
+        self.vote_reconciliation = new_value;
+    }
+
     /// Update quorum floor: percent of all voting power need to vote yes for the proposal to pass.
     pub fn update_quorum_floor(&mut self, new_value: u16) {
         self.assert_only_operator();
@@ -182,22 +349,27 @@ impl ProposalsContract {
         self.quorum_floor = new_value;
     }
 
+    /// Enable, update or disable (`None`) the adaptive quorum mode.
+    pub fn update_adaptive_quorum(&mut self, new_value: Option<AdaptiveQuorumConfig>) {
+        self.assert_only_operator();
+        if let Some(config) = &new_value {
+            config.assert_valid();
+        }
+        self.adaptive_quorum = new_value;
+    }
+
     // ************
     // *  *
     // ************
 
+    /// The voting period can only start once the proposal is reviewed by an operator.
     pub fn start_voting_period(&mut self, proposal_id: ProposalId) {
         self.assert_only_operator_or_creator(proposal_id);
-        self.assert_proposal_is_active_or_draft(proposal_id);
-        ext_proposal_vote::ext(self.staking_position_contract_address.clone())
-            .with_static_gas(GAS_FOR_GET_VOTING_POWER)
-            .with_attached_deposit(1)
-            .get_total_voting_power()
-            .then(
-                Self::ext(env::current_account_id())
-                    .with_static_gas(GAS_FOR_RESOLVE_VOTE)
-                    .start_voting_period_callback(proposal_id),
-            );
+        require!(
+            self.internal_get_proposal_state(proposal_id) == ProposalState::Active,
+            "Proposal is not in active state"
+        );
+        self.internal_start_voting_period(proposal_id);
     }
 
     #[private]
@@ -206,81 +378,125 @@ impl ProposalsContract {
         let mut proposal = self.internal_get_proposal(&proposal_id);
         let now = get_current_epoch_millis();
         proposal.vote_start_timestamp = Some(now);
-        proposal.vote_end_timestamp = Some(now + self.voting_period);
-        proposal.draft = false;
-        proposal.v_power_quorum_to_reach = Some(self.internal_get_quorum(total_voting_power));
-        self.proposals.insert(&proposal_id, &proposal);
+        proposal.vote_end_timestamp = Some(now + proposal.category.voting_period);
+        let quorum_floor = self.internal_get_quorum_floor(&proposal.category);
+        proposal.v_power_quorum_to_reach =
+            Some(self.internal_get_quorum(total_voting_power, quorum_floor));
+        proposal.total_voting_power = Some(total_voting_power);
+        proposal.voting_round = Some(self.voting_rounds);
+        self.voting_rounds += 1;
+        self.internal_update_proposal_status(
+            &mut proposal,
+            ProposalState::VotingProcess,
+            env::signer_account_id(),
+        );
     }
 
     // *******************************
     // * Proposal creators functions *
     // *******************************
 
+    /// Any account with the minimum voting power (proposal threshold) can submit
+    /// a proposal, subject to the proposer rate limits. New proposals start as Draft.
+    /// The attached NEAR and the `proposal_cost_in_asset_token`, taken from the
+    /// asset tokens deposited with `ft_transfer_call`, are locked as proposal bond.
+    /// The proposal follows the rules of its `category`, or the global settings if `None`.
+    /// With `offchain_content` the body is kept off-chain, and `body` must be empty.
     #[payable]
-    pub fn create_proposal(
-        &mut self,
-        title: String,
-        short_description: String,
-        body: String,
-        data: String,
-        extra: String,
-    ) {
+    pub fn create_proposal(&mut self, content: ProposalContent, category: Option<String>) {
         self.assert_open_for_new_proposals();
-        // self.assert_proposal_storage_is_covered();
-        self.assert_only_operator();
+        content.assert_valid();
+        self.assert_proposal_storage_is_covered();
+        let creator_id = env::predecessor_account_id();
+        self.assert_proposer_limits(&creator_id);
+        let category = self.internal_get_category(&category);
+        let asset_token_bond = self.internal_lock_asset_token_bond(&creator_id);
         ext_proposal_vote::ext(self.staking_position_contract_address.clone())
             .with_static_gas(GAS_FOR_GET_VOTING_POWER)
             .with_attached_deposit(1)
-            .get_all_locking_positions(env::predecessor_account_id())
+            .get_all_locking_positions(creator_id.clone())
             .then(
                 Self::ext(env::current_account_id())
                     .with_static_gas(GAS_FOR_RESOLVE_VOTE)
-                    .create_proposal_callback(title, short_description, body, data, extra),
+                    .create_proposal_callback(
+                        creator_id,
+                        content,
+                        category,
+                        U128::from(env::attached_deposit()),
+                        U128::from(asset_token_bond),
+                    ),
             );
     }
 
+    /// `creator_id` is the account that called `create_proposal` and locked the bond.
+    /// If the proposal threshold or the proposer limits are not met, the bond is returned to the creator.
     #[private]
     pub fn create_proposal_callback(
         &mut self,
-        title: String,
-        short_description: String,
-        body: String,
-        data: String,
-        extra: String,
-    ) -> ProposalId {
-        let total_v_power = self.internal_get_user_total_voting_power_from_promise();
-        self.assert_proposal_threshold(total_v_power);
+        creator_id: AccountId,
+        content: ProposalContent,
+        category: ProposalCategory,
+        near_bond: U128,
+        asset_token_bond: U128,
+    ) -> Option<ProposalId> {
+        if !near_sdk::is_promise_success()
+            || !self.internal_check_proposal_threshold(
+                self.internal_get_user_total_voting_power_from_promise(),
+            )
+        {
+            log!("Proposal threshold does not reached. Returning bond to {}", &creator_id);
+            self.internal_return_unused_bond(&creator_id, near_bond.0, asset_token_bond.0);
+            return None;
+        }
+        // Limits are checked again, another proposal could be created meanwhile.
+        if self.internal_proposer_is_on_cooldown(&creator_id)
+            || self.internal_proposer_has_max_open_proposals(&creator_id)
+        {
+            log!("Proposer limits reached. Returning bond to {}", &creator_id);
+            self.internal_return_unused_bond(&creator_id, near_bond.0, asset_token_bond.0);
+            return None;
+        }
         let id = self.proposals.len() as ProposalId;
-        self.internal_create_proposal(id, title, short_description, body, data, extra);
-        id
+        self.internal_create_proposal(id, creator_id.clone(), content, category);
+        if near_bond.0 > 0 || asset_token_bond.0 > 0 {
+            let bond = ProposalBond {
+                creator_id,
+                near_amount: near_bond.0,
+                asset_token_amount: asset_token_bond.0,
+            };
+            self.bonds.insert(&id, &bond);
+        }
+        Some(id)
     }
 
+    /// Cancel a Draft or Active proposal. The bond is resolved by the caller, see `ProposalBond`.
     pub fn cancel_proposal(&mut self, proposal_id: ProposalId) {
         self.assert_only_operator_or_creator(proposal_id);
         self.assert_proposal_is_active_or_draft(proposal_id);
         let mut proposal = self.internal_get_proposal(&proposal_id);
-        proposal.canceled = true;
-        self.proposals.insert(&proposal_id, &proposal);
+        let withdrawn = proposal.creator_id == env::predecessor_account_id();
+        self.internal_update_proposal_status(
+            &mut proposal,
+            ProposalState::Canceled,
+            env::signer_account_id(),
+        );
+
+        if withdrawn {
+            self.internal_refund_bond(proposal_id);
+        } else {
+            self.internal_slash_bond(proposal_id);
+        }
     }
 
-    pub fn update_proposal(
-        &mut self,
-        proposal_id: ProposalId,
-        title: String,
-        short_description: String,
-        body: String,
-        data: String,
-        extra: String,
-    ) {
+    /// Append a new revision of the proposal content. Not allowed once voting has started.
+    pub fn update_proposal(&mut self, proposal_id: ProposalId, content: ProposalContent) {
         self.assert_only_creator(proposal_id);
         self.assert_proposal_is_active_or_draft(proposal_id);
+        content.assert_valid();
         let mut proposal = self.internal_get_proposal(&proposal_id);
-        proposal.title = title;
-        proposal.short_description = short_description;
-        proposal.body = body;
-        proposal.data = data;
-        proposal.extra = extra;
+        proposal.set_content(content);
         self.proposals.insert(&proposal_id, &proposal);
+        self.internal_push_revision(&proposal);
     }
 
     pub fn get_my_proposals(&self, proposer_id: AccountId) -> Vec<ProposalId> {
@@ -305,11 +521,57 @@ impl ProposalsContract {
         U128::from(self.proposal_storage_near)
     }
 
+    pub fn get_proposal_cost_in_asset_token(&self) -> U128 {
+        U128::from(self.proposal_cost_in_asset_token)
+    }
+
+    pub fn get_bond_deposit(&self, account_id: AccountId) -> U128 {
+        U128::from(self.internal_get_bond_deposit(&account_id))
+    }
+
+    pub fn get_proposal_bond(&self, proposal_id: ProposalId) -> Option<ProposalBondJSON> {
+        self.bonds
+            .get(&proposal_id)
+            .map(|bond| bond.to_json(proposal_id))
+    }
+
+    /// Bonds of the proposals that have not been refunded or slashed yet.
+    pub fn get_pending_bonds(&self, from_index: u32, limit: u32) -> Vec<ProposalBondJSON> {
+        let keys = self.bonds.keys_as_vector();
+        let start = from_index as u64;
+        let end = std::cmp::min(start + limit as u64, keys.len());
+        (start..end)
+            .map(|index| {
+                let proposal_id = keys.get(index).unwrap();
+                self.bonds.get(&proposal_id).unwrap().to_json(proposal_id)
+            })
+            .collect()
+    }
+
     pub fn get_proposal_votes(&self, proposal_id: ProposalId) -> ProposalVoteJson {
         let proposal_vote = self.internal_get_proposal_vote(proposal_id);
         proposal_vote.to_json()
     }
 
+    pub fn get_proposal_voters(
+        &self,
+        proposal_id: ProposalId,
+        from_index: u32,
+        limit: u32,
+    ) -> Vec<VoteJson> {
+        self.internal_get_proposal_vote(proposal_id)
+            .get_voters(from_index, limit)
+    }
+
+    /// Deterministic hash over the tally and the votes, to verify results off-chain.
+    /// The votes are hashed one by one, page them with `get_proposal_voters`.
+    pub fn get_proposal_result_digest(&self, proposal_id: ProposalId) -> Base58CryptoHash {
+        Base58CryptoHash::from(
+            self.internal_get_proposal_vote(proposal_id)
+                .result_digest(proposal_id),
+        )
+    }
+
     pub fn get_quorum_reached(&self, proposal_id: ProposalId) -> bool {
         self.assert_only_operator();
 
@@ -318,7 +580,8 @@ impl ProposalsContract {
 
     pub fn get_proposal_vote_succeeded(&self, proposal_id: ProposalId) -> bool {
         let proposal_vote = self.internal_get_proposal_vote(proposal_id);
-        proposal_vote.for_votes > proposal_vote.against_votes
+        let proposal = self.internal_get_proposal(&proposal_id);
+        proposal.category.is_approved(&proposal_vote)
     }
 
     pub fn get_proposal_state(&self, proposal_id: ProposalId) -> ProposalState {
@@ -330,6 +593,22 @@ impl ProposalsContract {
         proposal.to_json()
     }
 
+    /// Check the supplied off-chain body against the hash stored in the proposal.
+    pub fn verify_proposal_content(&self, proposal_id: ProposalId, content: String) -> bool {
+        match self.internal_get_proposal(&proposal_id).offchain_content {
+            Some(offchain_content) => offchain_content.verify(&content),
+            None => false,
+        }
+    }
+
+    /// Content history of the proposal, from the first revision.
+    pub fn get_proposal_revisions(&self, proposal_id: ProposalId) -> Vec<ProposalRevisionJSON> {
+        self.internal_get_revisions(proposal_id)
+            .iter()
+            .map(|revision| revision.to_json())
+            .collect()
+    }
+
     pub fn get_proposals(&self, from_index: u32, limit: u32) -> Option<Vec<ProposalJSON>> {
         let mut result = Vec::<ProposalJSON>::new();
 
@@ -349,6 +628,21 @@ impl ProposalsContract {
         Some(result)
     }
 
+    /// Paginate the proposals in a given state.
+    pub fn get_proposals_by_state(
+        &self,
+        state: ProposalState,
+        from_index: u32,
+        limit: u32,
+    ) -> Vec<ProposalJSON> {
+        let ids = self.internal_get_proposals_by_state(state);
+        ids.iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .map(|proposal_id| self.internal_get_proposal(&proposal_id).to_json())
+            .collect()
+    }
+
     pub fn get_user_proposals_ids(&self, proposer_id: AccountId) -> Vec<ProposalId> {
         self.proposers.get(&proposer_id).unwrap_or(Vec::new())
     }
@@ -361,14 +655,55 @@ impl ProposalsContract {
         }
     }
 
+    pub fn get_proposal_category(&self, name: String) -> Option<ProposalCategory> {
+        self.categories.get(&name)
+    }
+
+    pub fn get_proposal_categories(&self) -> Vec<ProposalCategory> {
+        self.categories.values().collect()
+    }
+
     pub fn get_quorum_floor(&self) -> BasisPoints {
         self.quorum_floor
     }
 
+    pub fn get_adaptive_quorum(&self) -> Option<AdaptiveQuorumConfig> {
+        self.adaptive_quorum.clone()
+    }
+
+    pub fn get_turnout_ema(&self) -> Option<BasisPoints> {
+        self.turnout_ema_bp
+    }
+
+    /// Quorum floor, in basis points, for a proposal of the category starting its voting now.
+    pub fn get_next_quorum_floor(&self, category: Option<String>) -> BasisPoints {
+        self.internal_get_quorum_floor(&self.internal_get_category(&category))
+    }
+
+    pub fn get_turnout_history(&self, from_index: u32, limit: u32) -> Vec<TurnoutRecord> {
+        self.turnout_history
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+
     pub fn get_proposal_threshold(&self) -> U128 {
         U128::from(self.min_voting_power_amount)
     }
 
+    pub fn get_proposal_cooldown(&self) -> U64 {
+        U64::from(self.proposal_cooldown)
+    }
+
+    pub fn get_max_open_proposals(&self) -> u32 {
+        self.max_open_proposals
+    }
+
+    pub fn get_open_proposals_count(&self, proposer_id: AccountId) -> u32 {
+        self.internal_count_open_proposals(&proposer_id)
+    }
+
     pub fn get_total_voters(&self) -> String {
         self.voters.len().to_string()
     }
@@ -377,6 +712,10 @@ impl ProposalsContract {
         self.internal_proposal_is_active_or_draft(proposal_id)
     }
 
+    pub fn get_vote_reconciliation(&self) -> VoteReconciliationMode {
+        self.vote_reconciliation
+    }
+
     pub fn get_staking_position_contract_address(&self) -> &AccountId {
         &self.staking_position_contract_address
     }
@@ -385,21 +724,21 @@ impl ProposalsContract {
     // * Voter functions *
     // *******************
 
+    /// Vote with all the locked voting power. Use `VoteType::Split` to divide
+    /// the voting power among For, Against and Abstain.
     pub fn vote_proposal(
         &mut self,
         proposal_id: ProposalId,
         vote: VoteType,
         memo: String,
     ) {
+        vote.assert_valid();
         self.assert_proposal_is_on_voting(&proposal_id);
         self.assert_has_not_voted(proposal_id, env::predecessor_account_id());
-        ext_proposal_vote::ext(self.staking_position_contract_address.clone())
-            .with_static_gas(GAS_FOR_GET_VOTING_POWER)
-            .with_attached_deposit(1)
-            .get_all_locking_positions(env::predecessor_account_id())
+        self.internal_get_vote_locking_positions(&env::predecessor_account_id(), proposal_id)
             .then(
                 Self::ext(env::current_account_id())
-                    .with_static_gas(GAS_FOR_RESOLVE_VOTE)
+                    .with_static_gas(GAS_FOR_RESOLVE_VOTE_PROPOSAL)
                     .vote_proposal_callback(
                         proposal_id.clone(),
                         env::predecessor_account_id(),
@@ -418,6 +757,8 @@ impl ProposalsContract {
         memo: String,
     ) {
         let total_v_power = self.internal_get_user_total_voting_power_from_promise();
+        // Another vote of the account could be counted meanwhile.
+        self.assert_has_not_voted(proposal_id, voter_id.clone());
         let mut voter = self.internal_get_voter(&voter_id);
         assert!(
             total_v_power > 0,
@@ -426,30 +767,25 @@ impl ProposalsContract {
         );
         let mut proposal_vote = self.internal_get_proposal_vote(proposal_id);
         let vote_v_power = total_v_power;
+        let proposal = self.internal_get_proposal(&proposal_id);
+        let revision = proposal.revision;
         let vote = Vote::new(
             proposal_id.clone(),
             vote_type.clone(),
             vote_v_power.clone(),
+            revision,
             memo.clone(),
+            get_current_epoch_millis(),
         );
 
         proposal_vote
             .has_voted
             .insert(&voter_id.clone(), &vote.clone());
-        match vote_type {
-            VoteType::For => {
-                proposal_vote.for_votes += vote_v_power;
-            }
-            VoteType::Against => {
-                proposal_vote.against_votes += vote_v_power;
-            }
-            VoteType::Abstain => {
-                proposal_vote.abstain_votes += vote_v_power;
-            }
-        }
+        proposal_vote.add_vote(&voter_id, &vote);
         self.votes.insert(&proposal_id.clone(), &proposal_vote);
         voter.votes.insert(&proposal_id.clone(), &vote.clone());
         self.voters.insert(&voter_id.clone(), &voter);
+        self.internal_record_vote_stats(&voter_id, &proposal);
     }
 
     pub fn remove_vote_proposal(&mut self, proposal_id: ProposalId) {
@@ -460,20 +796,12 @@ impl ProposalsContract {
         let user_vote = proposal_vote.has_voted.get(&voter_id).unwrap();
         let mut voter = self.internal_get_voter(&voter_id);
 
-        match user_vote.vote_type {
-            VoteType::For => {
-                proposal_vote.for_votes -= user_vote.voting_power;
-            }
-            VoteType::Against => {
-                proposal_vote.against_votes -= user_vote.voting_power;
-            }
-            VoteType::Abstain => {
-                proposal_vote.abstain_votes -= user_vote.voting_power;
-            }
-        }
+        proposal_vote.remove_vote(&voter_id, &user_vote);
         proposal_vote.has_voted.remove(&voter_id);
         self.votes.insert(&proposal_id, &proposal_vote);
         voter.votes.remove(&proposal_id);
+        self.internal_record_removed_vote_stats(&voter_id);
+        self.internal_reset_voter_unlocking(&voter_id, &voter);
 
         if voter.votes.is_empty() {
             self.voters.remove(&voter_id);
@@ -506,20 +834,62 @@ impl ProposalsContract {
     // * BOT FUNCTIONS *
     // *********
 
+    /// Store the voting result once the voting period is over.
     pub fn process_voting_status(&mut self, proposal_id: ProposalId) {
         self.assert_only_operator();
-        if !self.internal_proposal_is_on_voting(&proposal_id) {
-            return;
-        }
         let mut proposal = self.internal_get_proposal(&proposal_id);
-        if get_current_epoch_millis() >= proposal.vote_end_timestamp.unwrap() {
-            if self.internal_is_quorum_reached(proposal_id) {
-                // TODO EXECUTE
-                proposal.executed = true;
-                self.proposals.insert(&proposal_id, &proposal);
-            }
+        if self.internal_proposal_voting_ended(&proposal) {
+            self.internal_finalize_proposal(&mut proposal, env::signer_account_id());
         }
     }
+
+    /// Permissionless: examine up to `limit` proposals on voting, starting at `from_index`
+    /// of the VotingProcess index, and finalize those whose voting period is over.
+    /// The caller is paid `keeper_reward` per finalized proposal, from the keeper budget.
+    pub fn finalize_proposals(&mut self, from_index: u32, limit: u32) -> u32 {
+        let keeper_id = env::predecessor_account_id();
+        // Ids are collected first, finalizing removes them from the index.
+        let examined_ids: Vec<ProposalId> = self
+            .internal_get_proposals_by_state(ProposalState::VotingProcess)
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect();
+        let ended_ids: Vec<ProposalId> = examined_ids
+            .into_iter()
+            .filter(|proposal_id| {
+                self.internal_proposal_voting_ended(&self.internal_get_proposal(proposal_id))
+            })
+            .collect();
+
+        for proposal_id in ended_ids.iter() {
+            let mut proposal = self.internal_get_proposal(proposal_id);
+            self.internal_finalize_proposal(&mut proposal, keeper_id.clone());
+        }
+
+        let finalized = ended_ids.len() as u32;
+        let reward = std::cmp::min(self.keeper_reward * finalized as u128, self.keeper_budget);
+        if reward > 0 {
+            self.keeper_budget -= reward;
+            log!("KEEPER: {} rewarded to {}", reward, &keeper_id);
+            Promise::new(keeper_id).transfer(reward);
+        }
+        finalized
+    }
+
+    /// Anyone can fund the budget used to reward the keepers.
+    #[payable]
+    pub fn fund_keeper_budget(&mut self) {
+        self.keeper_budget += env::attached_deposit();
+    }
+
+    pub fn get_keeper_budget(&self) -> U128 {
+        U128::from(self.keeper_budget)
+    }
+
+    pub fn get_keeper_reward(&self) -> U128 {
+        U128::from(self.keeper_reward)
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -1,8 +1,12 @@
 use crate::*;
+use crate::events::emit_event;
+use crate::utils::generate_hash_id;
+use near_sdk::serde_json::json;
+use near_sdk::CryptoHash;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::serde::{Deserialize, Serialize};
 
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
 #[serde(crate = "near_sdk::serde")]
 pub enum ProposalState {
     Draft,  // proposer share the idea. Giving awareness from the community via discussion or poll
@@ -14,6 +18,54 @@ pub enum ProposalState {
     Canceled, // canceled by manager after community awareness
 }
 
+impl ProposalState {
+    /// Valid transitions of the proposal lifecycle.
+    pub(crate) fn can_transition_to(&self, next: &ProposalState) -> bool {
+        matches!(
+            (self, next),
+            (ProposalState::Draft, ProposalState::Active)
+                | (ProposalState::Draft, ProposalState::Canceled)
+                | (ProposalState::Active, ProposalState::VotingProcess)
+                | (ProposalState::Active, ProposalState::Canceled)
+                | (ProposalState::VotingProcess, ProposalState::Accepted)
+                | (ProposalState::VotingProcess, ProposalState::Rejected)
+                | (ProposalState::Accepted, ProposalState::Executed)
+        )
+    }
+
+    /// Proposals that are not resolved yet.
+    pub(crate) fn is_open(&self) -> bool {
+        matches!(
+            self,
+            ProposalState::Draft | ProposalState::Active | ProposalState::VotingProcess
+        )
+    }
+}
+
+/// Record of a proposal state transition.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalStatusChange {
+    pub state: ProposalState,
+    pub timestamp: EpochMillis,
+    pub actor: AccountId,
+}
+
+/// Final result of the voting, stored when the proposal is finalized.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalResult {
+    pub state: ProposalState,
+    pub for_votes: U128,
+    pub against_votes: U128,
+    pub abstain_votes: U128,
+    /// Total voting power that participated in the voting.
+    pub turnout: U128,
+    /// Turnout over the total voting power, in basis points.
+    pub turnout_bp: BasisPoints,
+    pub finalized_at: EpochMillis,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(crate = "near_sdk::serde")]
 pub struct ProposalJSON {
@@ -23,15 +75,31 @@ pub struct ProposalJSON {
     pub body: String,
     pub data: String,
     pub extra: String,
+    pub offchain_content: Option<OffchainContent>,
+    /// Current revision of the content, see `get_proposal_revisions`.
+    pub revision: u32,
     pub creator_id: AccountId,
+    pub creation_timestamp: EpochMillis,
+    pub category: ProposalCategory,
     pub vote_start_timestamp: Option<EpochMillis>,
     pub vote_end_timestamp: Option<EpochMillis>,
+    /// Sequence number of the voting round, assigned when voting starts.
+    pub voting_round: Option<u64>,
+    pub status: ProposalState,
+    pub status_history: Vec<ProposalStatusChange>,
+    pub council_history: Vec<CouncilRecord>,
+    /// Flags of the previous API, derived from `status`.
     pub draft: bool,
     pub executed: bool,
     pub canceled: bool,
-    pub v_power_quorum_to_reach: Option<U128>
+    pub v_power_quorum_to_reach: Option<U128>,
+    pub total_voting_power: Option<U128>,
+    pub result: Option<ProposalResult>,
 }
 
+/// The `draft`, `executed` and `canceled` flags are replaced by `status`. The storage
+/// layout is not compatible with the previous one, there is no state migration and
+/// the contract must be redeployed with a fresh state.
 #[derive(BorshDeserialize, BorshSerialize, Serialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct Proposal {
@@ -41,41 +109,106 @@ pub struct Proposal {
     pub body: String,
     pub data: String,
     pub extra: String,
+    pub offchain_content: Option<OffchainContent>,
+    /// Current revision of the content, see `get_proposal_revisions`.
+    pub revision: u32,
     pub creator_id: AccountId,
+    pub creation_timestamp: EpochMillis,
+    pub category: ProposalCategory,
     pub vote_start_timestamp: Option<EpochMillis>,
     pub vote_end_timestamp: Option<EpochMillis>,
-    pub draft: bool,
-    pub executed: bool,
-    pub canceled: bool,
+    /// Sequence number of the voting round, assigned when voting starts.
+    pub voting_round: Option<u64>,
+    pub status: ProposalState,
+    pub status_history: Vec<ProposalStatusChange>,
+    /// Fast-track and emergency signatures of the council.
+    pub council_history: Vec<CouncilRecord>,
     pub v_power_quorum_to_reach: Option<VotingPower>,
+    /// Snapshot of the total voting power when the voting period started.
+    pub total_voting_power: Option<VotingPower>,
+    pub result: Option<ProposalResult>,
 }
 
 impl Proposal {
     pub(crate) fn new(
         id: ProposalId,
-        title: String,
-        short_description: String,
-        body: String,
-        data: String,
-        extra: String,
+        creator_id: AccountId,
+        content: ProposalContent,
+        category: ProposalCategory,
     ) -> Self {
+        let creation_timestamp = get_current_epoch_millis();
         Proposal {
             proposal_id: id,
-            title,
-            short_description,
-            body,
-            data,
-            extra,
-            creator_id: env::signer_account_id(),
+            title: content.title,
+            short_description: content.short_description,
+            body: content.body,
+            data: content.data,
+            extra: content.extra,
+            offchain_content: content.offchain_content,
+            revision: 0,
+            creator_id: creator_id.clone(),
+            creation_timestamp,
+            category,
             vote_end_timestamp: None,
             vote_start_timestamp: None,
-            draft: true,
-            executed: false,
-            canceled: false,
+            voting_round: None,
+            status: ProposalState::Draft,
+            status_history: vec![ProposalStatusChange {
+                state: ProposalState::Draft,
+                timestamp: creation_timestamp,
+                actor: creator_id,
+            }],
+            council_history: Vec::new(),
             v_power_quorum_to_reach: None,
+            total_voting_power: None,
+            result: None,
         }
     }
 
+    /// Replace the content with a new revision.
+    pub(crate) fn set_content(&mut self, content: ProposalContent) {
+        self.title = content.title;
+        self.short_description = content.short_description;
+        self.body = content.body;
+        self.data = content.data;
+        self.extra = content.extra;
+        self.offchain_content = content.offchain_content;
+        self.revision += 1;
+    }
+
+    /// Hash of the proposal content, to identify a revision.
+    pub(crate) fn content_hash(&self) -> CryptoHash {
+        let content = (
+            &self.title,
+            &self.short_description,
+            &self.body,
+            &self.data,
+            &self.extra,
+            &self.offchain_content,
+        );
+        env::sha256_array(&content.try_to_vec().unwrap())
+    }
+
+    /// Move the proposal to a new state, only valid transitions are allowed.
+    /// Open proposals of an emergency category can also be accepted by the council.
+    pub(crate) fn set_status(&mut self, state: ProposalState, actor: AccountId) {
+        let emergency = self.category.emergency
+            && self.status.is_open()
+            && state == ProposalState::Accepted;
+        assert!(
+            self.status.can_transition_to(&state) || emergency,
+            "Invalid proposal transition from {:?} to {:?}",
+            self.status,
+            state
+        );
+        self.status = state;
+        self.status_history.push(ProposalStatusChange {
+            state,
+            timestamp: get_current_epoch_millis(),
+            actor,
+        });
+    }
+
     pub(crate) fn to_json(&self) -> ProposalJSON {
         let quorum_to_reach = match self.v_power_quorum_to_reach {
             Some(quorum_to_reach) => Some(U128::from(quorum_to_reach)),
@@ -89,13 +222,23 @@ impl Proposal {
             short_description: self.short_description.clone(),
             data: self.data.clone(),
             extra: self.extra.clone(),
+            offchain_content: self.offchain_content.clone(),
+            revision: self.revision,
             creator_id: self.creator_id.clone(),
+            creation_timestamp: self.creation_timestamp,
+            category: self.category.clone(),
             vote_end_timestamp: self.vote_end_timestamp.clone(),
             vote_start_timestamp: self.vote_start_timestamp.clone(),
-            executed: self.executed,
-            canceled: self.canceled,
-            draft: self.draft,
-            v_power_quorum_to_reach: quorum_to_reach
+            voting_round: self.voting_round,
+            status: self.status,
+            status_history: self.status_history.clone(),
+            council_history: self.council_history.clone(),
+            draft: self.status == ProposalState::Draft,
+            executed: self.status == ProposalState::Executed,
+            canceled: self.status == ProposalState::Canceled,
+            v_power_quorum_to_reach: quorum_to_reach,
+            total_voting_power: self.total_voting_power.map(U128::from),
+            result: self.result.clone(),
         }
     }
 }
@@ -104,48 +247,133 @@ impl ProposalsContract {
     pub(crate) fn internal_create_proposal(
         &mut self,
         proposal_id: ProposalId,
-        title: String,
-        short_description: String,
-        body: String,
-        data: String,
-        extra: String,
+        creator_id: AccountId,
+        content: ProposalContent,
+        category: ProposalCategory,
     ) -> ProposalId {
-        let proposal = Proposal::new(proposal_id, title, short_description, body, data, extra);
+        let proposal = Proposal::new(proposal_id, creator_id, content, category);
         self.proposals.insert(&proposal_id, &proposal);
+        self.internal_push_revision(&proposal);
+        self.internal_index_proposal_state(proposal_id, None, proposal.status);
         let mut proposer = self.internal_get_proposer(proposal.creator_id.clone());
         proposer.push(proposal_id);
         self.proposers.insert(&proposal.creator_id, &proposer);
+        self.internal_record_proposal_created_stats(&proposal.creator_id);
         // let voter = self.internal_get_voter(&proposal.creator_id);
         // voter.used_voting_power += self.min_voting_power_amount;
         // self.voters.insert(&proposal.creator_id, &voter);
         proposal.proposal_id.into()
     }
 
+    pub(crate) fn internal_start_voting_period(&self, proposal_id: ProposalId) -> Promise {
+        ext_proposal_vote::ext(self.staking_position_contract_address.clone())
+            .with_static_gas(GAS_FOR_GET_VOTING_POWER)
+            .with_attached_deposit(1)
+            .get_total_voting_power()
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_VOTE)
+                    .start_voting_period_callback(proposal_id),
+            )
+    }
+
     pub(crate) fn internal_get_proposal_state(
         &self,
         proposal_id: ProposalId
     ) -> ProposalState {
-        let proposal = self.internal_get_proposal(&proposal_id);
-        if proposal.executed {
-            return ProposalState::Executed;
-        } else if proposal.canceled {
-            return ProposalState::Canceled;
-        } else if proposal.draft {
-            return ProposalState::Draft;
-        }
+        self.internal_get_proposal(&proposal_id).status
+    }
 
-        if self.internal_proposal_is_on_voting(&proposal_id) {
-            return ProposalState::VotingProcess;
-        } else if self.internal_proposal_is_active(proposal_id) {
-            return ProposalState::Active;
-        }
+    /// Proposal on voting whose voting period is over.
+    pub(crate) fn internal_proposal_voting_ended(&self, proposal: &Proposal) -> bool {
+        proposal.status == ProposalState::VotingProcess
+            && get_current_epoch_millis() > proposal.vote_end_timestamp.unwrap()
+    }
 
-        if self.internal_is_quorum_reached(proposal_id)
-            && self.get_proposal_vote_succeeded(proposal_id)
-        {
-            return ProposalState::Accepted;
+    /// Store the final result of an ended proposal, and resolve its bond.
+    pub(crate) fn internal_finalize_proposal(&mut self, proposal: &mut Proposal, actor: AccountId) {
+        let proposal_id = proposal.proposal_id;
+        let proposal_vote = self.internal_get_proposal_vote(proposal_id);
+        let quorum_reached = self.internal_is_quorum_reached(proposal_id);
+        let state = if quorum_reached && self.get_proposal_vote_succeeded(proposal_id) {
+            // TODO EXECUTE
+            ProposalState::Accepted
         } else {
-            return ProposalState::Rejected;
+            ProposalState::Rejected
+        };
+
+        let turnout =
+            proposal_vote.for_votes + proposal_vote.against_votes + proposal_vote.abstain_votes;
+        let turnout_bp = match proposal.total_voting_power {
+            Some(total) if total > 0 => {
+                std::cmp::min(turnout * u128::from(ONE_HUNDRED) / total, u128::from(ONE_HUNDRED))
+                    as BasisPoints
+            }
+            _ => 0,
+        };
+        let result = ProposalResult {
+            state,
+            for_votes: U128::from(proposal_vote.for_votes),
+            against_votes: U128::from(proposal_vote.against_votes),
+            abstain_votes: U128::from(proposal_vote.abstain_votes),
+            turnout: U128::from(turnout),
+            turnout_bp,
+            finalized_at: get_current_epoch_millis(),
+        };
+        proposal.result = Some(result.clone());
+        self.internal_update_proposal_status(proposal, state, actor);
+        self.internal_record_turnout(proposal_id, turnout_bp);
+
+        if quorum_reached {
+            self.internal_refund_bond(proposal_id);
+        } else {
+            // Proposals ending without quorum are rejected as spam.
+            self.internal_slash_bond(proposal_id);
         }
+        emit_event(
+            "proposal_finalized",
+            json!({ "proposal_id": proposal_id, "result": result }),
+        );
+    }
+
+    /// Store the proposal transition and keep the state index updated.
+    pub(crate) fn internal_update_proposal_status(
+        &mut self,
+        proposal: &mut Proposal,
+        state: ProposalState,
+        actor: AccountId,
+    ) {
+        let previous = proposal.status;
+        proposal.set_status(state, actor);
+        self.proposals.insert(&proposal.proposal_id, proposal);
+        self.internal_index_proposal_state(proposal.proposal_id, Some(previous), state);
+        self.internal_record_proposal_outcome_stats(&proposal.creator_id, state);
+    }
+
+    fn internal_index_proposal_state(
+        &mut self,
+        proposal_id: ProposalId,
+        previous: Option<ProposalState>,
+        state: ProposalState,
+    ) {
+        if let Some(previous) = previous {
+            let mut ids = self.internal_get_proposals_by_state(previous);
+            ids.remove(&proposal_id);
+            self.proposals_by_state.insert(&previous, &ids);
+        }
+        let mut ids = self.internal_get_proposals_by_state(state);
+        ids.insert(&proposal_id);
+        self.proposals_by_state.insert(&state, &ids);
+    }
+
+    pub(crate) fn internal_get_proposals_by_state(
+        &self,
+        state: ProposalState,
+    ) -> UnorderedSet<ProposalId> {
+        self.proposals_by_state.get(&state).unwrap_or_else(|| {
+            UnorderedSet::new(StorageKey::ProposalsByStateIds {
+                hash_id: generate_hash_id(format!("{:?}", state)),
+            })
+        })
     }
 }
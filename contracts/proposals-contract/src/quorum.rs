@@ -0,0 +1,87 @@
+use crate::*;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Adaptive quorum: the quorum follows an exponential moving average (EMA)
+/// of the turnout of the finalized proposals. All values in basis points.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AdaptiveQuorumConfig {
+    /// Weight of the newest turnout in the moving average.
+    pub smoothing: BasisPoints,
+    /// Quorum is this share of the average turnout.
+    pub turnout_ratio: BasisPoints,
+    pub floor: BasisPoints,
+    pub ceiling: BasisPoints,
+}
+
+impl AdaptiveQuorumConfig {
+    pub(crate) fn assert_valid(&self) {
+        require!(
+            self.smoothing > 0 && self.smoothing <= ONE_HUNDRED,
+            "Incorrect smoothing basis points."
+        );
+        require!(
+            self.turnout_ratio <= ONE_HUNDRED,
+            "Incorrect turnout ratio basis points."
+        );
+        require!(
+            self.floor <= self.ceiling && self.ceiling <= ONE_HUNDRED,
+            "Incorrect quorum floor and ceiling basis points."
+        );
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TurnoutRecord {
+    pub proposal_id: ProposalId,
+    pub turnout_bp: BasisPoints,
+    /// Moving average after including this proposal.
+    pub turnout_ema_bp: BasisPoints,
+    pub timestamp: EpochMillis,
+}
+
+impl ProposalsContract {
+    /// Add the turnout of a finalized proposal to the moving average.
+    pub(crate) fn internal_record_turnout(&mut self, proposal_id: ProposalId, turnout_bp: BasisPoints) {
+        let smoothing = match &self.adaptive_quorum {
+            Some(config) => u32::from(config.smoothing),
+            None => u32::from(DEFAULT_TURNOUT_SMOOTHING),
+        };
+        let ema = match self.turnout_ema_bp {
+            Some(ema) => {
+                (u32::from(turnout_bp) * smoothing
+                    + u32::from(ema) * (u32::from(ONE_HUNDRED) - smoothing))
+                    / u32::from(ONE_HUNDRED)
+            }
+            None => u32::from(turnout_bp),
+        } as BasisPoints;
+        self.turnout_ema_bp = Some(ema);
+        self.turnout_history.push(&TurnoutRecord {
+            proposal_id,
+            turnout_bp,
+            turnout_ema_bp: ema,
+            timestamp: get_current_epoch_millis(),
+        });
+    }
+
+    /// Quorum in basis points for a proposal starting its voting period now.
+    /// The adaptive quorum never goes below the floor of the category.
+    pub(crate) fn internal_get_quorum_floor(&self, category: &ProposalCategory) -> BasisPoints {
+        match (&self.adaptive_quorum, self.turnout_ema_bp) {
+            (Some(config), Some(ema)) => {
+                let quorum = u32::from(ema) * u32::from(config.turnout_ratio)
+                    / u32::from(ONE_HUNDRED);
+                let adaptive = (quorum as BasisPoints).clamp(config.floor, config.ceiling);
+                std::cmp::max(category.quorum_floor, adaptive)
+            }
+            // No turnout history yet.
+            (Some(config), None) => {
+                let adaptive = category.quorum_floor.clamp(config.floor, config.ceiling);
+                std::cmp::max(category.quorum_floor, adaptive)
+            }
+            (None, _) => category.quorum_floor,
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use crate::*;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// How votes on active proposals are kept consistent with the voter locked voting power.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum VoteReconciliationMode {
+    /// When the locked voting power decreases, active votes shrink to the new voting power.
+    ShrinkVotes,
+    /// The staking contract blocks unlocking until the end of the voted proposals.
+    BlockUnlock,
+}
+
+#[near_bindgen]
+impl ProposalsContract {
+    /// Called by the staking contract when a staker unlocks voting power. The call
+    /// has a fixed gas, only the first proposals on voting are reconciled, the rest
+    /// with `reconcile_voter`.
+    pub fn on_locked_voting_power_decreased(&mut self, voter_id: VoterId, locked_voting_power: U128) {
+        require!(
+            env::predecessor_account_id() == self.staking_position_contract_address,
+            "Only the staking position contract can call this function."
+        );
+        if self.vote_reconciliation == VoteReconciliationMode::ShrinkVotes {
+            let examined = self.internal_reconcile_voter_votes(
+                &voter_id,
+                locked_voting_power.0,
+                0,
+                MAX_PROPOSALS_RECONCILED_ON_NOTIFICATION,
+            );
+            let on_voting = self.internal_get_proposals_by_state(ProposalState::VotingProcess).len();
+            if u64::from(examined) < on_voting {
+                log!(
+                    "RECONCILE: votes of {} pending from index {}, call reconcile_voter",
+                    voter_id,
+                    examined
+                );
+            }
+        }
+    }
+
+    /// Permissionless: re-verify the voter locked voting power and shrink its votes on
+    /// `limit` proposals on voting, starting at `from_index` of the VotingProcess index.
+    pub fn reconcile_voter(&mut self, voter_id: VoterId, from_index: u32, limit: u32) -> Promise {
+        ext_proposal_vote::ext(self.staking_position_contract_address.clone())
+            .with_static_gas(GAS_FOR_GET_VOTING_POWER)
+            .with_attached_deposit(1)
+            .get_all_locking_positions(voter_id.clone())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_VOTE)
+                    .reconcile_voter_callback(voter_id, from_index, limit),
+            )
+    }
+
+    #[private]
+    pub fn reconcile_voter_callback(&mut self, voter_id: VoterId, from_index: u32, limit: u32) {
+        let locked_voting_power = self.internal_get_user_total_voting_power_from_promise();
+        self.internal_reconcile_voter_votes(&voter_id, locked_voting_power, from_index, limit);
+    }
+}
+
+impl ProposalsContract {
+    /// Shrink the votes that exceed the voting power, on `limit` proposals on voting
+    /// from `from_index`. Returns the number of proposals examined.
+    pub(crate) fn internal_reconcile_voter_votes(
+        &mut self,
+        voter_id: &VoterId,
+        voting_power: VotingPower,
+        from_index: u32,
+        limit: u32,
+    ) -> u32 {
+        let mut voter = match self.voters.get(voter_id) {
+            Some(voter) => voter,
+            None => return 0,
+        };
+        let proposal_ids: Vec<ProposalId> = self
+            .internal_get_proposals_by_state(ProposalState::VotingProcess)
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect();
+        let exceeded_votes: Vec<Vote> = proposal_ids
+            .iter()
+            .filter_map(|proposal_id| voter.votes.get(proposal_id))
+            .filter(|vote| {
+                vote.voting_power > voting_power
+                    && self.internal_proposal_is_on_voting(&vote.proposal_id)
+            })
+            .collect();
+
+        for mut vote in exceeded_votes {
+            let mut proposal_vote = self.internal_get_proposal_vote(vote.proposal_id);
+            log!(
+                "RECONCILE: {} vote on proposal {} decreased from {} to {}",
+                voter_id,
+                vote.proposal_id,
+                vote.voting_power,
+                voting_power
+            );
+            proposal_vote.remove_vote(voter_id, &vote);
+            vote.voting_power = voting_power;
+            proposal_vote.add_vote(voter_id, &vote);
+            proposal_vote.has_voted.insert(voter_id, &vote);
+            self.votes.insert(&vote.proposal_id, &proposal_vote);
+            voter.votes.insert(&vote.proposal_id, &vote);
+        }
+        self.voters.insert(voter_id, &voter);
+        proposal_ids.len() as u32
+    }
+
+    /// Query the voter locking positions to count a vote on the proposal. In
+    /// BlockUnlock mode the staking contract first blocks unlocking until the end
+    /// of the voting period, and then returns the locking positions, so a vote is
+    /// only counted once the voting power is locked.
+    pub(crate) fn internal_get_vote_locking_positions(
+        &self,
+        voter_id: &VoterId,
+        proposal_id: ProposalId,
+    ) -> Promise {
+        let staking = ext_proposal_vote::ext(self.staking_position_contract_address.clone())
+            .with_static_gas(GAS_FOR_GET_VOTING_POWER);
+        match self.vote_reconciliation {
+            VoteReconciliationMode::ShrinkVotes => staking
+                .with_attached_deposit(1)
+                .get_all_locking_positions(voter_id.clone()),
+            VoteReconciliationMode::BlockUnlock => {
+                let vote_end = self
+                    .internal_get_proposal(&proposal_id)
+                    .vote_end_timestamp
+                    .expect("Proposal is not on voting period");
+                staking.lock_unlocking_until(voter_id.clone(), vote_end)
+            }
+        }
+    }
+
+    /// After a vote is removed, the unlocking lock is reset to the end of the
+    /// proposals still voted, or released when there is none.
+    pub(crate) fn internal_reset_voter_unlocking(&self, voter_id: &VoterId, voter: &Voter) {
+        if self.vote_reconciliation != VoteReconciliationMode::BlockUnlock {
+            return;
+        }
+        let until = self
+            .internal_get_proposals_by_state(ProposalState::VotingProcess)
+            .iter()
+            .filter(|proposal_id| voter.votes.get(proposal_id).is_some())
+            .filter_map(|proposal_id| self.internal_get_proposal(&proposal_id).vote_end_timestamp)
+            .max();
+        ext_proposal_vote::ext(self.staking_position_contract_address.clone())
+            .with_static_gas(GAS_FOR_LOCK_UNLOCKING)
+            .reset_unlocking_lock(voter_id.clone(), until);
+    }
+}
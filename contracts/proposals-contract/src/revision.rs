@@ -0,0 +1,81 @@
+use crate::*;
+use crate::utils::generate_hash_id;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::Base58CryptoHash;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::CryptoHash;
+
+/// Immutable version of the proposal content. Every update appends a new revision.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ProposalRevision {
+    pub revision: u32,
+    pub title: String,
+    pub short_description: String,
+    pub body: String,
+    pub data: String,
+    pub extra: String,
+    pub offchain_content: Option<OffchainContent>,
+    pub content_hash: CryptoHash,
+    pub timestamp: EpochMillis,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalRevisionJSON {
+    pub revision: u32,
+    pub title: String,
+    pub short_description: String,
+    pub body: String,
+    pub data: String,
+    pub extra: String,
+    pub offchain_content: Option<OffchainContent>,
+    pub content_hash: Base58CryptoHash,
+    pub timestamp: EpochMillis,
+}
+
+impl ProposalRevision {
+    pub(crate) fn from_proposal(proposal: &Proposal) -> Self {
+        ProposalRevision {
+            revision: proposal.revision,
+            title: proposal.title.clone(),
+            short_description: proposal.short_description.clone(),
+            body: proposal.body.clone(),
+            data: proposal.data.clone(),
+            extra: proposal.extra.clone(),
+            offchain_content: proposal.offchain_content.clone(),
+            content_hash: proposal.content_hash(),
+            timestamp: get_current_epoch_millis(),
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> ProposalRevisionJSON {
+        ProposalRevisionJSON {
+            revision: self.revision,
+            title: self.title.clone(),
+            short_description: self.short_description.clone(),
+            body: self.body.clone(),
+            data: self.data.clone(),
+            extra: self.extra.clone(),
+            offchain_content: self.offchain_content.clone(),
+            content_hash: Base58CryptoHash::from(self.content_hash),
+            timestamp: self.timestamp,
+        }
+    }
+}
+
+impl ProposalsContract {
+    pub(crate) fn internal_get_revisions(&self, proposal_id: ProposalId) -> Vector<ProposalRevision> {
+        self.revisions.get(&proposal_id).unwrap_or_else(|| {
+            Vector::new(StorageKey::ProposalRevisions {
+                hash_id: generate_hash_id(proposal_id.to_string()),
+            })
+        })
+    }
+
+    /// Store the current content of the proposal as a new revision.
+    pub(crate) fn internal_push_revision(&mut self, proposal: &Proposal) {
+        let mut revisions = self.internal_get_revisions(proposal.proposal_id);
+        revisions.push(&ProposalRevision::from_proposal(proposal));
+        self.revisions.insert(&proposal.proposal_id, &revisions);
+    }
+}
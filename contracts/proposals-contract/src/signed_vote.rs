@@ -0,0 +1,192 @@
+use crate::*;
+use crate::utils::ed25519_verify;
+use near_sdk::borsh::{self, BorshSerialize};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{CurveType, PublicKey};
+
+/// Message signed by the voter. The signature covers its borsh serialization,
+/// see `get_signed_vote_message`.
+#[derive(BorshSerialize)]
+pub struct VoteMessage {
+    pub contract_id: AccountId,
+    pub voter_id: VoterId,
+    pub proposal_id: ProposalId,
+    pub vote_type: VoteType,
+    pub memo: String,
+    pub nonce: u64,
+    pub expiry: EpochMillis,
+}
+
+/// Vote signed off-chain by the voter, and submitted by a relayer.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SignedVote {
+    pub voter_id: VoterId,
+    pub proposal_id: ProposalId,
+    pub vote_type: VoteType,
+    pub memo: String,
+    /// Must be greater than the last nonce used by the voter.
+    pub nonce: U64,
+    pub expiry: EpochMillis,
+    pub signature: Base64VecU8,
+}
+
+impl SignedVote {
+    pub(crate) fn message(&self) -> VoteMessage {
+        VoteMessage {
+            contract_id: env::current_account_id(),
+            voter_id: self.voter_id.clone(),
+            proposal_id: self.proposal_id,
+            vote_type: self.vote_type.clone(),
+            memo: self.memo.clone(),
+            nonce: self.nonce.0,
+            expiry: self.expiry,
+        }
+    }
+}
+
+#[near_bindgen]
+impl ProposalsContract {
+    /// Register the ed25519 key used to sign gasless votes.
+    pub fn register_vote_key(&mut self, public_key: PublicKey) {
+        require!(
+            public_key.curve_type() == CurveType::ED25519,
+            "Only ed25519 keys are supported"
+        );
+        self.vote_keys.insert(&env::predecessor_account_id(), &public_key);
+    }
+
+    pub fn remove_vote_key(&mut self) {
+        self.vote_keys.remove(&env::predecessor_account_id());
+    }
+
+    /// Relayers submit votes signed by the voters. Each valid vote is counted with
+    /// the voter locked voting power, as in `vote_proposal`. Invalid votes are skipped.
+    /// The nonce is consumed when the vote is counted, a failed vote can be resubmitted.
+    pub fn submit_signed_votes(&mut self, votes: Vec<SignedVote>) -> u32 {
+        require!(
+            votes.len() <= MAX_SIGNED_VOTES_PER_BATCH,
+            format!("Max {} signed votes per batch", MAX_SIGNED_VOTES_PER_BATCH)
+        );
+        let gas_per_vote = GAS_FOR_GET_VOTING_POWER.0 + GAS_FOR_RESOLVE_VOTE_PROPOSAL.0;
+        require!(
+            env::prepaid_gas().0 > gas_per_vote * votes.len() as u64,
+            "Not enough gas for the signed votes"
+        );
+        let mut accepted = 0;
+        for signed_vote in votes.iter() {
+            if let Err(reason) = self.internal_check_signed_vote(signed_vote) {
+                log!(
+                    "SKIPPED: vote of {} on proposal {}. {}",
+                    &signed_vote.voter_id,
+                    signed_vote.proposal_id,
+                    reason
+                );
+                continue;
+            }
+            self.internal_get_vote_locking_positions(&signed_vote.voter_id, signed_vote.proposal_id)
+                .then(
+                    Self::ext(env::current_account_id())
+                        .with_static_gas(GAS_FOR_RESOLVE_VOTE_PROPOSAL)
+                        .signed_vote_proposal_callback(
+                            signed_vote.proposal_id,
+                            signed_vote.voter_id.clone(),
+                            signed_vote.vote_type.clone(),
+                            signed_vote.memo.clone(),
+                            signed_vote.nonce,
+                        ),
+                );
+            accepted += 1;
+        }
+        accepted
+    }
+
+    /// Replay protection: the nonce is stored with the vote. If the vote fails,
+    /// the callback panics and the nonce is not consumed.
+    #[private]
+    pub fn signed_vote_proposal_callback(
+        &mut self,
+        proposal_id: ProposalId,
+        voter_id: VoterId,
+        vote_type: VoteType,
+        memo: String,
+        nonce: U64,
+    ) {
+        // Another vote of the batch could use the nonce meanwhile.
+        require!(
+            nonce.0 > self.vote_nonces.get(&voter_id).unwrap_or(0),
+            "Nonce already used"
+        );
+        self.vote_nonces.insert(&voter_id, &nonce.0);
+        self.vote_proposal_callback(proposal_id, voter_id, vote_type, memo);
+    }
+
+    pub fn get_vote_key(&self, voter_id: VoterId) -> Option<PublicKey> {
+        self.vote_keys.get(&voter_id)
+    }
+
+    pub fn get_vote_nonce(&self, voter_id: VoterId) -> U64 {
+        U64::from(self.vote_nonces.get(&voter_id).unwrap_or(0))
+    }
+
+    /// Bytes the voter must sign for a gasless vote.
+    pub fn get_signed_vote_message(
+        &self,
+        voter_id: VoterId,
+        proposal_id: ProposalId,
+        vote_type: VoteType,
+        memo: String,
+        nonce: U64,
+        expiry: EpochMillis,
+    ) -> Base64VecU8 {
+        let message = VoteMessage {
+            contract_id: env::current_account_id(),
+            voter_id,
+            proposal_id,
+            vote_type,
+            memo,
+            nonce: nonce.0,
+            expiry,
+        };
+        Base64VecU8::from(message.try_to_vec().unwrap())
+    }
+}
+
+impl ProposalsContract {
+    fn internal_check_signed_vote(&self, signed_vote: &SignedVote) -> Result<(), &'static str> {
+        let public_key = self
+            .vote_keys
+            .get(&signed_vote.voter_id)
+            .ok_or("Voter has no registered vote key")?;
+        if get_current_epoch_millis() > signed_vote.expiry {
+            return Err("Signed vote expired");
+        }
+        if signed_vote.nonce.0 <= self.vote_nonces.get(&signed_vote.voter_id).unwrap_or(0) {
+            return Err("Nonce already used");
+        }
+        let signature: &[u8; 64] = signed_vote
+            .signature
+            .0
+            .as_slice()
+            .try_into()
+            .map_err(|_| "Invalid signature length")?;
+        let key: &[u8; 32] = public_key.as_bytes()[1..]
+            .try_into()
+            .map_err(|_| "Invalid vote key")?;
+        let message = signed_vote.message().try_to_vec().unwrap();
+        if !ed25519_verify(signature, &message, key) {
+            return Err("Invalid signature");
+        }
+        if !signed_vote.vote_type.is_valid() {
+            return Err("Invalid vote type");
+        }
+        if !self.internal_proposal_is_on_voting(&signed_vote.proposal_id) {
+            return Err("Proposal is not on voting period");
+        }
+        if self.internal_has_voted(&signed_vote.proposal_id, &signed_vote.voter_id) {
+            return Err("Account has already voted");
+        }
+        Ok(())
+    }
+}
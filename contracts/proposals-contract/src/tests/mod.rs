@@ -1,1051 +1,1553 @@
-// use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
-// use super::*;
-// use near_sdk::{testing_env, VMContext};
-// mod utils;
-// fn new_contract() -> ProposalsContract {
-//     todo!();
-    // ProposalsContract::new(
-    //     owner_account(),
-    //     MIN_LOCKING_PERIOD,
-    //     MAX_LOCKING_PERIOD,
-    //     U128::from(MIN_DEPOSIT_AMOUNT),
-    //     MAX_LOCKING_POSITIONS,
-    //     MAX_VOTING_POSITIONS,
-    //     meta_token_account(),
-    // )
-// }
-
-// fn get_contract_setup(context: VMContext) -> ProposalsContract {
-//     testing_env!(context.clone());
-//     let contract = new_contract();
-//     contract
-// }
-
-// TODO! Try to run both kind of tests: The contract context and the workspace.
-
-// #[test]
-// fn test_single_deposit() {
-//     let context = get_context(
-//         meta_token_account(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         to_ts(GENESIS_TIME_IN_DAYS)
-//     );
-//     let mut contract = get_contract_setup(context);
-
-//     let sender_id: AccountId = voter_account();
-//     let amount = U128::from(2 * YOCTO_UNITS);
-//     let msg: String = "30".to_owned();
-
-//     contract.ft_on_transfer(sender_id.clone(), amount.clone(), msg.clone());
-//     assert_eq!(1, contract.voters.len(), "Voter was not created!");
-
-//     let voter = contract.internal_get_voter(&sender_id);
-//     assert_eq!(1, voter.locking_positions.len(), "Locking position was not created!");
-
-//     let vote_power = contract.calculate_voting_power(
-//         Meta::from(amount),
-//         msg.parse::<Days>().unwrap()
-//     );
-//     assert_eq!(vote_power, voter.voting_power, "Incorrect voting power calculation!");
-
-//     let voters = contract.get_voters(0, 10);
-//     assert_eq!(voters.len(), 1);
-//     let locking_position = &voters.first().unwrap().locking_positions;
-//     assert_eq!(locking_position.len(), 1);
-//     let vote_position = &voters.first().unwrap().vote_positions;
-//     assert_eq!(vote_position.len(), 0);
-// }
-
-// #[test]
-// fn test_multiple_deposit_same_locking_period() {
-//     let context = get_context(
-//         meta_token_account(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         to_ts(GENESIS_TIME_IN_DAYS)
-//     );
-//     let mut contract = get_contract_setup(context);
-
-//     let sender_id: AccountId = voter_account();
-//     let amount = U128::from(2 * YOCTO_UNITS);
-//     let msg: String = "30".to_owned();
-
-//     contract.ft_on_transfer(sender_id.clone(), amount.clone(), msg.clone());
-
-//     let new_amount = U128::from(5 * YOCTO_UNITS);
-//     contract.ft_on_transfer(sender_id.clone(), new_amount.clone(), msg.clone());
-
-//     let voter = contract.internal_get_voter(&sender_id);
-//     assert_eq!(1, voter.locking_positions.len(), "Locking position was not created!");
-
-//     let total_vote_power = contract.calculate_voting_power(
-//         Meta::from(amount.clone()),
-//         msg.parse::<Days>().unwrap()
-//     ) + contract.calculate_voting_power(
-//         Meta::from(new_amount.clone()),
-//         msg.parse::<Days>().unwrap()
-//     );
-
-//     // New context: the voter is doing the call now!
-//     let context = get_context(
-//         sender_id.clone(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         to_ts(GENESIS_TIME_IN_DAYS)
-//     );
-//     testing_env!(context.clone());
-//     assert_eq!(
-//         U128::from(total_vote_power),
-//         contract.get_available_voting_power(sender_id.clone()),
-//         "Incorrect voting power calculation!"
-//     );
-
-//     let locked_balance = u128::from(amount) + u128::from(new_amount);
-//     assert_eq!(
-//         U128::from(locked_balance),
-//         contract.get_locked_balance(sender_id.clone()),
-//         "Incorrect locked balance sum!"
-//     );
-//     assert_eq!(
-//         U128::from(0),
-//         contract.get_balance(sender_id.clone()),
-//         "Incorrect balance!"
-//     );
-
-//     let voters = contract.get_voters(0, 10);
-//     assert_eq!(voters.len(), 1);
-//     let locking_position = &voters.first().unwrap().locking_positions;
-//     assert_eq!(locking_position.len(), 1);
-//     let vote_position = &voters.first().unwrap().vote_positions;
-//     assert_eq!(vote_position.len(), 0);
-// }
-
-// #[test]
-// fn test_multiple_deposit_diff_locking_period() {
-//     let context = get_context(
-//         meta_token_account(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         to_ts(GENESIS_TIME_IN_DAYS)
-//     );
-//     let mut contract = get_contract_setup(context);
-
-//     let sender_id: AccountId = voter_account();
-//     let amount = U128::from(2 * YOCTO_UNITS);
-//     let msg: String = "30".to_owned();
-
-//     contract.ft_on_transfer(sender_id.clone(), amount.clone(), msg.clone());
-
-//     let new_amount = U128::from(5 * YOCTO_UNITS);
-//     let new_msg: String = "200".to_owned();
-//     contract.ft_on_transfer(sender_id.clone(), new_amount.clone(), new_msg.clone());
-
-//     let voter = contract.internal_get_voter(&sender_id);
-//     assert_eq!(2, voter.locking_positions.len(), "Locking position was not created!");
-
-//     let total_vote_power = contract.calculate_voting_power(
-//         Meta::from(amount),
-//         msg.parse::<Days>().unwrap()
-//     ) + contract.calculate_voting_power(
-//         Meta::from(new_amount),
-//         new_msg.parse::<Days>().unwrap()
-//     );
-
-//     // New context: the voter is doing the call now!
-//     let context = get_context(
-//         sender_id.clone(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         to_ts(GENESIS_TIME_IN_DAYS)
-//     );
-//     testing_env!(context.clone());
-//     assert_eq!(
-//         U128::from(total_vote_power),
-//         contract.get_available_voting_power(sender_id.clone()),
-//         "Incorrect voting power calculation!"
-//     );
-
-//     let locked_balance = u128::from(amount) + u128::from(new_amount);
-//     assert_eq!(
-//         U128::from(locked_balance),
-//         contract.get_locked_balance(sender_id.clone()),
-//         "Incorrect locked balance sum!"
-//     );
-//     assert_eq!(
-//         U128::from(0),
-//         contract.get_balance(sender_id.clone()),
-//         "Incorrect balance!"
-//     );
-
-//     let voters = contract.get_voters(0, 10);
-//     assert_eq!(voters.len(), 1);
-//     let locking_position = &voters.first().unwrap().locking_positions;
-//     assert_eq!(locking_position.len(), 2);
-//     let vote_position = &voters.first().unwrap().vote_positions;
-//     assert_eq!(vote_position.len(), 0);
-// }
-
-// #[test]
-// fn test_unlock_position() {
-//     let context = get_context(
-//         meta_token_account(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         to_ts(GENESIS_TIME_IN_DAYS)
-//     );
-//     let mut contract = get_contract_setup(context);
-
-//     let sender_id: AccountId = voter_account();
-//     let amount = U128::from(2 * YOCTO_UNITS);
-//     let msg: String = "30".to_owned();
-
-//     contract.ft_on_transfer(sender_id.clone(), amount.clone(), msg.clone());
-
-//     // New context: the voter is doing the call now!
-//     let context = get_context(
-//         sender_id.clone(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         to_ts(GENESIS_TIME_IN_DAYS)
-//     );
-//     testing_env!(context.clone());
-
-//     assert_eq!(
-//         amount,
-//         contract.get_locked_balance(sender_id.clone()),
-//         "Incorrect locked balance!"
-//     );
-//     assert_eq!(
-//         U128::from(0),
-//         contract.get_unlocking_balance(sender_id.clone()),
-//         "Incorrect unlocking balance!"
-//     );
-
-//     let voter = contract.internal_get_voter(&sender_id);
-//     let index = contract.get_all_locking_positions(
-//             sender_id.clone()
-//         ).first()
-//         .unwrap()
-//         .index
-//         .unwrap();
-//     contract.unlock_position(index);
-//     assert_eq!(1, voter.locking_positions.len(), "Locking position was not created!");
-
-//     let unlocking_started_at = contract.get_all_locking_positions(
-//             sender_id.clone()
-//         ).first()
-//         .unwrap()
-//         .unlocking_started_at;
-//     assert!(unlocking_started_at.is_some(), "Position is not unlocked!");
-//     assert_eq!(
-//         U128::from(0),
-//         contract.get_locked_balance(sender_id.clone()),
-//         "Incorrect locked balance!"
-//     );
-//     assert_eq!(
-//         amount,
-//         contract.get_unlocking_balance(sender_id.clone()),
-//         "Incorrect unlocking balance!"
-//     );
-
-//     let voter = contract.internal_get_voter(&sender_id);
-//     assert_eq!(voter.voting_power, 0, "Voting power was not removed!");
-// }
-
-// #[test]
-// fn test_unlock_partial_position() {
-//     let context = get_context(
-//         meta_token_account(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         to_ts(GENESIS_TIME_IN_DAYS)
-//     );
-//     let mut contract = get_contract_setup(context);
-
-//     let sender_id: AccountId = voter_account();
-//     let amount = U128::from(2 * YOCTO_UNITS);
-//     let msg: String = "30".to_owned();
-//     contract.ft_on_transfer(sender_id.clone(), amount.clone(), msg.clone());
-
-//     let new_amount = U128::from(5 * YOCTO_UNITS);
-//     let new_msg: String = "200".to_owned();
-//     contract.ft_on_transfer(sender_id.clone(), new_amount.clone(), new_msg.clone());
-
-//     // New context: the voter is doing the call now!
-//     let context = get_context(
-//         sender_id.clone(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         to_ts(GENESIS_TIME_IN_DAYS)
-//     );
-//     testing_env!(context.clone());
-
-//     let total_amount = U128::from(u128::from(amount) + u128::from(new_amount));
-//     assert_eq!(
-//         total_amount,
-//         contract.get_locked_balance(sender_id.clone()),
-//         "Incorrect locked balance!"
-//     );
-//     assert_eq!(
-//         U128::from(0),
-//         contract.get_unlocking_balance(sender_id.clone()),
-//         "Incorrect unlocking balance!"
-//     );
-
-//     // Partially removing the last (second) locking position.
-//     let index = contract.get_all_locking_positions(
-//             sender_id.clone()
-//         ).last()
-//         .unwrap()
-//         .index
-//         .unwrap();
-//     let third_amount = U128::from(4 * YOCTO_UNITS);
-//     contract.unlock_partial_position(index, third_amount);
-//     let voter = contract.internal_get_voter(&sender_id);
-//     assert_eq!(3, voter.locking_positions.len(), "Locking position was not created!");
-
-//     let unlocking_started_at = contract.get_all_locking_positions(
-//             sender_id.clone()
-//         ).last()
-//         .unwrap()
-//         .unlocking_started_at;
-//     assert!(unlocking_started_at.is_some(), "Position is not unlocked!");
-//     let locked_amount = U128::from(
-//         u128::from(amount) + u128::from(new_amount) - u128::from(third_amount)
-//     );
-//     assert_eq!(
-//         locked_amount,
-//         contract.get_locked_balance(sender_id.clone()),
-//         "Incorrect locked balance!"
-//     );
-//     assert_eq!(
-//         third_amount,
-//         contract.get_unlocking_balance(sender_id.clone()),
-//         "Incorrect unlocking balance!"
-//     );
-
-//     let voter = contract.internal_get_voter(&sender_id);
-//     let total_vote_power = contract.calculate_voting_power(
-//         Meta::from(amount),
-//         msg.parse::<Days>().unwrap()
-//     ) + contract.calculate_voting_power(
-//         Meta::from(new_amount) - Meta::from(third_amount),
-//         new_msg.parse::<Days>().unwrap()
-//     );
-//     assert_eq!(voter.voting_power, total_vote_power, "Voting power was not removed!");
-// }
-
-// fn generate_relock_position_context() -> MetaVoteContract {
-//     const LOCKING_PERIOD: u64 = 100;
-//     let timestamp_0 = to_ts(GENESIS_TIME_IN_DAYS);
-//     let timestamp_1 = to_ts(GENESIS_TIME_IN_DAYS + 5);
-//     let timestamp_2 = to_ts(GENESIS_TIME_IN_DAYS + 5 + LOCKING_PERIOD);
-//     let context = get_context(
-//         meta_token_account(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         timestamp_0
-//     );
-//     let mut contract = get_contract_setup(context);
-
-//     let sender_id: AccountId = voter_account();
-//     let amount = U128::from(10 * YOCTO_UNITS);
-//     let msg: String = LOCKING_PERIOD.to_string();
-//     contract.ft_on_transfer(sender_id.clone(), amount.clone(), msg.clone());
-
-//     // New context: the voter is doing the call now!
-//     let context = get_context(
-//         sender_id.clone(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         timestamp_1
-//     );
-//     testing_env!(context.clone());
-//     let index = contract.get_all_locking_positions(
-//             sender_id.clone()
-//         ).first()
-//         .unwrap()
-//         .index
-//         .unwrap();
-//     contract.unlock_position(index);
-//     let voter = contract.internal_get_voter(&sender_id);
-//     let locking_position = voter.locking_positions.get(index).unwrap();
-//     assert_eq!(
-//         locking_position.unlocking_started_at.unwrap(),
-//         nanos_to_millis(timestamp_1),
-//         "Incorrect unlocking started at date."
-//     );
-//     assert_eq!(
-//         locking_position.unlocking_started_at.unwrap()
-//             + locking_position.locking_period_millis(),
-//         nanos_to_millis(timestamp_2),
-//         "Incorrect unlocking finish date."
-//     );
-//     assert_eq!(
-//         U128::from(0),
-//         contract.get_locked_balance(sender_id.clone()),
-//         "Incorrect locked balance!"
-//     );
-//     assert_eq!(
-//         amount,
-//         contract.get_unlocking_balance(sender_id.clone()),
-//         "Incorrect unlocking balance!"
-//     );
-//     contract
-// }
-
-// #[test]
-// #[should_panic(expected="The new locking period should be greater than 88 days.")]
-// fn test_relock_position_1() {
-//     let mut contract = generate_relock_position_context();
-//     let timestamp_0 = to_ts(GENESIS_TIME_IN_DAYS + 5 + 12);
-//     let sender_id: AccountId = voter_account();
-
-//     // New context: the voter is doing the call now!
-//     let context = get_context(
-//         sender_id.clone(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         timestamp_0
-//     );
-//     testing_env!(context.clone());
-//     contract.relock_position(0, 30, U128::from(0));
-// }
-
-// #[test]
-// fn test_relock_position_2() {
-//     let mut contract = generate_relock_position_context();
-//     let timestamp_0 = to_ts(GENESIS_TIME_IN_DAYS + 5 + 12);
-//     let sender_id: AccountId = voter_account();
-
-//     // New context: the voter is doing the call now!
-//     let context = get_context(
-//         sender_id.clone(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         timestamp_0
-//     );
-//     testing_env!(context.clone());
-//     let index = contract.get_all_locking_positions(
-//             sender_id.clone()
-//         ).first()
-//         .unwrap()
-//         .index
-//         .unwrap();
-//     let voter = contract.internal_get_voter(&sender_id);
-//     let locking_position = voter.locking_positions.get(index).unwrap();
-//     assert_eq!(voter.voting_power, 0, "Voting power should be 0.");
-
-//     let amount = locking_position.amount;
-//     let locking_period: Days = 89;
-//     contract.relock_position(0, locking_period, U128::from(0));
-
-//     let voter = contract.internal_get_voter(&sender_id);
-//     let locking_position = voter.locking_positions.get(index).unwrap();
-//     assert_eq!(
-//         voter.voting_power,
-//         contract.calculate_voting_power(amount, locking_period),
-//         "Voting power of Voter is incorrect.");
-//     assert_eq!(
-//         U128::from(locking_position.amount),
-//         contract.get_locked_balance(sender_id.clone()),
-//         "Incorrect locked balance!"
-//     );
-//     assert_eq!(
-//         U128::from(0),
-//         contract.get_unlocking_balance(sender_id.clone()),
-//         "Incorrect unlocking balance!"
-//     );
-//     assert!(
-//         locking_position.unlocking_started_at.is_none(),
-//         "Unlocking started should be None."
-//     );
-// }
-
-// #[test]
-// fn test_relock_position_3() {
-//     let mut contract = generate_relock_position_context();
-//     let timestamp_0 = to_ts(GENESIS_TIME_IN_DAYS + 5 + 177);
-//     let sender_id: AccountId = voter_account();
-
-//     // New context: the voter is doing the call now!
-//     let context = get_context(
-//         sender_id.clone(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         timestamp_0
-//     );
-//     testing_env!(context.clone());
-//     let index = contract.get_all_locking_positions(
-//             sender_id.clone()
-//         ).first()
-//         .unwrap()
-//         .index
-//         .unwrap();
-//     let voter = contract.internal_get_voter(&sender_id);
-//     let locking_position = voter.locking_positions.get(index).unwrap();
-//     assert_eq!(voter.voting_power, 0, "Voting power should be 0.");
-
-//     let amount = locking_position.amount;
-//     let locking_period: Days = 30;
-//     contract.relock_position(0, locking_period, U128::from(0));
-
-//     let voter = contract.internal_get_voter(&sender_id);
-//     let locking_position = voter.locking_positions.get(index).unwrap();
-//     assert_eq!(
-//         voter.voting_power,
-//         contract.calculate_voting_power(amount, locking_period),
-//         "Voting power of Voter is incorrect.");
-//     assert_eq!(
-//         U128::from(locking_position.amount),
-//         contract.get_locked_balance(sender_id.clone()),
-//         "Incorrect locked balance!"
-//     );
-//     assert_eq!(
-//         U128::from(0),
-//         contract.get_unlocking_balance(sender_id.clone()),
-//         "Incorrect unlocking balance!"
-//     );
-//     assert!(
-//         locking_position.unlocking_started_at.is_none(),
-//         "Unlocking started should be None."
-//     );
-// }
-
-// #[test]
-// #[should_panic(expected="The new locking period should be greater than 77 days.")]
-// fn test_relock_partial_position_1() {
-//     let mut contract = generate_relock_position_context();
-//     let timestamp_0 = to_ts(GENESIS_TIME_IN_DAYS + 5 + 23);
-//     let sender_id: AccountId = voter_account();
-
-//     // New context: the voter is doing the call now!
-//     let context = get_context(
-//         sender_id.clone(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         timestamp_0
-//     );
-//     testing_env!(context.clone());
-//     let index = contract.get_all_locking_positions(
-//             sender_id.clone()
-//         ).first()
-//         .unwrap()
-//         .index
-//         .unwrap();
-//     let voter = contract.internal_get_voter(&sender_id);
-//     let locking_position = voter.locking_positions.get(index).unwrap();
-//     contract.relock_partial_position(
-//         index,
-//         U128::from(
-//             locking_position.amount - 2 * YOCTO_UNITS
-//         ),
-//         30,
-//         U128::from(0)
-//     );
-// }
-
-// #[test]
-// fn test_relock_partial_position_2() {
-//     let mut contract = generate_relock_position_context();
-//     let timestamp_0 = to_ts(GENESIS_TIME_IN_DAYS + 5 + 23);
-//     let sender_id: AccountId = voter_account();
-
-//     // New context: the voter is doing the call now!
-//     let context = get_context(
-//         sender_id.clone(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         timestamp_0
-//     );
-//     testing_env!(context.clone());
-//     let index = contract.get_all_locking_positions(
-//             sender_id.clone()
-//         ).first()
-//         .unwrap()
-//         .index
-//         .unwrap();
-//     let voter = contract.internal_get_voter(&sender_id);
-//     let locking_position = voter.locking_positions.get(index).unwrap();
-//     assert_eq!(voter.voting_power, 0, "Voting power should be 0.");
-
-//     let keep_amount = 2 * YOCTO_UNITS;
-//     let relock_amount = locking_position.amount - keep_amount;
-//     let locking_period: Days = 89;
-//     contract.relock_partial_position(
-//         index,
-//         U128::from(relock_amount),
-//         locking_period,
-//         U128::from(0)
-//     );
-
-//     // The Unlocking is index 0, and the Relocked is index 1.
-//     let index = contract.get_all_locking_positions(
-//             sender_id.clone()
-//         ).last()
-//         .unwrap()
-//         .index
-//         .unwrap();
-//     let voter = contract.internal_get_voter(&sender_id);
-//     let locking_position = voter.locking_positions.get(index).unwrap();
-//     assert_eq!(voter.locking_positions.len(), 2, "Incorrect locking position.");
-//     assert_eq!(
-//         voter.voting_power,
-//         contract.calculate_voting_power(relock_amount, locking_period),
-//         "Voting power of Voter is incorrect."
-//     );
-//     assert_eq!(
-//         U128::from(relock_amount),
-//         contract.get_locked_balance(sender_id.clone()),
-//         "Incorrect locked balance!"
-//     );
-//     assert_eq!(
-//         U128::from(keep_amount),
-//         contract.get_unlocking_balance(sender_id.clone()),
-//         "Incorrect unlocking balance!"
-//     );
-//     assert!(
-//         locking_position.unlocking_started_at.is_none(),
-//         "Unlocking started should be None."
-//     );
-// }
-
-// #[test]
-// fn test_relock_partial_position_3() {
-//     let mut contract = generate_relock_position_context();
-//     let timestamp_0 = to_ts(GENESIS_TIME_IN_DAYS + 5 + 177);
-//     let sender_id: AccountId = voter_account();
-
-//     // New context: the voter is doing the call now!
-//     let context = get_context(
-//         sender_id.clone(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         timestamp_0
-//     );
-//     testing_env!(context.clone());
-//     let index = contract.get_all_locking_positions(
-//             sender_id.clone()
-//         ).first()
-//         .unwrap()
-//         .index
-//         .unwrap();
-//     let voter = contract.internal_get_voter(&sender_id);
-//     let locking_position = voter.locking_positions.get(index).unwrap();
-//     assert_eq!(voter.voting_power, 0, "Voting power should be 0.");
-
-//     let keep_amount = 2 * YOCTO_UNITS;
-//     let relock_amount = locking_position.amount - keep_amount;
-//     let locking_period: Days = 30;
-//     contract.relock_partial_position(
-//         index,
-//         U128::from(relock_amount),
-//         locking_period,
-//         U128::from(0)
-//     );
-
-//     // The Unlocking is index 0, and the Relocked is ALSO index 0.
-//     let voter = contract.internal_get_voter(&sender_id);
-//     let locking_position = voter.locking_positions.get(index).unwrap();
-//     assert_eq!(voter.locking_positions.len(), 1, "Incorrect locking position.");
-//     assert_eq!(
-//         voter.voting_power,
-//         contract.calculate_voting_power(relock_amount, locking_period),
-//         "Voting power of Voter is incorrect."
-//     );
-//     assert_eq!(
-//         voter.balance,
-//         keep_amount,
-//         "Voter balance is incorrect."
-//     );
-//     assert_eq!(
-//         U128::from(keep_amount),
-//         contract.get_balance(sender_id.clone()),
-//         "Incorrect free balance!"
-//     );
-//     assert_eq!(
-//         U128::from(relock_amount),
-//         contract.get_locked_balance(sender_id.clone()),
-//         "Incorrect locked balance!"
-//     );
-//     assert_eq!(
-//         U128::from(0),
-//         contract.get_unlocking_balance(sender_id.clone()),
-//         "Incorrect unlocking balance!"
-//     );
-//     assert!(
-//         locking_position.unlocking_started_at.is_none(),
-//         "Unlocking started should be None."
-//     );
-
-//     contract.unlock_position(index);
-//     let locking_period: Days = 38;
-//     let keep_amount_1 = keep_amount - (1 * YOCTO_UNITS);
-//     let keep_amount_2 = keep_amount - keep_amount_1;
-//     contract.relock_position(
-//         index,
-//         locking_period,
-//         U128::from(keep_amount_1)
-//     );
-
-//     let voter = contract.internal_get_voter(&sender_id);
-//     assert_eq!(voter.balance, keep_amount_2, "Incorrect voter balance.");
-//     assert_eq!(
-//         voter.voting_power,
-//         contract.calculate_voting_power(relock_amount + keep_amount_1, locking_period),
-//         "Voting power of Voter is incorrect."
-//     );
-
-//     // Relock from balance
-//     let locking_period: Days = 278;
-//     contract.relock_from_balance(locking_period, U128::from(keep_amount_2));
-//     let voter = contract.internal_get_voter(&sender_id);
-//     assert_eq!(voter.balance, 0, "Incorrect voter balance.");
-//     assert_eq!(voter.locking_positions.len(), 2, "Incorrect number of Locking Positions.");
-// }
-
-// #[test]
-// fn test_clear_locking_position() {
-//     const LOCKING_PERIOD: u64 = 100;
-//     let timestamp_0 = to_ts(GENESIS_TIME_IN_DAYS);
-//     let timestamp_1 = to_ts(GENESIS_TIME_IN_DAYS + 5);
-//     let timestamp_2 = to_ts(GENESIS_TIME_IN_DAYS + 5 + LOCKING_PERIOD);
-//     let context = get_context(
-//         meta_token_account(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         timestamp_0
-//     );
-//     let mut contract = get_contract_setup(context);
-
-//     let sender_id: AccountId = voter_account();
-//     let amount = U128::from(2 * YOCTO_UNITS);
-//     let msg: String = "30".to_owned();
-
-//     contract.ft_on_transfer(sender_id.clone(), amount.clone(), msg.clone());
-
-//     let new_amount = U128::from(5 * YOCTO_UNITS);
-//     let new_msg: String = "32".to_owned();
-//     contract.ft_on_transfer(sender_id.clone(), new_amount.clone(), new_msg.clone());
-
-//     let voter = contract.internal_get_voter(&sender_id);
-//     assert_eq!(2, voter.locking_positions.len(), "Locking position was not created!");
-
-//     // New context: the voter is doing the call now!
-//     let context = get_context(
-//         sender_id.clone(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         timestamp_1
-//     );
-//     testing_env!(context.clone());
-
-//     contract.unlock_position(0);
-//     contract.unlock_position(1);
-
-//     // New context: the voter is doing the call now!
-//     let context = get_context(
-//         sender_id.clone(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         timestamp_2
-//     );
-//     testing_env!(context.clone());
-
-//     let position_index_list: Vec<PositionIndex> = vec![0, 1];
-//     contract.clear_locking_position(position_index_list);
-
-//     let voter = contract.internal_get_voter(&sender_id);
-//     assert_eq!(0, voter.locking_positions.len(), "Locking position was not deleted!");
-//     assert_eq!(
-//         Meta::from(amount) + Meta::from(new_amount),
-//         voter.balance,
-//         "Incorrect balance!"
-//     );
-//     assert_eq!(
-//         Meta::from(amount) + Meta::from(new_amount),
-//         Meta::from(contract.get_balance(sender_id.clone())),
-//         "Incorrect balance!"
-//     );
-
-//     let voters = contract.get_voters(0, 10);
-//     assert_eq!(voters.len(), 1);
-//     let locking_position = &voters.first().unwrap().locking_positions;
-//     assert_eq!(locking_position.len(), 0);
-//     let vote_position = &voters.first().unwrap().vote_positions;
-//     assert_eq!(vote_position.len(), 0);
-// }
-
-// #[test]
-// #[should_panic(expected="Not enough free voting power to unlock! You have 0, required 20370370370370370370370370.")]
-// fn test_unlock_position_without_voting_power() {
-//     const LOCKING_PERIOD: u64 = 100;
-//     let timestamp_0 = to_ts(GENESIS_TIME_IN_DAYS);
-//     let timestamp_1 = to_ts(GENESIS_TIME_IN_DAYS + 5);
-//     let context = get_context(
-//         meta_token_account(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         timestamp_0
-//     );
-//     let mut contract = get_contract_setup(context);
-
-//     let sender_id: AccountId = voter_account();
-//     let amount = U128::from(10 * YOCTO_UNITS);
-//     let msg: String = LOCKING_PERIOD.to_string();
-//     contract.ft_on_transfer(sender_id.clone(), amount.clone(), msg.clone());
-
-//     // New context: the voter is doing the call now!
-//     let context = get_context(
-//         sender_id.clone(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         timestamp_1
-//     );
-//     testing_env!(context.clone());
-//     let index = contract.get_all_locking_positions(
-//             sender_id.clone()
-//         ).first()
-//         .unwrap()
-//         .index
-//         .unwrap();
-
-
-//     let vote = contract.calculate_voting_power(
-//         Meta::from(amount),
-//         msg.parse::<Days>().unwrap()
-//     );
-//     contract.vote(
-//         U128::from(vote),
-//         votable_account(),
-//         "0".to_owned()
-//     );
-//     let voter = contract.internal_get_voter(&sender_id);
-//     assert_eq!(voter.voting_power, 0, "Incorrect Voting Power calculation.");
-//     assert_eq!(
-//         U128::from(vote),
-//         contract.get_votes_for_object(sender_id.clone(), votable_account(), "0".to_owned()),
-//         "Incorrect votes for votable object"
-//     );
-//     contract.unlock_position(index);
-// }
-
-// #[test]
-// fn test_rebalance_increase_and_decrease() {
-//     const LOCKING_PERIOD: u64 = 100;
-//     let timestamp_0 = to_ts(GENESIS_TIME_IN_DAYS);
-//     let timestamp_1 = to_ts(GENESIS_TIME_IN_DAYS + 5);
-//     let context = get_context(
-//         meta_token_account(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         timestamp_0
-//     );
-//     let mut contract = get_contract_setup(context);
-
-//     let sender_id: AccountId = voter_account();
-//     let amount = U128::from(10 * YOCTO_UNITS);
-//     let msg: String = LOCKING_PERIOD.to_string();
-//     contract.ft_on_transfer(sender_id.clone(), amount.clone(), msg.clone());
-
-//     // New context: the voter is doing the call now!
-//     let context = get_context(
-//         sender_id.clone(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         timestamp_1
-//     );
-//     testing_env!(context.clone());
-
-//     let vote = contract.calculate_voting_power(
-//         Meta::from(amount),
-//         msg.parse::<Days>().unwrap()
-//     );
-//     let contract_address = votable_account();
-//     let votable_object_id = "0".to_owned();
-
-//     contract.vote(
-//         U128::from(vote),
-//         contract_address.clone(),
-//         votable_object_id.clone()
-//     );
-
-//     // Decrease votes.
-//     let delta_1 = 5 * YOCTO_UNITS;
-//     let decreased_votes = U128::from(vote - delta_1);
-//     contract.rebalance(
-//         decreased_votes,
-//         contract_address.clone(),
-//         votable_object_id.clone()
-//     );
-//     let voter = contract.internal_get_voter(&sender_id);
-//     assert_eq!(voter.voting_power, delta_1, "Incorrect Voting Power calculation.");
-
-//     let votes_for_address = voter.get_votes_for_address(&sender_id, &contract_address);
-//     let votes = votes_for_address.get(&votable_object_id).unwrap();
-
-//     // Increase votes.
-//     let delta_2 = 1 * YOCTO_UNITS;
-//     let additional_votes = U128::from(votes + delta_2);
-//     contract.rebalance(
-//         additional_votes,
-//         contract_address.clone(),
-//         votable_object_id.clone()
-//     );
-//     let voter = contract.internal_get_voter(&sender_id);
-//     assert_eq!(voter.voting_power, delta_1 - delta_2, "Incorrect Voting Power calculation.");
-
-//     let votes_for_address = voter.get_votes_for_address(&sender_id, &contract_address);
-//     let votes = votes_for_address.get(&votable_object_id).unwrap();
-//     assert_eq!(votes, u128::from(additional_votes), "Incorrect Voting Power calculation.");
-
-//     let voters = contract.get_voters(0, 10);
-//     assert_eq!(voters.len(), 1);
-//     let locking_position = &voters.first().unwrap().locking_positions;
-//     assert_eq!(locking_position.len(), 1);
-//     let vote_position = &voters.first().unwrap().vote_positions;
-//     assert_eq!(vote_position.len(), 1);
-// }
-
-// #[test]
-// fn test_multi_voter_contract() {
-//     struct User {
-//         id: String,
-//         votes: VotingPower,
-//         locking_period: Days,
-//         contract_address: ContractAddress,
-//         votable_object_id: VotableObjId
-//     }
-//     let users = vec![
-//         User{id: "1".to_owned(), votes: 10 * YOCTO_UNITS, locking_period: 30,
-//                 contract_address: multi_voter_account("v1.near".to_owned()),
-//                 votable_object_id: "1".to_string()},
-//         User{id: "2".to_owned(), votes: 1 * YOCTO_UNITS, locking_period: 45,
-//                 contract_address: multi_voter_account("v1.near".to_owned()),
-//                 votable_object_id: "1".to_string()},
-//         User{id: "3".to_owned(), votes: 24 * YOCTO_UNITS, locking_period: 200,
-//                 contract_address: multi_voter_account("v1.near".to_owned()),
-//                 votable_object_id: "2".to_string()},
-//         User{id: "4".to_owned(), votes: 8 * YOCTO_UNITS, locking_period: 300,
-//                 contract_address: multi_voter_account("v2.near".to_owned()),
-//                 votable_object_id: "1".to_string()},
-//     ];
-
-//     let mut votes_1_1 = 0_u128;
-//     let mut votes_1_2 = 0_u128;
-//     let mut votes_2_1 = 0_u128;
-//     let timestamp_0 = to_ts(GENESIS_TIME_IN_DAYS);
-//     let timestamp_1 = to_ts(GENESIS_TIME_IN_DAYS + 5);
-
-//     let context = get_context(
-//         meta_token_account(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         timestamp_0
-//     );
-//     let mut contract = get_contract_setup(context);
-
-//     for user in users.iter() {
-//         let context = get_context(
-//             meta_token_account(),
-//             ntoy(TEST_INITIAL_BALANCE),
-//             0,
-//             timestamp_0
-//         );
-//         testing_env!(context.clone());
-
-//         let sender_id: AccountId = multi_voter_account(user.id.clone());
-//         let amount = U128::from(user.votes);
-//         let msg: String = user.locking_period.to_string();
-//         contract.ft_on_transfer(sender_id.clone(), amount.clone(), msg.clone());
-
-//         // New context: the voter is doing the call now!
-//         let context = get_context(
-//             sender_id.clone(),
-//             ntoy(TEST_INITIAL_BALANCE),
-//             0,
-//             timestamp_1
-//         );
-//         testing_env!(context.clone());
-
-//         let voting_power = contract.calculate_voting_power(
-//             u128::from(amount),
-//             user.locking_period.clone()
-//         );
-//         assert_eq!(
-//             u128::from(contract.get_available_voting_power(sender_id.clone())),
-//             voting_power,
-//             "Incorrect voting power for user."
-//         );
-//         let votes_to_use = user.votes;
-//         let remaining = voting_power - votes_to_use;
-//         contract.vote(
-//             U128::from(votes_to_use),
-//             user.contract_address.clone(),
-//             user.votable_object_id.clone()
-//         );
-
-//         if user.id.clone() == "1".to_string() || user.id.clone() == "2".to_string() {
-//             votes_1_1 += votes_to_use;
-//         } else if user.id.clone() == "3".to_string() {
-//             votes_1_2 += votes_to_use;
-//         } else if user.id.clone() == "4".to_string() {
-//             votes_2_1 += votes_to_use;
-//         }
-
-//         assert_eq!(
-//             U128::from(votes_to_use),
-//             contract.get_used_voting_power(sender_id.clone()),
-//             "Incorrect used voting power."
-//         );
-//         assert_eq!(
-//             U128::from(remaining),
-//             contract.get_available_voting_power(sender_id.clone()),
-//             "Incorrect remaining voting power."
-//         );
-//     }
-
-//     // Unvote and vote again to test contract total voting consistency.
-//     let user = users.get(3).unwrap();
-//     let sender_id: AccountId = multi_voter_account(user.id.clone());
-//     let context = get_context(
-//         sender_id.clone(),
-//         ntoy(TEST_INITIAL_BALANCE),
-//         0,
-//         timestamp_1
-//     );
-//     testing_env!(context.clone());
-//     contract.unvote(
-//         user.contract_address.clone(),
-//         user.votable_object_id.clone()
-//     );
-//     contract.vote(
-//         U128::from(user.votes.clone()),
-//         user.contract_address.clone(),
-//         user.votable_object_id.clone()
-//     );
-
-//     assert_eq!(
-//         contract.get_total_votes(multi_voter_account("v1.near".to_owned()), "1".to_string()),
-//         U128::from(votes_1_1),
-//         "Incorrect vote count for project 1, object 1."
-//     );
-//     assert_eq!(
-//         contract.get_total_votes(multi_voter_account("v1.near".to_owned()), "2".to_string()),
-//         U128::from(votes_1_2),
-//         "Incorrect vote count for project 1, object 2."
-//     );
-//     assert_eq!(
-//         contract.get_total_votes(multi_voter_account("v2.near".to_owned()), "1".to_string()),
-//         U128::from(votes_2_1),
-//         "Incorrect vote count for project 2, object 1."
-//     );
-
-//     let voters = contract.get_voters(0, 10);
-//     assert_eq!(voters.len(), 4);
-//     let locking_position = &voters.first().unwrap().locking_positions;
-//     assert_eq!(locking_position.len(), 1);
-//     let vote_position = &voters.first().unwrap().vote_positions;
-//     assert_eq!(vote_position.len(), 1);
-// }
\ No newline at end of file
+use super::*;
+use category::QuorumModel;
+use council::CouncilActionKind;
+use ed25519_dalek::{Signer, SigningKey};
+use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
+use near_sdk::PromiseResult;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::mock::VmAction;
+use signed_vote::SignedVote;
+use treasury::TreasuryMovementKind;
+
+mod utils;
+use utils::*;
+
+fn new_contract() -> ProposalsContract {
+    ProposalsContract::new(
+        owner_account(),
+        [operator_account()].to_vec(),
+        meta_token_account(),
+        staking_position_account(),
+        U64::from(VOTING_PERIOD),
+        U128::from(MIN_VOTING_POWER_AMOUNT),
+        U128::from(PROPOSAL_STORAGE_NEAR),
+        QUORUM_FLOOR,
+    )
+}
+
+fn setup_new_test() -> ProposalsContract {
+    set_context_caller(&owner_account());
+    new_contract()
+}
+
+/// Let the same account submit many proposals in a test.
+fn disable_proposal_cooldown(contract: &mut ProposalsContract) {
+    set_context_caller(&operator_account());
+    contract.update_proposal_cooldown(U64::from(0));
+}
+
+fn proposal_content(title: &str) -> ProposalContent {
+    ProposalContent {
+        title: title.to_string(),
+        short_description: "Short description".to_string(),
+        body: "Body".to_string(),
+        data: "".to_string(),
+        extra: "".to_string(),
+        offchain_content: None,
+    }
+}
+
+fn new_category(
+    name: &str,
+    quorum_floor: BasisPoints,
+    approval_threshold: ApprovalThreshold,
+) -> ProposalCategory {
+    ProposalCategory {
+        name: name.to_string(),
+        quorum_floor,
+        quorum_model: QuorumModel::ForAndAbstain,
+        approval_threshold,
+        voting_period: VOTING_PERIOD,
+        execution_delay: 0,
+        emergency: false,
+    }
+}
+
+/// Submit a proposal, and resolve the query of the creator voting power.
+fn submit_proposal(
+    contract: &mut ProposalsContract,
+    creator_id: &AccountId,
+    content: ProposalContent,
+    category: Option<String>,
+) -> Option<ProposalId> {
+    set_context_deposit(creator_id, PROPOSAL_STORAGE_NEAR);
+    let category_rules = contract.internal_get_category(&category);
+    let asset_token_bond = contract.proposal_cost_in_asset_token;
+    contract.create_proposal(content.clone(), category);
+
+    set_callback_context(locking_positions_result(MIN_VOTING_POWER_AMOUNT));
+    contract.create_proposal_callback(
+        creator_id.clone(),
+        content,
+        category_rules,
+        U128::from(PROPOSAL_STORAGE_NEAR),
+        U128::from(asset_token_bond),
+    )
+}
+
+fn create_proposal(contract: &mut ProposalsContract, creator_id: &AccountId) -> ProposalId {
+    submit_proposal(contract, creator_id, proposal_content("Proposal"), None).unwrap()
+}
+
+/// Review the proposal if needed and start the voting period, with a snapshot
+/// of `total_voting_power`.
+fn start_voting(contract: &mut ProposalsContract, proposal_id: ProposalId, total_voting_power: u128) {
+    set_context_caller(&operator_account());
+    if contract.get_proposal_state(proposal_id) == ProposalState::Draft {
+        contract.approve_proposal(proposal_id);
+    }
+    contract.start_voting_period(proposal_id);
+
+    set_callback_context(total_voting_power_result(total_voting_power));
+    contract.start_voting_period_callback(proposal_id);
+}
+
+fn vote(
+    contract: &mut ProposalsContract,
+    proposal_id: ProposalId,
+    voter_id: &AccountId,
+    vote_type: VoteType,
+    voting_power: u128,
+) {
+    set_context_caller(voter_id);
+    contract.vote_proposal(proposal_id, vote_type.clone(), "memo".to_string());
+
+    set_callback_context(locking_positions_result(voting_power));
+    contract.vote_proposal_callback(proposal_id, voter_id.clone(), vote_type, "memo".to_string());
+}
+
+/// Finalize the proposal once its voting period is over.
+fn finalize(contract: &mut ProposalsContract, proposal_id: ProposalId) {
+    set_context_caller_at(&operator_account(), 8);
+    contract.process_voting_status(proposal_id);
+}
+
+fn setup_council(contract: &mut ProposalsContract) {
+    set_context_caller(&owner_account());
+    contract.update_council(
+        [council_account(1), council_account(2), council_account(3)].to_vec(),
+        2,
+    );
+}
+
+/// Submit a proposal in a category that allows emergency actions.
+fn create_emergency_proposal(contract: &mut ProposalsContract) -> ProposalId {
+    let mut category = new_category("emergency", 1_000, ApprovalThreshold::SimpleMajority);
+    category.emergency = true;
+    set_context_caller(&owner_account());
+    contract.upsert_proposal_category(category);
+    submit_proposal(
+        contract,
+        &developer_account(),
+        proposal_content("Emergency"),
+        Some("emergency".to_string()),
+    )
+    .unwrap()
+}
+
+fn pause_staking_action(deposit: u128) -> EmergencyAction {
+    EmergencyAction::CallContract {
+        contract_id: staking_position_account(),
+        method_name: "pause".to_string(),
+        args: Base64VecU8::from(b"{}".to_vec()),
+        deposit: U128::from(deposit),
+        gas: U64::from(20_000_000_000_000),
+    }
+}
+
+fn vote_signing_key() -> SigningKey {
+    SigningKey::from_bytes(&[7; 32])
+}
+
+fn vote_public_key(signing_key: &SigningKey) -> PublicKey {
+    let mut data = vec![0];
+    data.extend_from_slice(&signing_key.verifying_key().to_bytes());
+    PublicKey::try_from(data).unwrap()
+}
+
+fn signed_vote(
+    contract: &ProposalsContract,
+    signing_key: &SigningKey,
+    voter_id: &AccountId,
+    proposal_id: ProposalId,
+    nonce: u64,
+    expiry: EpochMillis,
+) -> SignedVote {
+    let message = contract.get_signed_vote_message(
+        voter_id.clone(),
+        proposal_id,
+        VoteType::For,
+        "memo".to_string(),
+        U64::from(nonce),
+        expiry,
+    );
+    SignedVote {
+        voter_id: voter_id.clone(),
+        proposal_id,
+        vote_type: VoteType::For,
+        memo: "memo".to_string(),
+        nonce: U64::from(nonce),
+        expiry,
+        signature: Base64VecU8::from(signing_key.sign(&message.0).to_bytes().to_vec()),
+    }
+}
+
+// ***************
+// * Split votes *
+// ***************
+
+fn split(for_bp: BasisPoints, against_bp: BasisPoints, abstain_bp: BasisPoints) -> VoteType {
+    VoteType::Split { for_bp, against_bp, abstain_bp }
+}
+
+#[test]
+fn test_split_vote_allocation() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+    vote(&mut contract, proposal_id, &voter_account(), split(6_000, 2_500, 1_500), 200 * E24);
+
+    let votes = contract.get_proposal_votes(proposal_id);
+    assert_eq!(votes.for_votes.0, 120 * E24);
+    assert_eq!(votes.against_votes.0, 50 * E24);
+    assert_eq!(votes.abstain_votes.0, 30 * E24);
+}
+
+#[test]
+fn test_split_vote_remainder_goes_to_abstain() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+    // 3333 bp of 7 yocto is 2.3331, rounded down to 2.
+    vote(&mut contract, proposal_id, &voter_account(), split(3_333, 3_333, 3_334), 7);
+
+    let votes = contract.get_proposal_votes(proposal_id);
+    assert_eq!(votes.for_votes.0, 2);
+    assert_eq!(votes.against_votes.0, 2);
+    assert_eq!(votes.abstain_votes.0, 3);
+}
+
+#[test]
+fn test_split_vote_add_and_remove_are_inverse() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+    vote(&mut contract, proposal_id, &voter_account(), VoteType::For, 300 * E24);
+    let before = contract.get_proposal_votes(proposal_id);
+
+    for voting_power in [7, 100 * E24 + 1, 333 * E24 + 333] {
+        vote(&mut contract, proposal_id, &non_owner(), split(3_333, 3_333, 3_334), voting_power);
+        set_context_caller(&non_owner());
+        contract.remove_vote_proposal(proposal_id);
+
+        let after = contract.get_proposal_votes(proposal_id);
+        assert_eq!(after.for_votes, before.for_votes);
+        assert_eq!(after.against_votes, before.against_votes);
+        assert_eq!(after.abstain_votes, before.abstain_votes);
+    }
+}
+
+#[test]
+#[should_panic(expected = "Split vote must allocate exactly 10000 basis points")]
+fn test_fail_split_vote_not_fully_allocated() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+
+    set_context_caller(&voter_account());
+    contract.vote_proposal(proposal_id, split(5_000, 4_000, 0), "memo".to_string());
+}
+
+// *********
+// * Bonds *
+// *********
+
+#[test]
+fn test_create_proposal_locks_bond() {
+    let mut contract = setup_new_test();
+    set_context_caller(&operator_account());
+    contract.update_proposal_cost_in_asset_token(U128::from(5 * E24));
+
+    set_context_caller(&meta_token_account());
+    contract.ft_on_transfer(
+        developer_account(),
+        U128::from(8 * E24),
+        PROPOSAL_BOND_MSG.to_string(),
+    );
+    assert_eq!(contract.get_bond_deposit(developer_account()).0, 8 * E24);
+
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    assert_eq!(contract.get_bond_deposit(developer_account()).0, 3 * E24);
+    let bond = contract.get_proposal_bond(proposal_id).unwrap();
+    assert_eq!(bond.creator_id, developer_account());
+    assert_eq!(bond.near_amount.0, PROPOSAL_STORAGE_NEAR);
+    assert_eq!(bond.asset_token_amount.0, 5 * E24);
+    assert_eq!(contract.get_pending_bonds(0, 10).len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "The required asset token bond is")]
+fn test_fail_create_proposal_without_bond_deposit() {
+    let mut contract = setup_new_test();
+    set_context_caller(&operator_account());
+    contract.update_proposal_cost_in_asset_token(U128::from(5 * E24));
+
+    create_proposal(&mut contract, &developer_account());
+}
+
+#[test]
+#[should_panic(expected = "Invalid ft_on_transfer msg")]
+fn test_fail_bond_deposit_invalid_msg() {
+    let mut contract = setup_new_test();
+    set_context_caller(&meta_token_account());
+    contract.ft_on_transfer(developer_account(), U128::from(E24), "bond".to_string());
+}
+
+/// Deposit a 5 tokens bond for the developer, and submit a proposal with it.
+fn create_bonded_proposal(contract: &mut ProposalsContract) -> ProposalId {
+    set_context_caller(&operator_account());
+    contract.update_proposal_cost_in_asset_token(U128::from(5 * E24));
+    set_context_caller(&meta_token_account());
+    contract.ft_on_transfer(
+        developer_account(),
+        U128::from(5 * E24),
+        PROPOSAL_BOND_MSG.to_string(),
+    );
+    create_proposal(contract, &developer_account())
+}
+
+#[test]
+fn test_creator_cancel_refunds_bond() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_bonded_proposal(&mut contract);
+
+    set_context_caller(&developer_account());
+    contract.cancel_proposal(proposal_id);
+    assert_eq!(contract.get_proposal_state(proposal_id), ProposalState::Canceled);
+    assert!(contract.get_proposal_bond(proposal_id).is_none());
+    assert_eq!(contract.get_treasury_balance(TreasuryAsset::Near).0, 0);
+    assert_eq!(
+        contract
+            .get_treasury_balance(TreasuryAsset::FungibleToken(meta_token_account()))
+            .0,
+        0
+    );
+}
+
+#[test]
+fn test_operator_cancel_slashes_bond() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_bonded_proposal(&mut contract);
+    set_context_caller(&operator_account());
+    contract.approve_proposal(proposal_id);
+
+    // Slashed even once the proposal was reviewed.
+    contract.cancel_proposal(proposal_id);
+    assert_eq!(contract.get_proposal_state(proposal_id), ProposalState::Canceled);
+    assert!(contract.get_proposal_bond(proposal_id).is_none());
+    assert_eq!(
+        contract.get_treasury_balance(TreasuryAsset::Near).0,
+        PROPOSAL_STORAGE_NEAR
+    );
+    assert_eq!(
+        contract
+            .get_treasury_balance(TreasuryAsset::FungibleToken(meta_token_account()))
+            .0,
+        5 * E24
+    );
+}
+
+#[test]
+fn test_bond_returned_below_proposal_threshold() {
+    let mut contract = setup_new_test();
+    set_context_caller(&operator_account());
+    contract.update_proposal_cost_in_asset_token(U128::from(5 * E24));
+    set_context_caller(&meta_token_account());
+    contract.ft_on_transfer(
+        developer_account(),
+        U128::from(5 * E24),
+        PROPOSAL_BOND_MSG.to_string(),
+    );
+
+    let content = proposal_content("Proposal");
+    set_context_deposit(&developer_account(), PROPOSAL_STORAGE_NEAR);
+    contract.create_proposal(content.clone(), None);
+    assert_eq!(contract.get_bond_deposit(developer_account()).0, 0);
+
+    set_callback_context(locking_positions_result(MIN_VOTING_POWER_AMOUNT - 1));
+    let proposal_id = contract.create_proposal_callback(
+        developer_account(),
+        content,
+        contract.internal_default_category(),
+        U128::from(PROPOSAL_STORAGE_NEAR),
+        U128::from(5 * E24),
+    );
+    assert!(proposal_id.is_none());
+    assert_eq!(contract.proposals.len(), 0);
+    assert_eq!(contract.get_bond_deposit(developer_account()).0, 5 * E24);
+}
+
+// ***************
+// * Rate limits *
+// ***************
+
+#[test]
+#[should_panic(expected = "Proposer must wait for the cooldown period to submit a new proposal")]
+fn test_fail_proposer_cooldown() {
+    let mut contract = setup_new_test();
+    create_proposal(&mut contract, &developer_account());
+    create_proposal(&mut contract, &developer_account());
+}
+
+#[test]
+#[should_panic(expected = "Proposer has reached the maximum of open proposals")]
+fn test_fail_proposer_max_open_proposals() {
+    let mut contract = setup_new_test();
+    disable_proposal_cooldown(&mut contract);
+    contract.update_max_open_proposals(2);
+
+    create_proposal(&mut contract, &developer_account());
+    create_proposal(&mut contract, &developer_account());
+    assert_eq!(contract.get_open_proposals_count(developer_account()), 2);
+    create_proposal(&mut contract, &developer_account());
+}
+
+#[test]
+fn test_closed_proposal_frees_open_slot() {
+    let mut contract = setup_new_test();
+    disable_proposal_cooldown(&mut contract);
+    contract.update_max_open_proposals(1);
+
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    set_context_caller(&developer_account());
+    contract.cancel_proposal(proposal_id);
+    assert_eq!(contract.get_open_proposals_count(developer_account()), 0);
+
+    create_proposal(&mut contract, &developer_account());
+    assert_eq!(contract.get_user_proposals_ids(developer_account()), vec![0, 1]);
+}
+
+#[test]
+fn test_proposer_limits_checked_again_on_callback() {
+    let mut contract = setup_new_test();
+    disable_proposal_cooldown(&mut contract);
+    contract.update_max_open_proposals(1);
+
+    // Both proposals are submitted before the first callback.
+    let content = proposal_content("Proposal");
+    set_context_deposit(&developer_account(), PROPOSAL_STORAGE_NEAR);
+    contract.create_proposal(content.clone(), None);
+    contract.create_proposal(content.clone(), None);
+
+    for expected in [Some(0), None] {
+        set_callback_context(locking_positions_result(MIN_VOTING_POWER_AMOUNT));
+        let proposal_id = contract.create_proposal_callback(
+            developer_account(),
+            content.clone(),
+            contract.internal_default_category(),
+            U128::from(PROPOSAL_STORAGE_NEAR),
+            U128::from(0),
+        );
+        assert_eq!(proposal_id, expected);
+    }
+    assert_eq!(contract.proposals.len(), 1);
+}
+
+// *****************
+// * State machine *
+// *****************
+
+#[test]
+fn test_proposal_lifecycle() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    assert_eq!(contract.get_proposal_state(proposal_id), ProposalState::Draft);
+    assert!(contract.get_proposal(proposal_id).draft);
+
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+    let proposal = contract.get_proposal(proposal_id);
+    assert_eq!(proposal.status, ProposalState::VotingProcess);
+    assert_eq!(proposal.v_power_quorum_to_reach.unwrap().0, 100 * E24);
+    assert_eq!(proposal.total_voting_power.unwrap().0, 1_000 * E24);
+    assert_eq!(proposal.voting_round, Some(0));
+    assert_eq!(
+        proposal.vote_end_timestamp.unwrap() - proposal.vote_start_timestamp.unwrap(),
+        VOTING_PERIOD
+    );
+
+    vote(&mut contract, proposal_id, &voter_account(), VoteType::For, 200 * E24);
+    finalize(&mut contract, proposal_id);
+    let result = contract.get_proposal(proposal_id).result.unwrap();
+    assert_eq!(result.state, ProposalState::Accepted);
+    assert_eq!(result.for_votes.0, 200 * E24);
+    assert_eq!(result.turnout.0, 200 * E24);
+    assert_eq!(result.turnout_bp, 2_000);
+    // Quorum was reached, the bond is refunded.
+    assert!(contract.get_proposal_bond(proposal_id).is_none());
+    assert_eq!(contract.get_treasury_balance(TreasuryAsset::Near).0, 0);
+
+    contract.set_proposal_executed(proposal_id);
+    let proposal = contract.get_proposal(proposal_id);
+    assert!(proposal.executed);
+    let history: Vec<ProposalState> = proposal
+        .status_history
+        .iter()
+        .map(|change| change.state)
+        .collect();
+    assert_eq!(
+        history,
+        vec![
+            ProposalState::Draft,
+            ProposalState::Active,
+            ProposalState::VotingProcess,
+            ProposalState::Accepted,
+            ProposalState::Executed,
+        ]
+    );
+    assert_eq!(contract.get_proposals_by_state(ProposalState::Executed, 0, 10).len(), 1);
+    assert!(contract
+        .get_proposals_by_state(ProposalState::VotingProcess, 0, 10)
+        .is_empty());
+}
+
+#[test]
+fn test_proposal_without_quorum_rejected_and_bond_slashed() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+    vote(&mut contract, proposal_id, &voter_account(), VoteType::For, 50 * E24);
+
+    finalize(&mut contract, proposal_id);
+    assert_eq!(contract.get_proposal_state(proposal_id), ProposalState::Rejected);
+    assert!(contract.get_proposal_bond(proposal_id).is_none());
+    assert_eq!(
+        contract.get_treasury_balance(TreasuryAsset::Near).0,
+        PROPOSAL_STORAGE_NEAR
+    );
+}
+
+#[test]
+#[should_panic(expected = "Proposal is not in active state")]
+fn test_fail_start_voting_draft_proposal() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    set_context_caller(&developer_account());
+    contract.start_voting_period(proposal_id);
+}
+
+#[test]
+#[should_panic(expected = "Invalid proposal transition from Rejected to Executed")]
+fn test_fail_execute_rejected_proposal() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+    finalize(&mut contract, proposal_id);
+
+    contract.set_proposal_executed(proposal_id);
+}
+
+#[test]
+#[should_panic(expected = "Proposal is not active or in draft state")]
+fn test_fail_cancel_proposal_on_voting() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+
+    set_context_caller(&developer_account());
+    contract.cancel_proposal(proposal_id);
+}
+
+#[test]
+#[should_panic(expected = "Proposal is not on voting period")]
+fn test_fail_vote_after_voting_period() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+
+    set_context_caller_at(&voter_account(), 8);
+    contract.vote_proposal(proposal_id, VoteType::For, "memo".to_string());
+}
+
+// **********
+// * Keeper *
+// **********
+
+#[test]
+fn test_keeper_finalizes_ended_proposals() {
+    let mut contract = setup_new_test();
+    disable_proposal_cooldown(&mut contract);
+    contract.update_keeper_reward(U128::from(E24));
+    set_context_deposit(&non_owner(), 3 * E24);
+    contract.fund_keeper_budget();
+    assert_eq!(contract.get_keeper_budget().0, 3 * E24);
+
+    for _ in 0..2 {
+        let proposal_id = create_proposal(&mut contract, &developer_account());
+        start_voting(&mut contract, proposal_id, 1_000 * E24);
+    }
+
+    // Voting period is not over.
+    set_context_caller_at(&voter_account(), 3);
+    assert_eq!(contract.finalize_proposals(0, 10), 0);
+    assert_eq!(contract.get_keeper_budget().0, 3 * E24);
+
+    set_context_caller_at(&voter_account(), 8);
+    assert_eq!(contract.finalize_proposals(0, 1), 1);
+    assert_eq!(contract.get_keeper_budget().0, 2 * E24);
+    assert_eq!(contract.finalize_proposals(0, 10), 1);
+    assert_eq!(contract.get_keeper_budget().0, E24);
+
+    for proposal_id in 0..2 {
+        let proposal = contract.get_proposal(proposal_id);
+        assert_eq!(proposal.status, ProposalState::Rejected);
+        assert_eq!(proposal.status_history.last().unwrap().actor, voter_account());
+    }
+}
+
+#[test]
+fn test_keeper_reward_capped_by_budget() {
+    let mut contract = setup_new_test();
+    set_context_caller(&operator_account());
+    contract.update_keeper_reward(U128::from(2 * E24));
+    set_context_deposit(&non_owner(), E24);
+    contract.fund_keeper_budget();
+
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+
+    set_context_caller_at(&voter_account(), 8);
+    assert_eq!(contract.finalize_proposals(0, 10), 1);
+    assert_eq!(contract.get_keeper_budget().0, 0);
+}
+
+// **************
+// * Categories *
+// **************
+
+#[test]
+fn test_proposal_follows_category_rules() {
+    let mut contract = setup_new_test();
+    let mut category = new_category("treasury", 2_000, ApprovalThreshold::TwoThirdsSupermajority);
+    category.quorum_model = QuorumModel::ForOnly;
+    category.voting_period = 3 * 24 * 60 * 60 * 1000;
+    set_context_caller(&owner_account());
+    contract.upsert_proposal_category(category.clone());
+    assert_eq!(contract.get_proposal_categories().len(), 1);
+
+    let proposal_id = submit_proposal(
+        &mut contract,
+        &developer_account(),
+        proposal_content("Proposal"),
+        Some("treasury".to_string()),
+    )
+    .unwrap();
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+    let proposal = contract.get_proposal(proposal_id);
+    assert_eq!(proposal.category.name, "treasury");
+    assert_eq!(proposal.v_power_quorum_to_reach.unwrap().0, 200 * E24);
+    assert_eq!(
+        proposal.vote_end_timestamp.unwrap() - proposal.vote_start_timestamp.unwrap(),
+        category.voting_period
+    );
+
+    // Quorum reached with the For votes, but 60% is below the supermajority.
+    vote(&mut contract, proposal_id, &voter_account(), VoteType::For, 300 * E24);
+    vote(&mut contract, proposal_id, &non_owner(), VoteType::Against, 200 * E24);
+    finalize(&mut contract, proposal_id);
+    assert_eq!(contract.get_proposal_state(proposal_id), ProposalState::Rejected);
+    assert!(contract.get_proposal_bond(proposal_id).is_none());
+    assert_eq!(contract.get_treasury_balance(TreasuryAsset::Near).0, 0);
+}
+
+#[test]
+fn test_category_update_keeps_existing_proposals_rules() {
+    let mut contract = setup_new_test();
+    set_context_caller(&owner_account());
+    contract.upsert_proposal_category(new_category("param", 2_000, ApprovalThreshold::SimpleMajority));
+    let proposal_id = submit_proposal(
+        &mut contract,
+        &developer_account(),
+        proposal_content("Proposal"),
+        Some("param".to_string()),
+    )
+    .unwrap();
+
+    set_context_caller(&owner_account());
+    contract.upsert_proposal_category(new_category("param", 5_000, ApprovalThreshold::SimpleMajority));
+    assert_eq!(contract.get_proposal(proposal_id).category.quorum_floor, 2_000);
+    assert_eq!(
+        contract.get_proposal_category("param".to_string()).unwrap().quorum_floor,
+        5_000
+    );
+}
+
+#[test]
+#[should_panic(expected = "Proposal execution delay has not passed")]
+fn test_fail_execute_before_execution_delay() {
+    let mut contract = setup_new_test();
+    let mut category = new_category("upgrade", 1_000, ApprovalThreshold::SimpleMajority);
+    category.voting_period = 3 * 24 * 60 * 60 * 1000;
+    category.execution_delay = 10 * 24 * 60 * 60 * 1000;
+    set_context_caller(&owner_account());
+    contract.upsert_proposal_category(category);
+
+    let proposal_id = submit_proposal(
+        &mut contract,
+        &developer_account(),
+        proposal_content("Proposal"),
+        Some("upgrade".to_string()),
+    )
+    .unwrap();
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+    vote(&mut contract, proposal_id, &voter_account(), VoteType::For, 200 * E24);
+    finalize(&mut contract, proposal_id);
+    assert_eq!(contract.get_proposal_state(proposal_id), ProposalState::Accepted);
+
+    contract.set_proposal_executed(proposal_id);
+}
+
+#[test]
+#[should_panic(expected = "Proposal category does not exist")]
+fn test_fail_create_proposal_unknown_category() {
+    let mut contract = setup_new_test();
+    submit_proposal(
+        &mut contract,
+        &developer_account(),
+        proposal_content("Proposal"),
+        Some("unknown".to_string()),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Only the admin can call this function.")]
+fn test_fail_upsert_category_not_admin() {
+    let mut contract = setup_new_test();
+    set_context_caller(&operator_account());
+    contract.upsert_proposal_category(new_category("param", 2_000, ApprovalThreshold::SimpleMajority));
+}
+
+// *******************
+// * Adaptive quorum *
+// *******************
+
+#[test]
+fn test_adaptive_quorum_follows_turnout() {
+    let mut contract = setup_new_test();
+    disable_proposal_cooldown(&mut contract);
+    contract.update_adaptive_quorum(Some(AdaptiveQuorumConfig {
+        smoothing: 5_000,
+        turnout_ratio: 5_000,
+        floor: 500,
+        ceiling: 4_000,
+    }));
+    // No turnout history yet.
+    assert_eq!(contract.get_next_quorum_floor(None), QUORUM_FLOOR);
+
+    let first_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, first_id, 1_000 * E24);
+    vote(&mut contract, first_id, &voter_account(), VoteType::For, 1_000 * E24);
+    finalize(&mut contract, first_id);
+    // Full turnout, half of it is over the ceiling.
+    assert_eq!(contract.get_turnout_ema(), Some(10_000));
+    assert_eq!(contract.get_next_quorum_floor(None), 4_000);
+
+    let second_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, second_id, 1_000 * E24);
+    assert_eq!(
+        contract.get_proposal(second_id).v_power_quorum_to_reach.unwrap().0,
+        400 * E24
+    );
+    vote(&mut contract, second_id, &voter_account(), VoteType::For, 200 * E24);
+    finalize(&mut contract, second_id);
+    assert_eq!(contract.get_proposal_state(second_id), ProposalState::Rejected);
+    assert_eq!(contract.get_turnout_ema(), Some(6_000));
+    assert_eq!(contract.get_next_quorum_floor(None), 3_000);
+
+    let history = contract.get_turnout_history(0, 10);
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[1].proposal_id, second_id);
+    assert_eq!(history[1].turnout_bp, 2_000);
+    assert_eq!(history[1].turnout_ema_bp, 6_000);
+}
+
+#[test]
+fn test_adaptive_quorum_never_below_category_floor() {
+    let mut contract = setup_new_test();
+    set_context_caller(&owner_account());
+    contract.upsert_proposal_category(new_category("param", 1_500, ApprovalThreshold::SimpleMajority));
+    set_context_caller(&operator_account());
+    contract.update_adaptive_quorum(Some(AdaptiveQuorumConfig {
+        smoothing: 5_000,
+        turnout_ratio: 1_000,
+        floor: 0,
+        ceiling: 4_000,
+    }));
+
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+    vote(&mut contract, proposal_id, &voter_account(), VoteType::For, 200 * E24);
+    finalize(&mut contract, proposal_id);
+
+    // The adaptive quorum is 10% of the 20% turnout.
+    assert_eq!(contract.get_turnout_ema(), Some(2_000));
+    assert_eq!(contract.get_next_quorum_floor(None), QUORUM_FLOOR);
+    assert_eq!(contract.get_next_quorum_floor(Some("param".to_string())), 1_500);
+}
+
+#[test]
+fn test_adaptive_quorum_category_floor_above_ceiling() {
+    let mut contract = setup_new_test();
+    set_context_caller(&owner_account());
+    contract.upsert_proposal_category(new_category("upgrade", 5_000, ApprovalThreshold::SimpleMajority));
+    set_context_caller(&operator_account());
+    contract.update_adaptive_quorum(Some(AdaptiveQuorumConfig {
+        smoothing: 5_000,
+        turnout_ratio: 5_000,
+        floor: 500,
+        ceiling: 4_000,
+    }));
+    let category = Some("upgrade".to_string());
+
+    // No turnout history yet.
+    assert_eq!(contract.get_next_quorum_floor(category.clone()), 5_000);
+
+    disable_proposal_cooldown(&mut contract);
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+    vote(&mut contract, proposal_id, &voter_account(), VoteType::For, 1_000 * E24);
+    finalize(&mut contract, proposal_id);
+    assert_eq!(contract.get_next_quorum_floor(category), 5_000);
+}
+
+#[test]
+#[should_panic(expected = "Incorrect quorum floor and ceiling basis points.")]
+fn test_fail_invalid_adaptive_quorum() {
+    let mut contract = setup_new_test();
+    set_context_caller(&operator_account());
+    contract.update_adaptive_quorum(Some(AdaptiveQuorumConfig {
+        smoothing: 5_000,
+        turnout_ratio: 5_000,
+        floor: 4_000,
+        ceiling: 500,
+    }));
+}
+
+// *************
+// * Revisions *
+// *************
+
+#[test]
+fn test_update_proposal_appends_revision() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+
+    set_context_caller(&developer_account());
+    contract.update_proposal(proposal_id, proposal_content("Proposal v2"));
+    let proposal = contract.get_proposal(proposal_id);
+    assert_eq!(proposal.title, "Proposal v2");
+    assert_eq!(proposal.revision, 1);
+
+    let revisions = contract.get_proposal_revisions(proposal_id);
+    assert_eq!(revisions.len(), 2);
+    assert_eq!(revisions[0].revision, 0);
+    assert_eq!(revisions[0].title, "Proposal");
+    assert_eq!(revisions[1].revision, 1);
+    assert_ne!(revisions[0].content_hash, revisions[1].content_hash);
+
+    // Votes refer to the revision on voting.
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+    vote(&mut contract, proposal_id, &voter_account(), VoteType::For, 200 * E24);
+    let vote = contract.get_my_vote(voter_account(), proposal_id).unwrap();
+    assert_eq!(vote.revision, 1);
+}
+
+#[test]
+#[should_panic(expected = "Only the proposal creator can call this function.")]
+fn test_fail_update_proposal_not_creator() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+
+    set_context_caller(&operator_account());
+    contract.update_proposal(proposal_id, proposal_content("Proposal v2"));
+}
+
+#[test]
+#[should_panic(expected = "Proposal is not active or in draft state")]
+fn test_fail_update_proposal_on_voting() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+
+    set_context_caller(&developer_account());
+    contract.update_proposal(proposal_id, proposal_content("Proposal v2"));
+}
+
+// ******************
+// * Content hashes *
+// ******************
+
+#[test]
+fn test_verify_offchain_content() {
+    let mut contract = setup_new_test();
+    let mut content = proposal_content("Proposal");
+    content.body = "".to_string();
+    content.offchain_content = Some(OffchainContent {
+        uri: "ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi".to_string(),
+        sha256: Base58CryptoHash::from(env::sha256_array(b"Full proposal body")),
+    });
+    let proposal_id =
+        submit_proposal(&mut contract, &developer_account(), content, None).unwrap();
+
+    assert!(contract.verify_proposal_content(proposal_id, "Full proposal body".to_string()));
+    assert!(!contract.verify_proposal_content(proposal_id, "Edited proposal body".to_string()));
+}
+
+#[test]
+fn test_verify_onchain_content() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    assert!(!contract.verify_proposal_content(proposal_id, "Body".to_string()));
+}
+
+#[test]
+#[should_panic(expected = "Body must be empty when it is stored off-chain")]
+fn test_fail_offchain_content_with_body() {
+    let mut contract = setup_new_test();
+    let mut content = proposal_content("Proposal");
+    content.offchain_content = Some(OffchainContent {
+        uri: "https://forum.example.com/proposal".to_string(),
+        sha256: Base58CryptoHash::from(env::sha256_array(b"Body")),
+    });
+    submit_proposal(&mut contract, &developer_account(), content, None);
+}
+
+#[test]
+#[should_panic(expected = "Proposal title exceeds 200 bytes")]
+fn test_fail_proposal_title_too_long() {
+    let mut contract = setup_new_test();
+    let content = proposal_content(&"a".repeat(MAX_TITLE_LEN + 1));
+    submit_proposal(&mut contract, &developer_account(), content, None);
+}
+
+// ****************
+// * Signed votes *
+// ****************
+
+#[test]
+fn test_submit_signed_vote() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+
+    let signing_key = vote_signing_key();
+    set_context_caller(&voter_account());
+    contract.register_vote_key(vote_public_key(&signing_key));
+
+    let expiry = nanos_to_millis(to_ts(GENESIS_TIME_IN_DAYS + 1));
+    let vote = signed_vote(&contract, &signing_key, &voter_account(), proposal_id, 1, expiry);
+    set_context_caller(&non_owner());
+    assert_eq!(contract.submit_signed_votes(vec![vote]), 1);
+
+    set_callback_context(locking_positions_result(200 * E24));
+    contract.signed_vote_proposal_callback(
+        proposal_id,
+        voter_account(),
+        VoteType::For,
+        "memo".to_string(),
+        U64::from(1),
+    );
+    assert!(contract.has_voted(voter_account(), proposal_id));
+    assert_eq!(contract.get_vote_nonce(voter_account()).0, 1);
+    assert_eq!(contract.get_proposal_votes(proposal_id).for_votes.0, 200 * E24);
+}
+
+#[test]
+fn test_invalid_signed_votes_skipped() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+
+    let signing_key = vote_signing_key();
+    set_context_caller(&voter_account());
+    contract.register_vote_key(vote_public_key(&signing_key));
+
+    let expiry = nanos_to_millis(to_ts(GENESIS_TIME_IN_DAYS + 1));
+    let other_key = SigningKey::from_bytes(&[8; 32]);
+    let votes = vec![
+        // Signed with another key.
+        signed_vote(&contract, &other_key, &voter_account(), proposal_id, 1, expiry),
+        // Expired.
+        signed_vote(&contract, &signing_key, &voter_account(), proposal_id, 1, expiry - 2 * 24 * 60 * 60 * 1000),
+        // Nonce must be greater than the last one used.
+        signed_vote(&contract, &signing_key, &voter_account(), proposal_id, 0, expiry),
+        // Voter without a registered key.
+        signed_vote(&contract, &signing_key, &non_owner(), proposal_id, 1, expiry),
+    ];
+    set_context_caller(&non_owner());
+    assert_eq!(contract.submit_signed_votes(votes), 0);
+}
+
+#[test]
+#[should_panic(expected = "Nonce already used")]
+fn test_fail_signed_vote_nonce_replay() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+
+    for _ in 0..2 {
+        set_callback_context(locking_positions_result(200 * E24));
+        contract.signed_vote_proposal_callback(
+            proposal_id,
+            voter_account(),
+            VoteType::For,
+            "memo".to_string(),
+            U64::from(1),
+        );
+    }
+}
+
+// ******************
+// * Reconciliation *
+// ******************
+
+#[test]
+fn test_votes_shrink_when_locked_voting_power_decreases() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+    vote(&mut contract, proposal_id, &voter_account(), VoteType::For, 500 * E24);
+
+    set_context_caller(&staking_position_account());
+    contract.on_locked_voting_power_decreased(voter_account(), U128::from(200 * E24));
+    assert_eq!(contract.get_proposal_votes(proposal_id).for_votes.0, 200 * E24);
+    let vote = contract.get_my_vote(voter_account(), proposal_id).unwrap();
+    assert_eq!(vote.voting_power.0, 200 * E24);
+    assert_eq!(vote.voter_power_snapshot.0, 500 * E24);
+}
+
+#[test]
+fn test_block_unlock_mode_keeps_votes() {
+    let mut contract = setup_new_test();
+    set_context_caller(&operator_account());
+    contract.update_vote_reconciliation(VoteReconciliationMode::BlockUnlock);
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+    vote(&mut contract, proposal_id, &voter_account(), VoteType::For, 500 * E24);
+
+    set_context_caller(&staking_position_account());
+    contract.on_locked_voting_power_decreased(voter_account(), U128::from(200 * E24));
+    assert_eq!(contract.get_proposal_votes(proposal_id).for_votes.0, 500 * E24);
+}
+
+#[test]
+fn test_block_unlock_mode_locks_before_counting_vote() {
+    let mut contract = setup_new_test();
+    set_context_caller(&operator_account());
+    contract.update_vote_reconciliation(VoteReconciliationMode::BlockUnlock);
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+
+    set_context_caller(&voter_account());
+    contract.vote_proposal(proposal_id, VoteType::For, "memo".to_string());
+    let receipt = near_sdk::test_utils::get_created_receipts().remove(0);
+    assert_eq!(receipt.receiver_id, staking_position_account());
+    assert!(matches!(
+        &receipt.actions[0],
+        VmAction::FunctionCall { function_name, .. } if function_name == "lock_unlocking_until"
+    ));
+    // The vote is counted with the locking positions returned by the lock.
+    assert!(!contract.has_voted(voter_account(), proposal_id));
+
+    set_callback_context(locking_positions_result(500 * E24));
+    contract.vote_proposal_callback(proposal_id, voter_account(), VoteType::For, "memo".to_string());
+    assert_eq!(contract.get_proposal_votes(proposal_id).for_votes.0, 500 * E24);
+}
+
+#[test]
+fn test_reconcile_voter_paginated() {
+    let mut contract = setup_new_test();
+    disable_proposal_cooldown(&mut contract);
+    for _ in 0..2 {
+        let proposal_id = create_proposal(&mut contract, &developer_account());
+        start_voting(&mut contract, proposal_id, 1_000 * E24);
+        vote(&mut contract, proposal_id, &voter_account(), VoteType::For, 500 * E24);
+    }
+
+    set_callback_context(locking_positions_result(200 * E24));
+    contract.reconcile_voter_callback(voter_account(), 0, 1);
+    assert_eq!(contract.get_proposal_votes(0).for_votes.0, 200 * E24);
+    assert_eq!(contract.get_proposal_votes(1).for_votes.0, 500 * E24);
+
+    set_callback_context(locking_positions_result(200 * E24));
+    contract.reconcile_voter_callback(voter_account(), 1, 1);
+    assert_eq!(contract.get_proposal_votes(1).for_votes.0, 200 * E24);
+}
+
+#[test]
+#[should_panic(expected = "Only the staking position contract can call this function.")]
+fn test_fail_locked_voting_power_decreased_not_staking() {
+    let mut contract = setup_new_test();
+    set_context_caller(&voter_account());
+    contract.on_locked_voting_power_decreased(voter_account(), U128::from(0));
+}
+
+// ************
+// * Treasury *
+// ************
+
+#[test]
+fn test_treasury_deposits() {
+    let mut contract = setup_new_test();
+    set_context_deposit(&non_owner(), 5 * E24);
+    contract.deposit_to_treasury();
+
+    set_context_caller(&owner_account());
+    contract.add_treasury_token(usdc_token_account());
+    set_context_caller(&usdc_token_account());
+    contract.ft_on_transfer(non_owner(), U128::from(100), TREASURY_DEPOSIT_MSG.to_string());
+
+    assert_eq!(contract.get_treasury_balance(TreasuryAsset::Near).0, 5 * E24);
+    assert_eq!(
+        contract
+            .get_treasury_balance(TreasuryAsset::FungibleToken(usdc_token_account()))
+            .0,
+        100
+    );
+    let movements = contract.get_treasury_movements(0, 10);
+    assert_eq!(movements.len(), 2);
+    assert_eq!(movements[0].kind, TreasuryMovementKind::Deposit);
+    assert_eq!(movements[0].account_id, non_owner());
+}
+
+#[test]
+#[should_panic(expected = "Token is not whitelisted")]
+fn test_fail_treasury_deposit_token_not_whitelisted() {
+    let mut contract = setup_new_test();
+    set_context_caller(&usdc_token_account());
+    contract.ft_on_transfer(non_owner(), U128::from(100), TREASURY_DEPOSIT_MSG.to_string());
+}
+
+#[test]
+fn test_treasury_payout_on_execution() {
+    let mut contract = setup_new_test();
+    set_context_deposit(&non_owner(), 10 * E24);
+    contract.deposit_to_treasury();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    set_context_caller(&developer_account());
+    contract.set_treasury_payout(
+        proposal_id,
+        TreasuryPayout::Transfer {
+            receiver_id: developer_account(),
+            asset: TreasuryAsset::Near,
+            amount: U128::from(4 * E24),
+        },
+    );
+
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+    vote(&mut contract, proposal_id, &voter_account(), VoteType::For, 200 * E24);
+    finalize(&mut contract, proposal_id);
+    contract.set_proposal_executed(proposal_id);
+
+    assert_eq!(contract.get_treasury_balance(TreasuryAsset::Near).0, 6 * E24);
+    assert!(contract.get_treasury_payout(proposal_id).is_none());
+    let movement = contract.get_treasury_movements(1, 1).pop().unwrap();
+    assert_eq!(movement.kind, TreasuryMovementKind::ProposalPayout);
+    assert_eq!(movement.proposal_id, Some(proposal_id));
+}
+
+#[test]
+fn test_treasury_stream_payout() {
+    let mut contract = setup_new_test();
+    set_context_deposit(&non_owner(), 10 * E24);
+    contract.deposit_to_treasury();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    set_context_caller(&developer_account());
+    contract.set_treasury_payout(
+        proposal_id,
+        TreasuryPayout::Stream {
+            receiver_id: developer_account(),
+            asset: TreasuryAsset::Near,
+            amount: U128::from(10 * E24),
+            start_timestamp: nanos_to_millis(to_ts(GENESIS_TIME_IN_DAYS + 8)),
+            end_timestamp: nanos_to_millis(to_ts(GENESIS_TIME_IN_DAYS + 18)),
+        },
+    );
+
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+    vote(&mut contract, proposal_id, &voter_account(), VoteType::For, 200 * E24);
+    finalize(&mut contract, proposal_id);
+    contract.set_proposal_executed(proposal_id);
+    // The whole stream is reserved on execution.
+    assert_eq!(contract.get_treasury_balance(TreasuryAsset::Near).0, 0);
+
+    set_context_caller_at(&developer_account(), 13);
+    assert_eq!(contract.get_stream_withdrawable(0).0, 5 * E24);
+    contract.withdraw_stream(0);
+    assert_eq!(contract.get_stream(0).unwrap().withdrawn.0, 5 * E24);
+    assert_eq!(contract.get_stream_withdrawable(0).0, 0);
+}
+
+#[test]
+#[should_panic(expected = "Spending limit exceeded for the period.")]
+fn test_fail_operator_spend_over_limit() {
+    let mut contract = setup_new_test();
+    set_context_deposit(&non_owner(), 10 * E24);
+    contract.deposit_to_treasury();
+    set_context_caller(&owner_account());
+    contract.update_spending_limit(
+        TreasuryAsset::Near,
+        U64::from(30 * 24 * 60 * 60 * 1000),
+        U128::from(3 * E24),
+    );
+
+    set_context_caller(&operator_account());
+    contract.operator_spend(TreasuryAsset::Near, developer_account(), U128::from(2 * E24));
+    contract.operator_spend(TreasuryAsset::Near, developer_account(), U128::from(2 * E24));
+}
+
+#[test]
+fn test_failed_treasury_transfer_recovered() {
+    let mut contract = setup_new_test();
+    set_context_deposit(&non_owner(), 10 * E24);
+    contract.deposit_to_treasury();
+    set_context_caller(&owner_account());
+    contract.update_spending_limit(
+        TreasuryAsset::Near,
+        U64::from(30 * 24 * 60 * 60 * 1000),
+        U128::from(3 * E24),
+    );
+    set_context_caller(&operator_account());
+    contract.operator_spend(TreasuryAsset::Near, developer_account(), U128::from(2 * E24));
+    assert_eq!(contract.get_treasury_balance(TreasuryAsset::Near).0, 8 * E24);
+
+    set_callback_context(PromiseResult::Failed);
+    contract.after_treasury_transfer_callback(
+        TreasuryAsset::Near,
+        developer_account(),
+        U128::from(2 * E24),
+        None,
+    );
+    assert_eq!(contract.get_treasury_balance(TreasuryAsset::Near).0, 10 * E24);
+    let movement = contract.get_treasury_movements(2, 1).pop().unwrap();
+    assert_eq!(movement.kind, TreasuryMovementKind::FailedTransfer);
+}
+
+// **********
+// * Digest *
+// **********
+
+#[test]
+fn test_result_digest_independent_of_vote_order() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+    vote(&mut contract, proposal_id, &voter_account(), VoteType::For, 300 * E24);
+    vote(&mut contract, proposal_id, &non_owner(), VoteType::Against, 100 * E24);
+    let digest = contract.get_proposal_result_digest(proposal_id);
+
+    set_context_caller(&voter_account());
+    contract.remove_vote_proposal(proposal_id);
+    assert_ne!(contract.get_proposal_result_digest(proposal_id), digest);
+
+    // Same votes, stored in a different order.
+    vote(&mut contract, proposal_id, &voter_account(), VoteType::For, 300 * E24);
+    assert_eq!(contract.get_proposal_result_digest(proposal_id), digest);
+}
+
+#[test]
+fn test_result_digest_from_paged_voters() {
+    let mut contract = setup_new_test();
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+    start_voting(&mut contract, proposal_id, 1_000 * E24);
+    for index in 0..5 {
+        let voter_id = multi_voter_account(index.to_string());
+        vote(&mut contract, proposal_id, &voter_id, VoteType::For, (index + 1) * E24);
+    }
+    assert_eq!(contract.get_proposal_votes(proposal_id).voters_count, 5);
+
+    let mut votes_digest = [0u8; 32];
+    let mut from_index = 0;
+    loop {
+        let page = contract.get_proposal_voters(proposal_id, from_index, 2);
+        if page.is_empty() {
+            break;
+        }
+        from_index += page.len() as u32;
+        for vote in page {
+            let data = (
+                vote.voter_id,
+                Vote {
+                    proposal_id: vote.proposal_id,
+                    vote_type: vote.vote_type,
+                    voting_power: vote.voting_power.0,
+                    voter_power_snapshot: vote.voter_power_snapshot.0,
+                    revision: vote.revision,
+                    memo: vote.memo,
+                    timestamp: vote.timestamp,
+                },
+            );
+            let vote_hash = env::sha256_array(&data.try_to_vec().unwrap());
+            for (byte, vote_byte) in votes_digest.iter_mut().zip(vote_hash) {
+                *byte ^= vote_byte;
+            }
+        }
+    }
+    assert_eq!(from_index, 5);
+
+    let votes = contract.get_proposal_votes(proposal_id);
+    let data = (
+        proposal_id,
+        votes.for_votes.0,
+        votes.against_votes.0,
+        votes.abstain_votes.0,
+        votes_digest,
+    );
+    assert_eq!(
+        contract.get_proposal_result_digest(proposal_id),
+        Base58CryptoHash::from(env::sha256_array(&data.try_to_vec().unwrap()))
+    );
+}
+
+// ***************
+// * Voter stats *
+// ***************
+
+#[test]
+fn test_voter_stats_and_leaderboard() {
+    let mut contract = setup_new_test();
+    disable_proposal_cooldown(&mut contract);
+    for proposal_id in 0..3 {
+        create_proposal(&mut contract, &developer_account());
+        start_voting(&mut contract, proposal_id, 1_000 * E24);
+        vote(&mut contract, proposal_id, &voter_account(), VoteType::For, 200 * E24);
+        if proposal_id != 1 {
+            vote(&mut contract, proposal_id, &non_owner(), VoteType::For, 100 * E24);
+        }
+    }
+
+    let stats = contract.get_voter_stats(voter_account()).unwrap();
+    assert_eq!(stats.proposals_voted, 3);
+    assert_eq!(stats.current_streak, 3);
+    assert_eq!(stats.best_streak, 3);
+    assert_eq!(stats.participation_bp, 10_000);
+
+    // Round 1 was skipped, the streak restarts.
+    let stats = contract.get_voter_stats(non_owner()).unwrap();
+    assert_eq!(stats.proposals_voted, 2);
+    assert_eq!(stats.current_streak, 1);
+    assert_eq!(stats.best_streak, 1);
+    assert_eq!(stats.participation_bp, 6_666);
+
+    let leaderboard = contract.get_voter_leaderboard(10);
+    assert_eq!(leaderboard.len(), 2);
+    assert_eq!(leaderboard[0].voter_id, voter_account());
+    assert_eq!(leaderboard[1].voter_id, non_owner());
+
+    set_context_caller(&non_owner());
+    contract.remove_vote_proposal(2);
+    assert_eq!(contract.get_voter_stats(non_owner()).unwrap().proposals_voted, 1);
+
+    for proposal_id in 0..3 {
+        finalize(&mut contract, proposal_id);
+    }
+    let stats = contract.get_voter_stats(developer_account()).unwrap();
+    assert_eq!(stats.proposals_created, 3);
+    assert_eq!(stats.proposals_accepted, 3);
+    assert_eq!(stats.proposals_voted, 0);
+}
+
+#[test]
+fn test_vote_on_older_round_keeps_streak() {
+    let mut contract = setup_new_test();
+    disable_proposal_cooldown(&mut contract);
+    contract.update_max_open_proposals(4);
+    for proposal_id in 0..4 {
+        create_proposal(&mut contract, &developer_account());
+        start_voting(&mut contract, proposal_id, 1_000 * E24);
+    }
+
+    vote(&mut contract, 1, &voter_account(), VoteType::For, 200 * E24);
+    vote(&mut contract, 3, &voter_account(), VoteType::For, 200 * E24);
+    let streak = contract.get_voter_stats(voter_account()).unwrap().current_streak;
+    assert_eq!(streak, 1);
+
+    vote(&mut contract, 2, &voter_account(), VoteType::For, 200 * E24);
+    let stats = contract.get_voter_stats(voter_account()).unwrap();
+    assert_eq!(stats.proposals_voted, 3);
+    assert_eq!(stats.current_streak, streak);
+    assert_eq!(stats.best_streak, 1);
+}
+
+// ***********
+// * Council *
+// ***********
+
+#[test]
+fn test_council_fast_track() {
+    let mut contract = setup_new_test();
+    setup_council(&mut contract);
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+
+    set_context_caller(&council_account(1));
+    assert!(contract.approve_fast_track(proposal_id).is_none());
+    assert_eq!(contract.get_proposal_state(proposal_id), ProposalState::Draft);
+
+    set_context_caller(&council_account(2));
+    assert!(contract.approve_fast_track(proposal_id).is_some());
+    let proposal = contract.get_proposal(proposal_id);
+    assert_eq!(proposal.status, ProposalState::Active);
+    assert_eq!(proposal.category.voting_period, DEFAULT_FAST_TRACK_VOTING_PERIOD);
+    assert_eq!(
+        proposal.category.approval_threshold,
+        ApprovalThreshold::TwoThirdsSupermajority
+    );
+    assert_eq!(
+        proposal.council_history.last().unwrap().kind,
+        CouncilActionKind::FastTracked
+    );
+
+    set_callback_context(total_voting_power_result(1_000 * E24));
+    contract.start_voting_period_callback(proposal_id);
+    let proposal = contract.get_proposal(proposal_id);
+    assert_eq!(proposal.status, ProposalState::VotingProcess);
+    assert_eq!(
+        proposal.vote_end_timestamp.unwrap() - proposal.vote_start_timestamp.unwrap(),
+        DEFAULT_FAST_TRACK_VOTING_PERIOD
+    );
+}
+
+#[test]
+fn test_council_counts_current_members_only() {
+    let mut contract = setup_new_test();
+    setup_council(&mut contract);
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+
+    set_context_caller(&council_account(1));
+    contract.approve_fast_track(proposal_id);
+    set_context_caller(&owner_account());
+    contract.update_council([council_account(2), council_account(3)].to_vec(), 2);
+
+    set_context_caller(&council_account(2));
+    assert!(contract.approve_fast_track(proposal_id).is_none());
+    assert_eq!(contract.get_proposal_state(proposal_id), ProposalState::Draft);
+}
+
+#[test]
+#[should_panic(expected = "Council member already approved.")]
+fn test_fail_council_duplicated_approval() {
+    let mut contract = setup_new_test();
+    setup_council(&mut contract);
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+
+    set_context_caller(&council_account(1));
+    contract.approve_fast_track(proposal_id);
+    contract.approve_fast_track(proposal_id);
+}
+
+#[test]
+#[should_panic(expected = "Only council members can call this function.")]
+fn test_fail_fast_track_not_council() {
+    let mut contract = setup_new_test();
+    setup_council(&mut contract);
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+
+    set_context_caller(&operator_account());
+    contract.approve_fast_track(proposal_id);
+}
+
+#[test]
+fn test_council_emergency_action() {
+    let mut contract = setup_new_test();
+    setup_council(&mut contract);
+    let proposal_id = create_emergency_proposal(&mut contract);
+
+    set_context_caller(&council_account(1));
+    contract.propose_emergency_action(proposal_id, EmergencyAction::PauseProposals);
+    assert!(contract.get_emergency_action(proposal_id).is_some());
+    assert_eq!(contract.get_proposal_state(proposal_id), ProposalState::Draft);
+
+    set_context_caller(&council_account(2));
+    contract.approve_emergency_action(proposal_id);
+    assert!(contract.get_emergency_action(proposal_id).is_none());
+    assert!(!contract.open_for_new_proposals);
+    assert!(contract.get_proposal_bond(proposal_id).is_none());
+    let proposal = contract.get_proposal(proposal_id);
+    assert_eq!(proposal.status, ProposalState::Executed);
+    let kinds: Vec<CouncilActionKind> = proposal
+        .council_history
+        .iter()
+        .map(|record| record.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            CouncilActionKind::EmergencyProposed,
+            CouncilActionKind::EmergencyApproval,
+            CouncilActionKind::EmergencyApproval,
+            CouncilActionKind::EmergencyExecuted,
+        ]
+    );
+}
+
+#[test]
+fn test_emergency_call_contract_deposit_from_treasury() {
+    let mut contract = setup_new_test();
+    setup_council(&mut contract);
+    set_context_deposit(&non_owner(), 5 * E24);
+    contract.deposit_to_treasury();
+    let proposal_id = create_emergency_proposal(&mut contract);
+
+    set_context_caller(&council_account(1));
+    contract.propose_emergency_action(proposal_id, pause_staking_action(2 * E24));
+    set_context_caller(&council_account(2));
+    contract.approve_emergency_action(proposal_id);
+    assert_eq!(contract.get_treasury_balance(TreasuryAsset::Near).0, 3 * E24);
+    let movement = contract.get_treasury_movements(1, 1).pop().unwrap();
+    assert_eq!(movement.kind, TreasuryMovementKind::EmergencyCall);
+    assert_eq!(movement.account_id, staking_position_account());
+    assert_eq!(movement.proposal_id, Some(proposal_id));
+
+    // The deposit is back in the treasury when the call fails.
+    set_callback_context(PromiseResult::Failed);
+    contract.after_treasury_transfer_callback(
+        TreasuryAsset::Near,
+        staking_position_account(),
+        U128::from(2 * E24),
+        None,
+    );
+    assert_eq!(contract.get_treasury_balance(TreasuryAsset::Near).0, 5 * E24);
+}
+
+#[test]
+#[should_panic(expected = "Not enough treasury balance.")]
+fn test_fail_emergency_call_contract_deposit_over_treasury() {
+    let mut contract = setup_new_test();
+    setup_council(&mut contract);
+    set_context_deposit(&non_owner(), E24);
+    contract.deposit_to_treasury();
+    let proposal_id = create_emergency_proposal(&mut contract);
+
+    set_context_caller(&council_account(1));
+    contract.propose_emergency_action(proposal_id, pause_staking_action(2 * E24));
+    set_context_caller(&council_account(2));
+    contract.approve_emergency_action(proposal_id);
+}
+
+#[test]
+#[should_panic(expected = "Proposal category does not allow emergency actions.")]
+fn test_fail_emergency_action_not_emergency_category() {
+    let mut contract = setup_new_test();
+    setup_council(&mut contract);
+    let proposal_id = create_proposal(&mut contract, &developer_account());
+
+    set_context_caller(&council_account(1));
+    contract.propose_emergency_action(proposal_id, EmergencyAction::PauseProposals);
+}
+
+#[test]
+#[should_panic(expected = "Invalid council threshold.")]
+fn test_fail_update_council_invalid_threshold() {
+    let mut contract = setup_new_test();
+    set_context_caller(&owner_account());
+    contract.update_council([council_account(1)].to_vec(), 2);
+}
@@ -2,21 +2,21 @@
 #![allow(unused_variables)]
 #![allow(dead_code)]
 
-use near_sdk::{AccountId, MockedBlockchain, PromiseResult, VMContext, Balance, PublicKey, Gas};
+use near_sdk::json_types::U128;
+use near_sdk::{
+    testing_env, AccountId, Balance, Gas, MockedBlockchain, PromiseResult, PublicKey, VMContext,
+};
 
-use crate::constants::YOCTO_UNITS;
 use crate::types::*;
 
-pub const LOCKUP_NEAR: u128 = 1000;
+pub const E24: u128 = 1_000_000_000_000_000_000_000_000;
 pub const GENESIS_TIME_IN_DAYS: u64 = 500;
-pub const YEAR: u64 = 365;
 pub const TEST_INITIAL_BALANCE: u128 = 100;
 
-pub const MIN_LOCKING_PERIOD: Days = 30;
-pub const MAX_LOCKING_PERIOD: Days = 300;
-pub const MIN_DEPOSIT_AMOUNT: Balance = 1 * YOCTO_UNITS;
-pub const MAX_LOCKING_POSITIONS: u8 = 20;
-pub const MAX_VOTING_POSITIONS: u8 = 100;
+pub const VOTING_PERIOD: EpochMillis = 7 * 24 * 60 * 60 * 1000;
+pub const MIN_VOTING_POWER_AMOUNT: u128 = 10 * E24;
+pub const PROPOSAL_STORAGE_NEAR: Balance = E24;
+pub const QUORUM_FLOOR: BasisPoints = 1_000;
 
 pub fn system_account() -> AccountId {
     AccountId::new_unchecked("system.proposals.near".to_string())
@@ -50,6 +50,14 @@ pub fn meta_token_account() -> AccountId {
     AccountId::new_unchecked("meta-token.proposals.near".to_string())
 }
 
+pub fn usdc_token_account() -> AccountId {
+    AccountId::new_unchecked("usdc-token.proposals.near".to_string())
+}
+
+pub fn staking_position_account() -> AccountId {
+    AccountId::new_unchecked("staking-position.proposals.near".to_string())
+}
+
 pub fn voter_account() -> AccountId {
     AccountId::new_unchecked("voter.proposals.near".to_string())
 }
@@ -58,25 +66,29 @@ pub fn multi_voter_account(id: String) -> AccountId {
     AccountId::new_unchecked(format!("voter_{}.proposals.near", id))
 }
 
+pub fn council_account(id: u8) -> AccountId {
+    AccountId::new_unchecked(format!("council_{}.proposals.near", id))
+}
+
 pub fn votable_account() -> AccountId {
     AccountId::new_unchecked("votable.proposals.near".to_string())
 }
 
 pub fn ntoy(near_amount: u128) -> u128 {
-    return near_amount * 10u128.pow(24)
+    near_amount * 10u128.pow(24)
 }
 
 pub fn yton(yoctos_amount: u128) -> f64 {
-    return yoctos_amount as f64 / 10u128.pow(24) as f64
+    yoctos_amount as f64 / 10u128.pow(24) as f64
 }
 //convert yocto to f64 NEAR truncate to 4 dec places
 pub fn ytof(yoctos_amount: u128) -> f64 {
     let four_dec_f:f64 = ((yoctos_amount / 10u128.pow(20)) as u32).into();
-    return four_dec_f / 10000.0;
+    four_dec_f / 10000.0
 }
 
 pub fn to_nanos(num_days: u64) -> u64 {
-    return num_days * 86400_000_000_000
+    num_days * 86_400_000_000_000
 }
 
 #[inline]
@@ -86,7 +98,7 @@ pub fn nanos_to_millis(nanoseconds: u64) -> EpochMillis {
 
 pub fn to_ts(num_days: u64) -> u64 {
     // 2018-08-01 UTC in nanoseconds
-    1533081600_000_000_000 + to_nanos(num_days)
+    1_533_081_600_000_000_000 + to_nanos(num_days)
 }
 
 pub fn assert_almost_eq_with_max_delta(left: u128, right: u128, max_delta: u128) {
@@ -130,3 +142,68 @@ pub fn get_context(
         output_data_receivers: Vec::new()
     }
 }
+
+/// Call from `predecessor_account_id`, `num_days` after the genesis time.
+pub fn set_context_caller_at(predecessor_account_id: &AccountId, num_days: u64) {
+    testing_env!(get_context(
+        predecessor_account_id.clone(),
+        ntoy(TEST_INITIAL_BALANCE),
+        0,
+        to_ts(GENESIS_TIME_IN_DAYS + num_days),
+    ));
+}
+
+pub fn set_context_caller(predecessor_account_id: &AccountId) {
+    set_context_caller_at(predecessor_account_id, 0);
+}
+
+pub fn set_context_deposit(predecessor_account_id: &AccountId, attached_deposit: Balance) {
+    let mut context = get_context(
+        predecessor_account_id.clone(),
+        ntoy(TEST_INITIAL_BALANCE),
+        0,
+        to_ts(GENESIS_TIME_IN_DAYS),
+    );
+    context.attached_deposit = attached_deposit;
+    testing_env!(context);
+}
+
+/// Callback executed by the contract with the result of a cross-contract call.
+pub fn set_callback_context_at(promise_result: PromiseResult, num_days: u64) {
+    testing_env!(
+        get_context(
+            contract_account(),
+            ntoy(TEST_INITIAL_BALANCE),
+            0,
+            to_ts(GENESIS_TIME_IN_DAYS + num_days),
+        ),
+        near_sdk::VMConfig::test(),
+        near_sdk::RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![promise_result],
+    );
+}
+
+pub fn set_callback_context(promise_result: PromiseResult) {
+    set_callback_context_at(promise_result, 0);
+}
+
+/// Result of `get_all_locking_positions` with a single locked position.
+pub fn locking_positions_result(voting_power: u128) -> PromiseResult {
+    let locking_positions = vec![LockingPositionJSON {
+        index: Some(0),
+        amount: U128::from(voting_power),
+        locking_period: 30,
+        voting_power: U128::from(voting_power),
+        unlocking_started_at: None,
+        is_unlocked: false,
+        is_unlocking: false,
+        is_locked: true,
+    }];
+    PromiseResult::Successful(near_sdk::serde_json::to_vec(&locking_positions).unwrap())
+}
+
+/// Result of `get_total_voting_power`.
+pub fn total_voting_power_result(voting_power: u128) -> PromiseResult {
+    PromiseResult::Successful(near_sdk::serde_json::to_vec(&U128::from(voting_power)).unwrap())
+}
@@ -0,0 +1,486 @@
+use crate::*;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::PromiseResult;
+use utils::proportional;
+
+pub type StreamId = u32;
+
+/// Assets held by the treasury: NEAR or a whitelisted fungible token.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum TreasuryAsset {
+    Near,
+    FungibleToken(AccountId),
+}
+
+/// Payment released by the treasury when a proposal is executed.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum TreasuryPayout {
+    Transfer {
+        receiver_id: AccountId,
+        asset: TreasuryAsset,
+        amount: U128,
+    },
+    /// The amount is released linearly to the grantee between start and end.
+    Stream {
+        receiver_id: AccountId,
+        asset: TreasuryAsset,
+        amount: U128,
+        start_timestamp: EpochMillis,
+        end_timestamp: EpochMillis,
+    },
+}
+
+/// Max amount operators can spend without a proposal on every period.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SpendingLimit {
+    pub period: EpochMillis,
+    pub limit: U128,
+    pub period_start: EpochMillis,
+    pub spent: U128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PaymentStream {
+    pub proposal_id: ProposalId,
+    pub receiver_id: AccountId,
+    pub asset: TreasuryAsset,
+    pub amount: U128,
+    pub withdrawn: U128,
+    pub start_timestamp: EpochMillis,
+    pub end_timestamp: EpochMillis,
+}
+
+impl PaymentStream {
+    pub(crate) fn vested(&self, now: EpochMillis) -> Balance {
+        if now <= self.start_timestamp {
+            0
+        } else if now >= self.end_timestamp {
+            self.amount.0
+        } else {
+            let elapsed = (now - self.start_timestamp) as u128;
+            let duration = (self.end_timestamp - self.start_timestamp) as u128;
+            proportional(self.amount.0, elapsed, duration)
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum TreasuryMovementKind {
+    Deposit,
+    SlashedBond,
+    ProposalPayout,
+    StreamReserved,
+    StreamWithdraw,
+    OperatorSpend,
+    FailedTransfer,
+    /// Deposit attached to an emergency contract call of the council.
+    EmergencyCall,
+}
+
+/// Entry of the treasury ledger, every change of the balances is recorded.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TreasuryMovement {
+    pub kind: TreasuryMovementKind,
+    pub asset: TreasuryAsset,
+    pub amount: U128,
+    pub account_id: AccountId,
+    pub proposal_id: Option<ProposalId>,
+    pub timestamp: EpochMillis,
+}
+
+#[near_bindgen]
+impl ProposalsContract {
+    /// Anyone can fund the treasury with NEAR.
+    #[payable]
+    pub fn deposit_to_treasury(&mut self) {
+        let amount = env::attached_deposit();
+        require!(amount > 0, "Attach NEAR to deposit to the treasury.");
+        self.internal_treasury_deposit(
+            TreasuryMovementKind::Deposit,
+            &TreasuryAsset::Near,
+            amount,
+            &env::predecessor_account_id(),
+            None,
+        );
+    }
+
+    /// Attach the treasury payout released when the proposal is executed.
+    /// Only the creator, before the voting period starts.
+    pub fn set_treasury_payout(&mut self, proposal_id: ProposalId, payout: TreasuryPayout) {
+        let proposal = self.internal_get_proposal(&proposal_id);
+        require!(
+            proposal.creator_id == env::predecessor_account_id(),
+            "Only the proposal creator can set the treasury payout."
+        );
+        require!(
+            self.internal_proposal_is_active_or_draft(proposal_id),
+            "Treasury payout cannot change once voting has started."
+        );
+        match &payout {
+            TreasuryPayout::Transfer { asset, amount, .. } => {
+                self.assert_treasury_asset(asset);
+                require!(amount.0 > 0, "Payout amount must be greater than zero.");
+            }
+            TreasuryPayout::Stream {
+                asset,
+                amount,
+                start_timestamp,
+                end_timestamp,
+                ..
+            } => {
+                self.assert_treasury_asset(asset);
+                require!(amount.0 > 0, "Payout amount must be greater than zero.");
+                require!(start_timestamp < end_timestamp, "Invalid stream period.");
+            }
+        }
+        self.treasury_payouts.insert(&proposal_id, &payout);
+    }
+
+    /// Withdraw the vested amount of a payment stream.
+    pub fn withdraw_stream(&mut self, stream_id: StreamId) -> Promise {
+        let mut stream = self
+            .treasury_streams
+            .get(stream_id as u64)
+            .expect("Stream does not exist.");
+        require!(
+            stream.receiver_id == env::predecessor_account_id(),
+            "Only the stream receiver can withdraw."
+        );
+        let available = stream.vested(get_current_epoch_millis()) - stream.withdrawn.0;
+        require!(available > 0, "Nothing to withdraw from the stream.");
+        stream.withdrawn = U128::from(stream.withdrawn.0 + available);
+        self.treasury_streams.replace(stream_id as u64, &stream);
+        self.internal_record_treasury_movement(
+            TreasuryMovementKind::StreamWithdraw,
+            &stream.asset,
+            available,
+            &stream.receiver_id,
+            Some(stream.proposal_id),
+        );
+        self.internal_transfer_treasury_asset(
+            &stream.asset,
+            stream.receiver_id,
+            available,
+            Some(stream_id),
+        )
+    }
+
+    // ************
+    // * Operator *
+    // ************
+
+    /// Spend from the treasury within the spending limit of the current period.
+    pub fn operator_spend(
+        &mut self,
+        asset: TreasuryAsset,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> Promise {
+        self.assert_only_operator();
+        let mut limit = self
+            .spending_limits
+            .get(&asset)
+            .expect("No spending limit for the asset.");
+        let now = get_current_epoch_millis();
+        if now >= limit.period_start + limit.period {
+            limit.period_start = now;
+            limit.spent = U128::from(0);
+        }
+        let spent = limit.spent.0 + amount.0;
+        require!(spent <= limit.limit.0, "Spending limit exceeded for the period.");
+        limit.spent = U128::from(spent);
+        self.spending_limits.insert(&asset, &limit);
+
+        self.internal_treasury_withdraw(
+            TreasuryMovementKind::OperatorSpend,
+            &asset,
+            amount.0,
+            &receiver_id,
+            None,
+        );
+        self.internal_transfer_treasury_asset(&asset, receiver_id, amount.0, None)
+    }
+
+    // *********
+    // * Admin *
+    // *********
+
+    pub fn add_treasury_token(&mut self, token_id: AccountId) {
+        self.assert_only_admin();
+        self.treasury_tokens.insert(&token_id);
+    }
+
+    /// Tokens with balance in the treasury cannot be removed.
+    pub fn remove_treasury_token(&mut self, token_id: AccountId) {
+        self.assert_only_admin();
+        let asset = TreasuryAsset::FungibleToken(token_id.clone());
+        require!(
+            self.internal_get_treasury_balance(&asset) == 0,
+            "The treasury still holds the token."
+        );
+        self.treasury_tokens.remove(&token_id);
+    }
+
+    /// Set the operators spending limit for an asset. A zero limit removes it.
+    pub fn update_spending_limit(&mut self, asset: TreasuryAsset, period: U64, limit: U128) {
+        self.assert_only_admin();
+        self.assert_treasury_asset(&asset);
+        if limit.0 == 0 {
+            self.spending_limits.remove(&asset);
+        } else {
+            require!(period.0 > 0, "Invalid spending period.");
+            self.spending_limits.insert(
+                &asset,
+                &SpendingLimit {
+                    period: period.0,
+                    limit,
+                    period_start: get_current_epoch_millis(),
+                    spent: U128::from(0),
+                },
+            );
+        }
+    }
+
+    #[private]
+    pub fn after_treasury_transfer_callback(
+        &mut self,
+        asset: TreasuryAsset,
+        receiver_id: AccountId,
+        amount: U128,
+        stream_id: Option<StreamId>,
+    ) {
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(_) => {
+                log!("TREASURY: {} transferred to {}", amount.0, &receiver_id);
+            }
+            PromiseResult::Failed => {
+                log!("FAILED: {} not transferred to {}", amount.0, &receiver_id);
+                match stream_id {
+                    // Let the receiver withdraw again.
+                    Some(stream_id) => {
+                        let mut stream = self.treasury_streams.get(stream_id as u64).unwrap();
+                        stream.withdrawn = U128::from(stream.withdrawn.0 - amount.0);
+                        self.treasury_streams.replace(stream_id as u64, &stream);
+                        self.internal_record_treasury_movement(
+                            TreasuryMovementKind::FailedTransfer,
+                            &asset,
+                            amount.0,
+                            &receiver_id,
+                            Some(stream.proposal_id),
+                        );
+                    }
+                    None => self.internal_treasury_deposit(
+                        TreasuryMovementKind::FailedTransfer,
+                        &asset,
+                        amount.0,
+                        &receiver_id,
+                        None,
+                    ),
+                }
+            }
+        }
+    }
+
+    // *********
+    // * View  *
+    // *********
+
+    pub fn get_treasury_balances(&self) -> Vec<(TreasuryAsset, U128)> {
+        self.treasury_balances
+            .iter()
+            .map(|(asset, balance)| (asset, U128::from(balance)))
+            .collect()
+    }
+
+    pub fn get_treasury_balance(&self, asset: TreasuryAsset) -> U128 {
+        U128::from(self.internal_get_treasury_balance(&asset))
+    }
+
+    pub fn get_treasury_tokens(&self) -> Vec<AccountId> {
+        self.treasury_tokens.to_vec()
+    }
+
+    pub fn get_treasury_payout(&self, proposal_id: ProposalId) -> Option<TreasuryPayout> {
+        self.treasury_payouts.get(&proposal_id)
+    }
+
+    pub fn get_spending_limits(&self) -> Vec<(TreasuryAsset, SpendingLimit)> {
+        self.spending_limits.to_vec()
+    }
+
+    pub fn get_stream(&self, stream_id: StreamId) -> Option<PaymentStream> {
+        self.treasury_streams.get(stream_id as u64)
+    }
+
+    pub fn get_streams(&self, from_index: u32, limit: u32) -> Vec<PaymentStream> {
+        self.treasury_streams
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+
+    /// Amount of the stream that the receiver can withdraw now.
+    pub fn get_stream_withdrawable(&self, stream_id: StreamId) -> U128 {
+        let stream = self
+            .treasury_streams
+            .get(stream_id as u64)
+            .expect("Stream does not exist.");
+        U128::from(stream.vested(get_current_epoch_millis()) - stream.withdrawn.0)
+    }
+
+    pub fn get_treasury_movements(&self, from_index: u32, limit: u32) -> Vec<TreasuryMovement> {
+        self.treasury_movements
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+}
+
+impl ProposalsContract {
+    /// The asset token is always accepted, other tokens must be whitelisted.
+    pub(crate) fn is_treasury_token(&self, token_id: &AccountId) -> bool {
+        *token_id == self.asset_token_contract_address || self.treasury_tokens.contains(token_id)
+    }
+
+    fn assert_treasury_asset(&self, asset: &TreasuryAsset) {
+        if let TreasuryAsset::FungibleToken(token_id) = asset {
+            require!(self.is_treasury_token(token_id), "Token is not whitelisted.");
+        }
+    }
+
+    pub(crate) fn internal_get_treasury_balance(&self, asset: &TreasuryAsset) -> Balance {
+        self.treasury_balances.get(asset).unwrap_or(0)
+    }
+
+    pub(crate) fn internal_treasury_deposit(
+        &mut self,
+        kind: TreasuryMovementKind,
+        asset: &TreasuryAsset,
+        amount: Balance,
+        account_id: &AccountId,
+        proposal_id: Option<ProposalId>,
+    ) {
+        if amount == 0 {
+            return;
+        }
+        let balance = self.internal_get_treasury_balance(asset) + amount;
+        self.treasury_balances.insert(asset, &balance);
+        self.internal_record_treasury_movement(kind, asset, amount, account_id, proposal_id);
+    }
+
+    pub(crate) fn internal_treasury_withdraw(
+        &mut self,
+        kind: TreasuryMovementKind,
+        asset: &TreasuryAsset,
+        amount: Balance,
+        account_id: &AccountId,
+        proposal_id: Option<ProposalId>,
+    ) {
+        let balance = self.internal_get_treasury_balance(asset);
+        require!(balance >= amount, "Not enough treasury balance.");
+        self.treasury_balances.insert(asset, &(balance - amount));
+        self.internal_record_treasury_movement(kind, asset, amount, account_id, proposal_id);
+    }
+
+    fn internal_record_treasury_movement(
+        &mut self,
+        kind: TreasuryMovementKind,
+        asset: &TreasuryAsset,
+        amount: Balance,
+        account_id: &AccountId,
+        proposal_id: Option<ProposalId>,
+    ) {
+        self.treasury_movements.push(&TreasuryMovement {
+            kind,
+            asset: asset.clone(),
+            amount: U128::from(amount),
+            account_id: account_id.clone(),
+            proposal_id,
+            timestamp: get_current_epoch_millis(),
+        });
+    }
+
+    /// Release the payout of an executed proposal.
+    pub(crate) fn internal_execute_treasury_payout(&mut self, proposal_id: ProposalId) {
+        let payout = match self.treasury_payouts.remove(&proposal_id) {
+            Some(payout) => payout,
+            None => return,
+        };
+        match payout {
+            TreasuryPayout::Transfer {
+                receiver_id,
+                asset,
+                amount,
+            } => {
+                self.internal_treasury_withdraw(
+                    TreasuryMovementKind::ProposalPayout,
+                    &asset,
+                    amount.0,
+                    &receiver_id,
+                    Some(proposal_id),
+                );
+                self.internal_transfer_treasury_asset(&asset, receiver_id, amount.0, None);
+            }
+            TreasuryPayout::Stream {
+                receiver_id,
+                asset,
+                amount,
+                start_timestamp,
+                end_timestamp,
+            } => {
+                self.internal_treasury_withdraw(
+                    TreasuryMovementKind::StreamReserved,
+                    &asset,
+                    amount.0,
+                    &receiver_id,
+                    Some(proposal_id),
+                );
+                self.treasury_streams.push(&PaymentStream {
+                    proposal_id,
+                    receiver_id,
+                    asset,
+                    amount,
+                    withdrawn: U128::from(0),
+                    start_timestamp,
+                    end_timestamp,
+                });
+            }
+        }
+    }
+
+    fn internal_transfer_treasury_asset(
+        &mut self,
+        asset: &TreasuryAsset,
+        receiver_id: AccountId,
+        amount: Balance,
+        stream_id: Option<StreamId>,
+    ) -> Promise {
+        let transfer = match asset {
+            TreasuryAsset::Near => Promise::new(receiver_id.clone()).transfer(amount),
+            TreasuryAsset::FungibleToken(token_id) => ext_ft::ext(token_id.clone())
+                .with_static_gas(GAS_FOR_FT_TRANSFER)
+                .with_attached_deposit(1)
+                .ft_transfer(receiver_id.clone(), U128::from(amount), None),
+        };
+        transfer.then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
+                .after_treasury_transfer_callback(
+                    asset.clone(),
+                    receiver_id,
+                    U128::from(amount),
+                    stream_id,
+                ),
+        )
+    }
+}
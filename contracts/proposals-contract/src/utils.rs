@@ -6,6 +6,40 @@ pub fn get_current_epoch_millis() -> EpochMillis {
     env::block_timestamp() / 1_000_000
 }
 
+#[inline]
+/// returns amount * numerator/denominator
+pub fn proportional(amount: u128, numerator: u128, denominator: u128) -> u128 {
+    (U256::from(amount) * U256::from(numerator) / U256::from(denominator)).as_u128()
+}
+
 pub fn generate_hash_id(id: String) -> CryptoHash {
     env::keccak256_array(id.as_bytes())
 }
+
+/// Verify an ed25519 signature with the host function. near-sdk 4.1 does not expose
+/// an `env::ed25519_verify`, so the host function is called through `near_sys`.
+/// Once near-sdk is upgraded, both versions can be replaced by `env::ed25519_verify`.
+#[cfg(target_arch = "wasm32")]
+pub fn ed25519_verify(signature: &[u8; 64], message: &[u8], public_key: &[u8; 32]) -> bool {
+    unsafe {
+        near_sys::ed25519_verify(
+            signature.len() as u64,
+            signature.as_ptr() as u64,
+            message.len() as u64,
+            message.as_ptr() as u64,
+            public_key.len() as u64,
+            public_key.as_ptr() as u64,
+        ) == 1
+    }
+}
+
+/// Off-chain (unit tests) the mocked blockchain of near-sdk 4.1 has no ed25519 host
+/// function, the same signature is verified in Rust with `ed25519-dalek`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn ed25519_verify(signature: &[u8; 64], message: &[u8], public_key: &[u8; 32]) -> bool {
+    use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+    match VerifyingKey::from_bytes(public_key) {
+        Ok(key) => key.verify(message, &Signature::from_bytes(signature)).is_ok(),
+        Err(_) => false,
+    }
+}
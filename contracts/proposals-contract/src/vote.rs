@@ -1,4 +1,5 @@
-use crate::types::{ProposalId, VoterId};
+use crate::constants::ONE_HUNDRED;
+use crate::types::{BasisPoints, EpochMillis, ProposalId, VoterId, VotingPower};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::json_types::U128;
 use near_sdk::serde::{Deserialize, Serialize};
@@ -10,6 +11,42 @@ pub enum VoteType {
     Against,
     For,
     Abstain,
+    /// Voting power divided among For, Against and Abstain, in basis points.
+    /// Used by custodial voters that vote on behalf of many users.
+    Split {
+        for_bp: BasisPoints,
+        against_bp: BasisPoints,
+        abstain_bp: BasisPoints,
+    },
+}
+
+impl VoteType {
+    /// Split votes must allocate exactly 100% of the voting power.
+    pub(crate) fn is_valid(&self) -> bool {
+        match self {
+            VoteType::Split { for_bp, against_bp, abstain_bp } => {
+                u32::from(*for_bp) + u32::from(*against_bp) + u32::from(*abstain_bp)
+                    == u32::from(ONE_HUNDRED)
+            }
+            _ => true,
+        }
+    }
+
+    pub(crate) fn assert_valid(&self) {
+        assert!(
+            self.is_valid(),
+            "Split vote must allocate exactly {} basis points",
+            ONE_HUNDRED
+        );
+    }
+}
+
+/// Voting power of a single vote assigned to each bucket.
+#[derive(Debug, Default, PartialEq)]
+pub struct VoteAllocation {
+    pub for_votes: VotingPower,
+    pub against_votes: VotingPower,
+    pub abstain_votes: VotingPower,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -19,7 +56,12 @@ pub struct VoteJson {
     pub voter_id: VoterId,
     pub vote_type: VoteType,
     pub voting_power: U128,
+    /// Voting power of the voter when the vote was cast.
+    pub voter_power_snapshot: U128,
+    /// Revision of the proposal content the vote refers to.
+    pub revision: u32,
     pub memo: String,
+    pub timestamp: EpochMillis,
     // pub already_withdrawn: bool
 }
 
@@ -29,7 +71,11 @@ pub struct Vote {
     pub proposal_id: ProposalId,
     pub vote_type: VoteType,
     pub voting_power: u128,
+    /// The counted voting power could shrink later, the snapshot is kept.
+    pub voter_power_snapshot: u128,
+    pub revision: u32,
     pub memo: String,
+    pub timestamp: EpochMillis,
     // pub already_withdrawn: bool
 }
 
@@ -38,23 +84,52 @@ impl Vote {
         proposal_id: ProposalId,
         _vote_type: VoteType,
         _voting_power: u128,
+        revision: u32,
         _memo: String,
+        timestamp: EpochMillis,
     ) -> Self {
         Vote {
             proposal_id,
             vote_type: _vote_type,
             voting_power: _voting_power,
+            voter_power_snapshot: _voting_power,
+            revision,
             memo: _memo,
+            timestamp,
             // already_withdrawn: false
         }
     }
+    /// Distribute the vote voting power among the For, Against and Abstain buckets.
+    /// For split votes the rounding remainder goes to Abstain, so the buckets
+    /// always add up to the snapshotted voting power.
+    pub(crate) fn allocation(&self) -> VoteAllocation {
+        let power = self.voting_power;
+        match &self.vote_type {
+            VoteType::For => VoteAllocation { for_votes: power, ..Default::default() },
+            VoteType::Against => VoteAllocation { against_votes: power, ..Default::default() },
+            VoteType::Abstain => VoteAllocation { abstain_votes: power, ..Default::default() },
+            VoteType::Split { for_bp, against_bp, .. } => {
+                let for_votes = power * u128::from(*for_bp) / u128::from(ONE_HUNDRED);
+                let against_votes = power * u128::from(*against_bp) / u128::from(ONE_HUNDRED);
+                VoteAllocation {
+                    for_votes,
+                    against_votes,
+                    abstain_votes: power - for_votes - against_votes,
+                }
+            }
+        }
+    }
+
     pub(crate) fn to_json(&self, voter_id: VoterId) -> VoteJson {
         VoteJson {
             proposal_id: self.proposal_id.clone(),
             voter_id: voter_id.clone(),
             vote_type: self.vote_type.clone(),
             voting_power: U128::from(self.voting_power),
+            voter_power_snapshot: U128::from(self.voter_power_snapshot),
+            revision: self.revision,
             memo: self.memo.clone(),
+            timestamp: self.timestamp,
             // already_withdrawn: self.already_withdrawn.clone()
         }
     }
@@ -2,6 +2,7 @@ use crate::*;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::serde::{Deserialize, Serialize};
 use crate::utils::generate_hash_id;
+use near_sdk::CryptoHash;
 // /////////////////
 // Comment struct //
 // /////////////////
@@ -12,7 +13,8 @@ pub struct ProposalVoteJson {
     pub for_votes: U128,
     pub against_votes: U128,
     pub abstain_votes: U128,
-    pub has_voted: Vec<VoteJson>
+    /// Use get_proposal_voters to page through the votes.
+    pub voters_count: u64,
 }
 
 #[derive(BorshDeserialize, BorshSerialize)]
@@ -21,6 +23,9 @@ pub struct ProposalVote {
     pub against_votes: u128,
     pub abstain_votes: u128,
     pub has_voted: UnorderedMap<AccountId, Vote>,
+    /// XOR of the sha256 of every `(voter_id, Vote)` counted, kept up to date
+    /// by `add_vote` and `remove_vote` so the digest never loads all the votes.
+    pub votes_digest: CryptoHash,
 }
 
 impl ProposalVote {
@@ -32,23 +37,66 @@ impl ProposalVote {
             has_voted: UnorderedMap::new(StorageKey::HasVoted {
                 hash_id: generate_hash_id(id.to_string())
             }),
+            votes_digest: [0; 32],
         }
     }
 
-    pub(crate) fn to_json(&self) -> ProposalVoteJson {
-        let mut votes = Vec::<VoteJson>::new();
-        for (account_id, vote) in self.has_voted.iter() {
-            votes.push(
-                vote.to_json(account_id)
-            );
-        }
+    /// Add the vote voting power to the proposal tally.
+    pub(crate) fn add_vote(&mut self, voter_id: &AccountId, vote: &Vote) {
+        self.toggle_votes_digest(voter_id, vote);
+        let allocation = vote.allocation();
+        self.for_votes += allocation.for_votes;
+        self.against_votes += allocation.against_votes;
+        self.abstain_votes += allocation.abstain_votes;
+    }
 
+    /// Remove the vote voting power from the proposal tally.
+    pub(crate) fn remove_vote(&mut self, voter_id: &AccountId, vote: &Vote) {
+        self.toggle_votes_digest(voter_id, vote);
+        let allocation = vote.allocation();
+        self.for_votes -= allocation.for_votes;
+        self.against_votes -= allocation.against_votes;
+        self.abstain_votes -= allocation.abstain_votes;
+    }
+
+    pub(crate) fn to_json(&self) -> ProposalVoteJson {
         ProposalVoteJson {
             for_votes: U128::from(self.for_votes),
             abstain_votes: U128::from(self.abstain_votes),
             against_votes: U128::from(self.against_votes),
-            has_voted: votes
+            voters_count: self.has_voted.len(),
+        }
+    }
+
+    pub(crate) fn get_voters(&self, from_index: u32, limit: u32) -> Vec<VoteJson> {
+        let keys = self.has_voted.keys_as_vector();
+        let values = self.has_voted.values_as_vector();
+        let start = from_index as u64;
+        let end = std::cmp::min(start + limit as u64, keys.len());
+        (start..end)
+            .map(|index| values.get(index).unwrap().to_json(keys.get(index).unwrap()))
+            .collect()
+    }
 
+    /// XOR is its own inverse, the same call adds or removes the vote.
+    fn toggle_votes_digest(&mut self, voter_id: &AccountId, vote: &Vote) {
+        let vote_hash = env::sha256_array(&(voter_id, vote).try_to_vec().unwrap());
+        for (byte, vote_byte) in self.votes_digest.iter_mut().zip(vote_hash) {
+            *byte ^= vote_byte;
         }
     }
+
+    /// sha256 of the borsh serialization of
+    /// `(proposal_id, for_votes, against_votes, abstain_votes, votes_digest)`.
+    /// The votes digest does not depend on the order of the votes.
+    pub(crate) fn result_digest(&self, proposal_id: ProposalId) -> CryptoHash {
+        let data = (
+            proposal_id,
+            self.for_votes,
+            self.against_votes,
+            self.abstain_votes,
+            self.votes_digest,
+        );
+        env::sha256_array(&data.try_to_vec().unwrap())
+    }
 }
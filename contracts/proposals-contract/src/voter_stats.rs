@@ -0,0 +1,172 @@
+use crate::*;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Participation record of an account. Unlike `Voter`, it is never deleted.
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct VoterStats {
+    pub proposals_voted: u32,
+    /// Consecutive voting rounds voted. It grows when the account votes the
+    /// round right after the last one it voted, otherwise it restarts at one.
+    /// Votes on older rounds leave it unchanged.
+    pub current_streak: u32,
+    pub best_streak: u32,
+    pub first_vote_round: Option<u64>,
+    pub last_vote_round: Option<u64>,
+    pub first_vote_timestamp: Option<EpochMillis>,
+    pub last_vote_timestamp: Option<EpochMillis>,
+    pub proposals_created: u32,
+    pub proposals_accepted: u32,
+    pub proposals_rejected: u32,
+    pub proposals_canceled: u32,
+    pub proposals_executed: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoterStatsJSON {
+    pub voter_id: AccountId,
+    pub proposals_voted: u32,
+    pub current_streak: u32,
+    pub best_streak: u32,
+    pub first_vote_timestamp: Option<EpochMillis>,
+    pub last_vote_timestamp: Option<EpochMillis>,
+    /// Voting rounds voted over the rounds since the first vote, in basis points.
+    pub participation_bp: BasisPoints,
+    pub proposals_created: u32,
+    pub proposals_accepted: u32,
+    pub proposals_rejected: u32,
+    pub proposals_canceled: u32,
+    pub proposals_executed: u32,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LeaderboardEntry {
+    pub voter_id: AccountId,
+    pub proposals_voted: u32,
+    pub best_streak: u32,
+}
+
+impl VoterStats {
+    pub(crate) fn to_json(&self, voter_id: AccountId, voting_rounds: u64) -> VoterStatsJSON {
+        let participation_bp = match self.first_vote_round {
+            Some(first) if voting_rounds > first => std::cmp::min(
+                u64::from(self.proposals_voted) * u64::from(ONE_HUNDRED) / (voting_rounds - first),
+                u64::from(ONE_HUNDRED),
+            ) as BasisPoints,
+            _ => 0,
+        };
+        VoterStatsJSON {
+            voter_id,
+            proposals_voted: self.proposals_voted,
+            current_streak: self.current_streak,
+            best_streak: self.best_streak,
+            first_vote_timestamp: self.first_vote_timestamp,
+            last_vote_timestamp: self.last_vote_timestamp,
+            participation_bp,
+            proposals_created: self.proposals_created,
+            proposals_accepted: self.proposals_accepted,
+            proposals_rejected: self.proposals_rejected,
+            proposals_canceled: self.proposals_canceled,
+            proposals_executed: self.proposals_executed,
+        }
+    }
+
+    fn record_vote(&mut self, round: u64, timestamp: EpochMillis) {
+        self.proposals_voted += 1;
+        match self.last_vote_round {
+            Some(last) if round == last + 1 => self.current_streak += 1,
+            Some(last) if round <= last => {}
+            _ => self.current_streak = 1,
+        }
+        self.best_streak = std::cmp::max(self.best_streak, self.current_streak);
+        self.last_vote_round = std::cmp::max(self.last_vote_round, Some(round));
+        if self.first_vote_round.is_none() {
+            self.first_vote_round = Some(round);
+            self.first_vote_timestamp = Some(timestamp);
+        }
+        self.last_vote_timestamp = Some(timestamp);
+    }
+}
+
+#[near_bindgen]
+impl ProposalsContract {
+    pub fn get_voter_stats(&self, voter_id: VoterId) -> Option<VoterStatsJSON> {
+        self.voter_stats
+            .get(&voter_id)
+            .map(|stats| stats.to_json(voter_id, self.voting_rounds))
+    }
+
+    /// Accounts with most proposals voted, best streak as tie breaker.
+    pub fn get_voter_leaderboard(&self, limit: u32) -> Vec<LeaderboardEntry> {
+        self.leaderboard
+            .iter()
+            .take(limit as usize)
+            .cloned()
+            .collect()
+    }
+}
+
+impl ProposalsContract {
+    fn internal_get_voter_stats(&self, account_id: &AccountId) -> VoterStats {
+        self.voter_stats.get(account_id).unwrap_or_default()
+    }
+
+    pub(crate) fn internal_record_vote_stats(&mut self, voter_id: &VoterId, proposal: &Proposal) {
+        let mut stats = self.internal_get_voter_stats(voter_id);
+        if let Some(round) = proposal.voting_round {
+            stats.record_vote(round, get_current_epoch_millis());
+        }
+        self.internal_update_leaderboard(voter_id, &stats);
+        self.voter_stats.insert(voter_id, &stats);
+    }
+
+    /// A removed vote is not counted, the streak is kept.
+    pub(crate) fn internal_record_removed_vote_stats(&mut self, voter_id: &VoterId) {
+        let mut stats = self.internal_get_voter_stats(voter_id);
+        stats.proposals_voted = stats.proposals_voted.saturating_sub(1);
+        self.internal_update_leaderboard(voter_id, &stats);
+        self.voter_stats.insert(voter_id, &stats);
+    }
+
+    pub(crate) fn internal_record_proposal_created_stats(&mut self, creator_id: &AccountId) {
+        let mut stats = self.internal_get_voter_stats(creator_id);
+        stats.proposals_created += 1;
+        self.voter_stats.insert(creator_id, &stats);
+    }
+
+    pub(crate) fn internal_record_proposal_outcome_stats(
+        &mut self,
+        creator_id: &AccountId,
+        state: ProposalState,
+    ) {
+        let mut stats = self.internal_get_voter_stats(creator_id);
+        match state {
+            ProposalState::Accepted => stats.proposals_accepted += 1,
+            ProposalState::Rejected => stats.proposals_rejected += 1,
+            ProposalState::Canceled => stats.proposals_canceled += 1,
+            ProposalState::Executed => stats.proposals_executed += 1,
+            _ => return,
+        }
+        self.voter_stats.insert(creator_id, &stats);
+    }
+
+    /// Keep the top `LEADERBOARD_SIZE` voters sorted.
+    fn internal_update_leaderboard(&mut self, voter_id: &VoterId, stats: &VoterStats) {
+        self.leaderboard.retain(|entry| entry.voter_id != *voter_id);
+        if stats.proposals_voted > 0 {
+            self.leaderboard.push(LeaderboardEntry {
+                voter_id: voter_id.clone(),
+                proposals_voted: stats.proposals_voted,
+                best_streak: stats.best_streak,
+            });
+        }
+        self.leaderboard.sort_by(|a, b| {
+            b.proposals_voted
+                .cmp(&a.proposals_voted)
+                .then(b.best_streak.cmp(&a.best_streak))
+        });
+        self.leaderboard.truncate(LEADERBOARD_SIZE);
+    }
+}
@@ -17,6 +17,6 @@ PROPOSALS_CONTRACT_ADDRESS="v010.meta-proposals.testnet"
 # NEAR_ENV=testnet near view $PROPOSALS_CONTRACT_ADDRESS get_staking_position_contract_address '{}'
 # NEAR_ENV=testnet near call $PROPOSALS_CONTRACT_ADDRESS insert_operator_role '{"account":"alpha-centauri.testnet"}' --accountId $NEAR_ACCOUNT --gas $TOTAL_PREPAID_GAS
 # NEAR_ENV=testnet near view $PROPOSALS_CONTRACT_ADDRESS get_operators '{}'
-NEAR_ENV=testnet near call $PROPOSALS_CONTRACT_ADDRESS create_proposal '{"title":"First Proposal","short_description":"New Proposal in last deployed contract", "body": "Lorem ipsum dolor sit amet, consectetur adipiscing elit. Integer tincidunt mi sollicitudin, auctor lectus eu, commodo elit. Quisque in tempor arcu. Lorem ipsum dolor sit amet, consectetur adipiscing elit. Integer tincidunt mi sollicitudin, auctor lectus eu, commodo elit. Quisque in tempor arcu.", "data": "", "extra": ""}' --accountId $NEAR_ACCOUNT --gas $TOTAL_PREPAID_GAS
+NEAR_ENV=testnet near call $PROPOSALS_CONTRACT_ADDRESS create_proposal '{"content": {"title":"First Proposal","short_description":"New Proposal in last deployed contract", "body": "Lorem ipsum dolor sit amet, consectetur adipiscing elit. Integer tincidunt mi sollicitudin, auctor lectus eu, commodo elit. Quisque in tempor arcu. Lorem ipsum dolor sit amet, consectetur adipiscing elit. Integer tincidunt mi sollicitudin, auctor lectus eu, commodo elit. Quisque in tempor arcu.", "data": "", "extra": ""}}' --accountId $NEAR_ACCOUNT --gas $TOTAL_PREPAID_GAS
 
 # insert_operator_role(&mut self, account: AccountId)
\ No newline at end of file
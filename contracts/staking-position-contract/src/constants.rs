@@ -7,3 +7,4 @@ pub const TGAS: u64 = 1_000_000_000_000;
 /// Amount of gas for fungible token transfers.
 pub const GAS_FOR_FT_TRANSFER: Gas = Gas(47 * TGAS);
 pub const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(11 * TGAS);
+pub const GAS_FOR_VOTING_POWER_NOTIFICATION: Gas = Gas(15 * TGAS);
@@ -1,4 +1,6 @@
 use crate::*;
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::json;
 
 impl StakingPositionContract {
     pub(crate) fn assert_only_owner(&self) {
@@ -16,6 +18,33 @@ impl StakingPositionContract {
         );
     }
 
+    /// Governance can block unlocking while the staker has votes on active proposals.
+    pub(crate) fn assert_can_unlock(&self, staker_id: &AccountId) {
+        if let Some(locked_until) = self.vote_locks.get(staker_id) {
+            assert!(
+                get_current_epoch_millis() > locked_until,
+                "Cannot unlock while voting on active proposals, wait until {}.",
+                locked_until
+            );
+        }
+    }
+
+    /// Let the governance contract reconcile the votes of the staker.
+    pub(crate) fn internal_notify_voting_power_decreased(&self, staker: &Staker) {
+        if let Some(governance) = &self.governance_contract_address {
+            Promise::new(governance.clone())
+                .function_call(
+                    String::from("on_locked_voting_power_decreased"),
+                    json!({
+                        "voter_id": staker.id,
+                        "locked_voting_power": U128::from(staker.sum_locked_voting_power()),
+                    }).to_string().into_bytes(),
+                    0,
+                    GAS_FOR_VOTING_POWER_NOTIFICATION
+                );
+        }
+    }
+
     /// Inner method to get or create a Voter.
     pub(crate) fn internal_get_staker(&self, account_id: AccountId) -> Staker {
         self.stakers.get(&account_id).unwrap_or(Staker::new(&account_id))
@@ -43,6 +43,11 @@ pub struct StakingPositionContract {
 
     /// Stakers can claim any FT token. Key is the ft address.
     pub claimable_ft: UnorderedMap<AccountId, FtDetails>,
+
+    /// Governance (proposals) contract notified when the locked voting power decreases.
+    pub governance_contract_address: Option<AccountId>,
+    /// Unlocking is blocked until the end of the proposals voted by the staker.
+    pub vote_locks: UnorderedMap<AccountId, EpochMillis>,
 }
 
 #[near_bindgen]
@@ -78,6 +83,8 @@ impl StakingPositionContract {
             accum_near_distributed_for_claims: 0,
             total_unclaimed_near: 0,
             claimable_ft: UnorderedMap::new(StorageKey::ClaimableFt),
+            governance_contract_address: None,
+            vote_locks: UnorderedMap::new(StorageKey::VoteLocks),
         };
         for token_address in available_claimable_ft_addresses.iter() {
             contract.insert_new_ft(token_address);
@@ -122,6 +129,49 @@ impl StakingPositionContract {
         self.min_deposit_amount = new_value.0;
     }
 
+    #[payable]
+    pub fn update_governance_contract_address(&mut self, new_value: Option<AccountId>) {
+        assert_one_yocto();
+        self.assert_only_owner();
+        self.governance_contract_address = new_value;
+    }
+
+    // **************
+    // * Governance *
+    // **************
+
+    /// Called by the governance contract when a staker votes, to block unlocking
+    /// until the end of the voting period. Returns the locking positions of the
+    /// staker, the governance contract counts the vote with them.
+    pub fn lock_unlocking_until(
+        &mut self,
+        staker_id: AccountId,
+        until: EpochMillis
+    ) -> Vec<LockingPositionJSON> {
+        require!(
+            self.governance_contract_address.as_ref() == Some(&env::predecessor_account_id()),
+            "Only the governance contract can call this function."
+        );
+        let locked_until = self.vote_locks.get(&staker_id).unwrap_or(0);
+        if until > locked_until {
+            self.vote_locks.insert(&staker_id, &until);
+        }
+        self.get_all_locking_positions(staker_id)
+    }
+
+    /// Called by the governance contract when a staker removes a vote, with the end
+    /// of the proposals still voted, or `None` to release the lock.
+    pub fn reset_unlocking_lock(&mut self, staker_id: AccountId, until: Option<EpochMillis>) {
+        require!(
+            self.governance_contract_address.as_ref() == Some(&env::predecessor_account_id()),
+            "Only the governance contract can call this function."
+        );
+        match until {
+            Some(until) => self.vote_locks.insert(&staker_id, &until),
+            None => self.vote_locks.remove(&staker_id),
+        };
+    }
+
     // *********
     // * claim *
     // *********
@@ -169,6 +219,7 @@ impl StakingPositionContract {
 
     pub fn unlock_position(&mut self, index: PositionIndex) {
         let mut staker = self.internal_get_staker_or_panic();
+        self.assert_can_unlock(&staker.id);
         let mut locking_position = staker.get_position(index);
 
         let voting_power = locking_position.voting_power;
@@ -185,11 +236,13 @@ impl StakingPositionContract {
         staker.voting_power -= voting_power;
         self.total_voting_power = self.total_voting_power.saturating_sub(voting_power);
         self.stakers.insert(&staker.id, &staker);
+        self.internal_notify_voting_power_decreased(&staker);
     }
 
     /// @param amount - The amount to unlock.
     pub fn unlock_partial_position(&mut self, index: PositionIndex, amount: U128) {
         let mut staker = self.internal_get_staker_or_panic();
+        self.assert_can_unlock(&staker.id);
         let mut locking_position = staker.get_position(index);
 
         let locking_period = locking_position.locking_period;
@@ -231,6 +284,7 @@ impl StakingPositionContract {
         staker.voting_power -= remove_voting_power;
         self.total_voting_power = self.total_voting_power.saturating_sub(remove_voting_power);
         self.stakers.insert(&staker.id, &staker);
+        self.internal_notify_voting_power_decreased(&staker);
     }
 
     // ********************************
@@ -666,6 +720,14 @@ impl StakingPositionContract {
         self.stakers.len().try_into().unwrap()
     }
 
+    pub fn get_vote_lock(&self, account_id: AccountId) -> Option<EpochMillis> {
+        self.vote_locks.get(&account_id)
+    }
+
+    pub fn get_governance_contract_address(&self) -> Option<AccountId> {
+        self.governance_contract_address.clone()
+    }
+
     pub fn get_total_voting_power(&self) -> U128 {
         U128::from(self.total_voting_power)
     }
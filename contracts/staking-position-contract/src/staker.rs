@@ -52,6 +52,17 @@ impl Staker {
         result
     }
 
+    /// Returns the voting power of the locked positions.
+    pub(crate) fn sum_locked_voting_power(&self) -> VotingPower {
+        let mut result = 0_u128;
+        for locking_position in self.locking_positions.iter() {
+            if locking_position.is_locked() {
+                result += locking_position.voting_power;
+            }
+        }
+        result
+    }
+
     /// Returns the total amount of AAXXII tokens that are under the unlocking process.
     pub(crate) fn sum_unlocking(&self) -> Balance {
         let mut result = 0_u128;
@@ -847,6 +847,95 @@ fn test_unlock_position_without_voting_power() {
     contract.unlock_position(index);
 }
 
+#[test]
+#[should_panic(expected = "Cannot unlock while voting on active proposals")]
+fn test_unlock_position_blocked_by_governance() {
+    const LOCKING_PERIOD: u16 = 100;
+    const AMOUNT: Balance = 10 * E24;
+    let (mut contract, sender_id) = generate_lock_position_context(LOCKING_PERIOD, AMOUNT);
+    contract.governance_contract_address = Some(governance_account());
+
+    let timestamp_1 = to_ts(GENESIS_TIME_IN_DAYS + 5);
+    let context = get_context(&governance_account(), ntoy(TEST_INITIAL_BALANCE), 0, timestamp_1);
+    testing_env!(context.clone());
+    let locking_positions =
+        contract.lock_unlocking_until(sender_id.clone(), timestamp_1 / 1_000_000 + 1_000);
+    assert_eq!(
+        Some(timestamp_1 / 1_000_000 + 1_000),
+        contract.get_vote_lock(sender_id.clone()),
+        "Incorrect vote lock!"
+    );
+    assert_eq!(locking_positions.len(), 1, "Incorrect locking positions!");
+    assert_eq!(locking_positions[0].amount, U128::from(AMOUNT), "Incorrect locking positions!");
+
+    // New context: the voter is doing the call now!
+    let context = get_context(&sender_id, ntoy(TEST_INITIAL_BALANCE), 0, timestamp_1);
+    testing_env!(context.clone());
+    contract.unlock_position(0);
+}
+
+#[test]
+fn test_unlock_position_after_governance_lock() {
+    const LOCKING_PERIOD: u16 = 100;
+    const AMOUNT: Balance = 10 * E24;
+    let (mut contract, sender_id) = generate_lock_position_context(LOCKING_PERIOD, AMOUNT);
+    contract.governance_contract_address = Some(governance_account());
+
+    let timestamp_1 = to_ts(GENESIS_TIME_IN_DAYS + 5);
+    let context = get_context(&governance_account(), ntoy(TEST_INITIAL_BALANCE), 0, timestamp_1);
+    testing_env!(context.clone());
+    contract.lock_unlocking_until(sender_id.clone(), timestamp_1 / 1_000_000 + 1_000);
+
+    // The voting period is over.
+    let timestamp_2 = to_ts(GENESIS_TIME_IN_DAYS + 6);
+    let context = get_context(&sender_id, ntoy(TEST_INITIAL_BALANCE), 0, timestamp_2);
+    testing_env!(context.clone());
+    contract.unlock_position(0);
+    assert_eq!(
+        U128::from(0),
+        contract.get_locked_balance(sender_id.clone()),
+        "Incorrect locked balance!"
+    );
+}
+
+#[test]
+fn test_reset_unlocking_lock() {
+    const LOCKING_PERIOD: u16 = 100;
+    const AMOUNT: Balance = 10 * E24;
+    let (mut contract, sender_id) = generate_lock_position_context(LOCKING_PERIOD, AMOUNT);
+    contract.governance_contract_address = Some(governance_account());
+
+    let timestamp_1 = to_ts(GENESIS_TIME_IN_DAYS + 5);
+    let context = get_context(&governance_account(), ntoy(TEST_INITIAL_BALANCE), 0, timestamp_1);
+    testing_env!(context.clone());
+    contract.lock_unlocking_until(sender_id.clone(), timestamp_1 / 1_000_000 + 2_000);
+
+    // The vote on the longest proposal is removed.
+    contract.reset_unlocking_lock(sender_id.clone(), Some(timestamp_1 / 1_000_000 + 1_000));
+    assert_eq!(
+        Some(timestamp_1 / 1_000_000 + 1_000),
+        contract.get_vote_lock(sender_id.clone()),
+        "Incorrect vote lock!"
+    );
+
+    // No more votes on active proposals.
+    contract.reset_unlocking_lock(sender_id.clone(), None);
+    assert_eq!(None, contract.get_vote_lock(sender_id.clone()), "Incorrect vote lock!");
+
+    // New context: the voter is doing the call now!
+    let context = get_context(&sender_id, ntoy(TEST_INITIAL_BALANCE), 0, timestamp_1);
+    testing_env!(context.clone());
+    contract.unlock_position(0);
+}
+
+#[test]
+#[should_panic(expected = "Only the governance contract can call this function.")]
+fn test_lock_unlocking_only_governance() {
+    let mut contract = setup_new_test();
+    contract.governance_contract_address = Some(governance_account());
+    contract.lock_unlocking_until(voter_account(), 1_000);
+}
+
 #[test]
 fn test_rebalance_increase_and_decrease() {
     const LOCKING_PERIOD: u16 = 100;
@@ -48,6 +48,10 @@ pub fn operator_account() -> AccountId {
     AccountId::new_unchecked("operator.staking.near".to_string())
 }
 
+pub fn governance_account() -> AccountId {
+    AccountId::new_unchecked("governance.staking.near".to_string())
+}
+
 pub fn underlying_token_account() -> AccountId {
     AccountId::new_unchecked("underlying-token.staking.near".to_string())
 }
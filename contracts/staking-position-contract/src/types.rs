@@ -50,6 +50,7 @@ pub enum StorageKey {
 
     ClaimableNear,
     ClaimableFt,
+    VoteLocks,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -92,6 +92,7 @@ pub async fn main() -> anyhow::Result<()> {
         true,
         &now,
         &owner,
+        &treasury,
         &katherine_contract,
         &sold_token_contract
     ).await?;
@@ -126,6 +127,7 @@ pub async fn main() -> anyhow::Result<()> {
         false,
         &now,
         &owner,
+        &treasury,
         &katherine_contract,
         &sold_token_contract
     ).await?;
@@ -717,6 +719,7 @@ async fn create_sale(
     is_in_near: bool,
     now: &Now,
     owner: &Account,
+    treasury: &Account,
     katherine_contract: &Contract,
     sold_token_contract: &Contract,
 ) -> anyhow::Result<ExecutionFinalResult> {
@@ -738,6 +741,9 @@ async fn create_sale(
             "open_date_timestamp": open_date_timestamp,
             "close_date_timestamp": close_date_timestamp,
             "release_date_timestamp": release_date_timestamp,
+            "options": {
+                "payout_id": treasury.id(),
+            },
         }))
         .deposit(NearToken::from_yoctonear(STORAGE_PER_SALE-1))
         .gas(NearGas::from_tgas(300))
@@ -756,6 +762,9 @@ async fn create_sale(
             "open_date_timestamp": open_date_timestamp,
             "close_date_timestamp": close_date_timestamp,
             "release_date_timestamp": release_date_timestamp,
+            "options": {
+                "payout_id": treasury.id(),
+            },
         }))
         .deposit(NearToken::from_yoctonear(STORAGE_PER_SALE))
         .gas(NearGas::from_tgas(300))